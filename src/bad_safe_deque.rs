@@ -1,5 +1,10 @@
-use std::cell::{Ref, RefCell, RefMut};
-use std::rc::Rc;
+use std::cell::{BorrowError, Ref, RefCell, RefMut};
+use std::fmt;
+use std::marker::PhantomData;
+use std::rc::{Rc, Weak};
+
+#[cfg(any(test, feature = "diagnostics"))]
+use crate::diagnostics::{ListKind, Live, StuckNode};
 
 pub struct List<T> {
     head: Link<T>,
@@ -12,10 +17,17 @@ pub struct List<T> {
 // This is getting pretty complicated compared to just C++ pointers
 type Link<T> = Option<Rc<RefCell<Node<T>>>>;
 
+// Strong next and strong prev would make every pair of neighbours a cycle, which Rc can't free on its own
+// Only next owns, prev just points back, so the list is a plain chain from the head
+// Dropping the head frees everything, whether our Drop got to run to the end or not
+type WeakLink<T> = Option<Weak<RefCell<Node<T>>>>;
+
 struct Node<T> {
     elem: T,
     next: Link<T>,
-    prev: Link<T>,
+    prev: WeakLink<T>,
+    #[cfg(any(test, feature = "diagnostics"))]
+    _live: Live,
 }
 
 impl<T> Node<T> {
@@ -24,6 +36,8 @@ impl<T> Node<T> {
             elem,
             prev: None,
             next: None,
+            #[cfg(any(test, feature = "diagnostics"))]
+            _live: Live::new(ListKind::BadSafeDeque),
         }))
     }
 }
@@ -45,7 +59,7 @@ impl<T> List<T> {
                 // Borrow mut tries to take unique mutable reference, so it tries to take the write lock
                 // IT WILL PANIC IF IT FAILS, panic is more or less exception, it starts unwinding
                 // (unless compiled to abort instantly)
-                old_head.borrow_mut().prev = Some(new_head.clone());
+                old_head.borrow_mut().prev = Some(Rc::downgrade(&new_head));
                 new_head.borrow_mut().next = Some(old_head);
                 self.head = Some(new_head);
             }
@@ -57,6 +71,11 @@ impl<T> List<T> {
         }
     }
 
+    // Panics if the front node, or the one after it, is borrowed,
+    // or if anything other than the list holds an Rc to the front node
+    // None of that can happen through this API, every Ref, RefMut, iterator and Cursor borrows the list,
+    // and this takes &mut self, so the borrow checker rules it out before we ever get here
+    // try_pop_front checks all of it anyway, and says what went wrong instead of panicking
     pub fn pop_front(&mut self) -> Option<T> {
         self.head.take().map(|old_head| {
             // What state was the head in?
@@ -82,7 +101,9 @@ impl<T> List<T> {
         })
     }
 
-    pub fn peek_front(&self) -> Option<Ref<T>> {
+    // Panics if the front element is mutably borrowed, only a RefMut from peek_front_mut could do that
+    // and that one needs &mut self, so it can't be alive now
+    pub fn peek_front(&self) -> Option<Ref<'_, T>> {
         // Originall code didn't work?
         // instead of node.borrow, RefCell::borrow(node) is needed
         // Anyway, we map the Ref returned by RefCell, so instead of pointing at the entire Node
@@ -104,7 +125,7 @@ impl<T> List<T> {
         match self.tail.take() {
             Some(old_tail) => {
                 old_tail.borrow_mut().next = Some(new_tail.clone());
-                new_tail.borrow_mut().prev = Some(old_tail);
+                new_tail.borrow_mut().prev = Some(Rc::downgrade(&old_tail));
                 self.tail = Some(new_tail);
             }
             None => {
@@ -114,9 +135,15 @@ impl<T> List<T> {
         }
     }
 
+    // Same panics as pop_front, for the back node and the one before it
     pub fn pop_back(&mut self) -> Option<T> {
         self.tail.take().map(|old_tail| {
-            match old_tail.borrow_mut().prev.take() {
+            match old_tail
+                .borrow_mut()
+                .prev
+                .take()
+                .and_then(|prev| prev.upgrade())
+            {
                 Some(new_tail) => {
                     new_tail.borrow_mut().next.take();
                     self.tail = Some(new_tail);
@@ -129,28 +156,174 @@ impl<T> List<T> {
         })
     }
 
-    pub fn peek_back(&self) -> Option<Ref<T>> {
+    // Same as peek_front, panics only if the back element is mutably borrowed, which peek_back_mut can't be now
+    pub fn peek_back(&self) -> Option<Ref<'_, T>> {
         self.tail
             .as_ref()
             .map(|node| Ref::map(RefCell::borrow(node), |node| &node.elem))
     }
 
-    pub fn peek_back_mut(&mut self) -> Option<RefMut<T>> {
+    // Both peek_*_mut panic if the element is borrowed at all, which &mut self again rules out
+    pub fn peek_back_mut(&mut self) -> Option<RefMut<'_, T>> {
         self.tail
             .as_ref()
             .map(|node| RefMut::map(node.borrow_mut(), |node| &mut node.elem))
     }
 
-    pub fn peek_front_mut(&mut self) -> Option<RefMut<T>> {
+    pub fn peek_front_mut(&mut self) -> Option<RefMut<'_, T>> {
         self.head
             .as_ref()
             .map(|node| RefMut::map(node.borrow_mut(), |node| &mut node.elem))
     }
 }
 
+// Why a try_ method couldn't do its thing
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ListError {
+    // A node we'd have to touch is borrowed, by a Ref or RefMut still alive somewhere
+    Borrowed,
+    // Something other than the list holds an Rc to the node, so we can't move the element out
+    Shared,
+    Empty,
+}
+
+impl fmt::Display for ListError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ListError::Borrowed => f.write_str("list node is already borrowed"),
+            ListError::Shared => f.write_str("list node is shared outside of the list"),
+            ListError::Empty => f.write_str("list is empty"),
+        }
+    }
+}
+
+impl std::error::Error for ListError {}
+
+// The non-panicking versions
+// Everything gets checked before anything changes, so on an error the list is left as it was
+impl<T> List<T> {
+    pub fn try_pop_front(&mut self) -> Result<T, ListError> {
+        let head = self.head.as_ref().ok_or(ListError::Empty)?;
+        // Owned by the list's head, and tail too when it's the only one
+        let owners = if self.is_single() { 2 } else { 1 };
+        Self::check_pop(head, owners)?;
+        if let Some(next) = head
+            .try_borrow()
+            .map_err(|_| ListError::Borrowed)?
+            .next
+            .as_ref()
+        {
+            next.try_borrow_mut().map_err(|_| ListError::Borrowed)?;
+        }
+        Ok(self.pop_front().unwrap())
+    }
+
+    pub fn try_pop_back(&mut self) -> Result<T, ListError> {
+        let tail = self.tail.as_ref().ok_or(ListError::Empty)?;
+        // Owned by the list's tail, and by the node before it or the list's head
+        Self::check_pop(tail, 2)?;
+        let prev = tail
+            .try_borrow()
+            .map_err(|_| ListError::Borrowed)?
+            .prev
+            .clone();
+        if let Some(prev) = prev.and_then(|prev| prev.upgrade()) {
+            prev.try_borrow_mut().map_err(|_| ListError::Borrowed)?;
+        }
+        Ok(self.pop_back().unwrap())
+    }
+
+    pub fn try_peek_front(&self) -> Result<Ref<'_, T>, ListError> {
+        Self::try_elem(&self.head)
+    }
+
+    pub fn try_peek_back(&self) -> Result<Ref<'_, T>, ListError> {
+        Self::try_elem(&self.tail)
+    }
+
+    pub fn try_peek_front_mut(&mut self) -> Result<RefMut<'_, T>, ListError> {
+        Self::try_elem_mut(&self.head)
+    }
+
+    pub fn try_peek_back_mut(&mut self) -> Result<RefMut<'_, T>, ListError> {
+        Self::try_elem_mut(&self.tail)
+    }
+
+    fn is_single(&self) -> bool {
+        match (&self.head, &self.tail) {
+            (Some(head), Some(tail)) => Rc::ptr_eq(head, tail),
+            _ => false,
+        }
+    }
+
+    // Could the node be taken apart, with owners being how many Rcs the list itself has to it
+    fn check_pop(node: &Rc<RefCell<Node<T>>>, owners: usize) -> Result<(), ListError> {
+        node.try_borrow_mut().map_err(|_| ListError::Borrowed)?;
+        if Rc::strong_count(node) > owners {
+            return Err(ListError::Shared);
+        }
+        Ok(())
+    }
+
+    fn try_elem(link: &Link<T>) -> Result<Ref<'_, T>, ListError> {
+        let node = link.as_ref().ok_or(ListError::Empty)?;
+        let node = node.try_borrow().map_err(|_| ListError::Borrowed)?;
+        Ok(Ref::map(node, |node| &node.elem))
+    }
+
+    fn try_elem_mut(link: &Link<T>) -> Result<RefMut<'_, T>, ListError> {
+        let node = link.as_ref().ok_or(ListError::Empty)?;
+        let node = node.try_borrow_mut().map_err(|_| ListError::Borrowed)?;
+        Ok(RefMut::map(node, |node| &mut node.elem))
+    }
+}
+
+impl<T> Default for List<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 impl<T> Drop for List<T> {
     fn drop(&mut self) {
-        while self.pop_front().is_some() {}
+        // Nothing would leak without this anymore, dropping head frees the whole chain
+        // But it would do it recursively, node by node down the stack, so unlink them one at a time instead
+        self.tail.take();
+        let mut cur = self.head.take();
+        while let Some(node) = cur {
+            cur = node.borrow_mut().next.take();
+        }
+    }
+}
+
+// Every node is owned by exactly the link before it, plus the list's tail for the last one
+// Anything more and something else is keeping it alive, like the strong prevs used to
+// Cursors hold strong references too, so check with none of them around
+#[cfg(any(test, feature = "diagnostics"))]
+impl<T> List<T> {
+    pub fn stuck_nodes(&self) -> Vec<StuckNode> {
+        let mut stuck = Vec::new();
+        let mut cur = self.head.clone();
+        let mut index = 0;
+        while let Some(node) = cur {
+            let is_tail = self
+                .tail
+                .as_ref()
+                .is_some_and(|tail| Rc::ptr_eq(tail, &node));
+            let expected = 1 + usize::from(is_tail);
+            // Not counting our own clone
+            let strong_count = Rc::strong_count(&node) - 1;
+            if strong_count > expected {
+                stuck.push(StuckNode {
+                    index,
+                    strong_count,
+                    expected,
+                });
+            }
+            cur = node.borrow().next.clone();
+            index += 1;
+        }
+        stuck
     }
 }
 
@@ -160,8 +333,11 @@ impl<T> Drop for List<T> {
 // So, let's implement the from-end iteration as well!
 pub struct IntoIter<T>(List<T>);
 
-impl<T> List<T> {
-    pub fn into_iter(self) -> IntoIter<T> {
+impl<T> IntoIterator for List<T> {
+    type IntoIter = IntoIter<T>;
+    type Item = T;
+
+    fn into_iter(self) -> Self::IntoIter {
         IntoIter(self)
     }
 }
@@ -190,7 +366,7 @@ impl<T> DoubleEndedIterator for IntoIter<T> {
 // as 2nd node depends on 1st, 3rd on 2nd, 4th on 3rd and so on
 // While in PersistentLinkedList we could easily handle out Rc to everything, and share to our hearts content, but struggled with unique ownership
 // Here we have fairly easy unique ownership, but references are a Lovecraftian Nightmare from which there is no escape
-// We're not even going to try IterMut
+// We're not even going to try IterMut, not like this anyway
 
 // pub struct Iter<'a, T>(Option<Ref<'a, Node<T>>>);
 
@@ -216,9 +392,196 @@ impl<T> DoubleEndedIterator for IntoIter<T> {
 //     }
 // }
 
+// What does work is not borrowing our way down the list at all
+// The list owns every node, and while it's borrowed for 'a nothing can unlink one,
+// &self methods never relink anything and &mut self ones have to wait until 'a is over
+// So every node lives at least for 'a, and we can hold plain &'a RefCell to them, straight through the Rcs
+// Borrowing one of those gives a Ref<'a, T> that needs nothing else to keep it alive
+// That's a promise the compiler can't check for us, so it's the one bit of unsafe in here, in node_ref below
+//
+// The iterators never panic
+// Iter only reads, and with &List nobody can hold a RefMut to any element
+// IterMut hands out one RefMut per node, and nothing else can borrow a node while the list is borrowed mutably
+// Links are read right before a node is handed out, never after, so the RefMuts already out can't get in the way
+
+type NodeRef<'a, T> = Option<&'a RefCell<Node<T>>>;
+
+// SAFETY: the node has to stay alive for 'a, which it does if the list that owns it stays borrowed for 'a
+unsafe fn node_ref<'a, T>(node: *const RefCell<Node<T>>) -> &'a RefCell<Node<T>> {
+    unsafe { &*node }
+}
+
+// Both ends walk towards each other, when they meet on the same node it's the last one
+pub struct Iter<'a, T> {
+    front: NodeRef<'a, T>,
+    back: NodeRef<'a, T>,
+}
+
+pub struct IterMut<'a, T> {
+    front: NodeRef<'a, T>,
+    back: NodeRef<'a, T>,
+    _list: PhantomData<&'a mut List<T>>,
+}
+
+impl<T> List<T> {
+    pub fn iter(&self) -> Iter<'_, T> {
+        Iter {
+            front: self.head.as_deref(),
+            back: self.tail.as_deref(),
+        }
+    }
+
+    pub fn iter_mut(&mut self) -> IterMut<'_, T> {
+        IterMut {
+            front: self.head.as_deref(),
+            back: self.tail.as_deref(),
+            _list: PhantomData,
+        }
+    }
+}
+
+// Shared by Iter and IterMut, step one end inwards and give back the node it was on
+// Only called with the list borrowed for 'a
+fn step_front<'a, T>(front: &mut NodeRef<'a, T>, back: &mut NodeRef<'a, T>) -> NodeRef<'a, T> {
+    let node = front.take()?;
+    if back.is_some_and(|back| std::ptr::eq(back, node)) {
+        back.take();
+    } else {
+        // SAFETY: next is a node of the list, which is borrowed for 'a
+        *front = node
+            .borrow()
+            .next
+            .as_ref()
+            .map(|next| unsafe { node_ref(Rc::as_ptr(next)) });
+    }
+    Some(node)
+}
+
+fn step_back<'a, T>(front: &mut NodeRef<'a, T>, back: &mut NodeRef<'a, T>) -> NodeRef<'a, T> {
+    let node = back.take()?;
+    if front.is_some_and(|front| std::ptr::eq(front, node)) {
+        front.take();
+    } else {
+        // SAFETY: prev is a node of the list, which is borrowed for 'a, so the Weak still has its node
+        *back = node
+            .borrow()
+            .prev
+            .as_ref()
+            .map(|prev| unsafe { node_ref(Weak::as_ptr(prev)) });
+    }
+    Some(node)
+}
+
+impl<'a, T> Iterator for Iter<'a, T> {
+    type Item = Ref<'a, T>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        step_front(&mut self.front, &mut self.back)
+            .map(|node| Ref::map(node.borrow(), |node| &node.elem))
+    }
+}
+
+impl<T> DoubleEndedIterator for Iter<'_, T> {
+    fn next_back(&mut self) -> Option<Self::Item> {
+        step_back(&mut self.front, &mut self.back)
+            .map(|node| Ref::map(node.borrow(), |node| &node.elem))
+    }
+}
+
+impl<'a, T> Iterator for IterMut<'a, T> {
+    type Item = RefMut<'a, T>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        step_front(&mut self.front, &mut self.back)
+            .map(|node| RefMut::map(node.borrow_mut(), |node| &mut node.elem))
+    }
+}
+
+impl<T> DoubleEndedIterator for IterMut<'_, T> {
+    fn next_back(&mut self) -> Option<Self::Item> {
+        step_back(&mut self.front, &mut self.back)
+            .map(|node| RefMut::map(node.borrow_mut(), |node| &mut node.elem))
+    }
+}
+
+// Cursor, same idea, it holds an Rc to the node it's on, None is the ghost between back and front
+// current lends a Ref for as long as the cursor is borrowed
+pub struct Cursor<'a, T> {
+    list: &'a List<T>,
+    cur: Link<T>,
+}
+
+impl<T> List<T> {
+    pub fn cursor_front(&self) -> Cursor<'_, T> {
+        Cursor {
+            list: self,
+            cur: self.head.clone(),
+        }
+    }
+
+    pub fn cursor_back(&self) -> Cursor<'_, T> {
+        Cursor {
+            list: self,
+            cur: self.tail.clone(),
+        }
+    }
+}
+
+impl<T> Cursor<'_, T> {
+    pub fn move_next(&mut self) {
+        self.cur = match self.cur.take() {
+            Some(node) => node.borrow().next.clone(),
+            None => self.list.head.clone(),
+        };
+    }
+
+    pub fn move_prev(&mut self) {
+        self.cur = match self.cur.take() {
+            Some(node) => node.borrow().prev.as_ref().and_then(Weak::upgrade),
+            None => self.list.tail.clone(),
+        };
+    }
+
+    // None on the ghost
+    // Panics if the element is mutably borrowed, which can't happen while the cursor has the list borrowed
+    pub fn current(&self) -> Option<Ref<'_, T>> {
+        self.cur
+            .as_ref()
+            .map(|node| Ref::map(node.borrow(), |node| &node.elem))
+    }
+
+    pub fn try_current(&self) -> Result<Option<Ref<'_, T>>, BorrowError> {
+        self.cur
+            .as_ref()
+            .map(|node| {
+                node.try_borrow()
+                    .map(|node| Ref::map(node, |node| &node.elem))
+            })
+            .transpose()
+    }
+}
+
 #[cfg(test)]
 mod test {
-    use super::List;
+    use super::{List, ListError, Node};
+    use crate::diagnostics::{ListKind, StuckNode, assert_no_live_nodes, live_nodes};
+    use std::cell::{Cell, RefCell, RefMut};
+    use std::rc::{Rc, Weak};
+
+    // Weak refs to every node, to count how many are still alive later
+    fn nodes<T>(list: &List<T>) -> Vec<Weak<RefCell<Node<T>>>> {
+        let mut nodes = Vec::new();
+        let mut cur = list.head.clone();
+        while let Some(node) = cur {
+            nodes.push(Rc::downgrade(&node));
+            cur = node.borrow().next.clone();
+        }
+        nodes
+    }
+
+    fn live<T>(nodes: &[Weak<RefCell<Node<T>>>]) -> usize {
+        nodes.iter().filter(|node| node.strong_count() > 0).count()
+    }
 
     #[test]
     fn basics() {
@@ -307,4 +670,277 @@ mod test {
         assert_eq!(iter.next_back(), None);
         assert_eq!(iter.next(), None);
     }
+
+    #[test]
+    fn iter() {
+        let mut list = List::new();
+        list.push_back(1);
+        list.push_back(2);
+        list.push_back(3);
+
+        let mut iter = list.iter();
+        let first = iter.next().unwrap();
+        assert_eq!(*first, 1);
+        assert_eq!(*iter.next_back().unwrap(), 3);
+        assert_eq!(*iter.next().unwrap(), 2);
+        assert!(iter.next().is_none());
+        assert!(iter.next_back().is_none());
+        // Still fine to look at, even next to peeks
+        assert_eq!(*first, *list.peek_front().unwrap());
+        drop(first);
+
+        let mut sum = 0;
+        for elem in list.iter() {
+            sum += *elem;
+        }
+        assert_eq!(sum, 6);
+        let values: Vec<i32> = list.iter().rev().map(|elem| *elem).collect();
+        assert_eq!(values, &[3, 2, 1]);
+        assert!(List::<i32>::new().iter().next().is_none());
+
+        // Iterators don't hold on to the nodes, popping is fine right after
+        assert_eq!(list.pop_front(), Some(1));
+    }
+
+    #[test]
+    fn iter_mut() {
+        let mut list = List::new();
+        list.push_back(1);
+        list.push_back(2);
+        list.push_back(3);
+
+        for mut elem in list.iter_mut() {
+            *elem *= 10;
+        }
+
+        // Mutable borrows of different elements at the same time
+        let guards: Vec<RefMut<i32>> = list.iter_mut().collect();
+        let [mut a, _, mut c] = guards.try_into().ok().unwrap();
+        std::mem::swap(&mut *a, &mut *c);
+        drop((a, c));
+
+        let mut iter = list.iter_mut();
+        let back = iter.next_back().unwrap();
+        let front = iter.next().unwrap();
+        assert_eq!((*front, *back), (30, 10));
+        assert_eq!(*iter.next().unwrap(), 20);
+        assert!(iter.next_back().is_none());
+        drop((iter, front, back));
+        assert_eq!(list.pop_front(), Some(30));
+    }
+
+    #[test]
+    fn cursor() {
+        let mut list = List::new();
+        list.push_back(1);
+        list.push_back(2);
+        list.push_back(3);
+
+        let mut cursor = list.cursor_front();
+        assert_eq!(*cursor.current().unwrap(), 1);
+        cursor.move_next();
+        cursor.move_next();
+        assert_eq!(*cursor.current().unwrap(), 3);
+        cursor.move_next();
+        assert!(cursor.current().is_none());
+        assert!(cursor.try_current().unwrap().is_none());
+        cursor.move_next();
+        assert_eq!(*cursor.current().unwrap(), 1);
+        cursor.move_prev();
+        cursor.move_prev();
+        assert_eq!(*cursor.try_current().unwrap().unwrap(), 3);
+
+        let mut cursor = list.cursor_back();
+        cursor.move_prev();
+        assert_eq!(*cursor.current().unwrap(), 2);
+        assert!(List::<i32>::new().cursor_front().current().is_none());
+    }
+
+    #[test]
+    fn no_cycles() {
+        let mut list = List::new();
+        for i in 0..5 {
+            list.push_back(i);
+        }
+        list.push_front(-1);
+        list.pop_back();
+        let nodes = nodes(&list);
+        assert_eq!(live(&nodes), 5);
+        // Each node is only owned by the one before it, head and tail by the list too
+        let counts: Vec<_> = nodes.iter().map(Weak::strong_count).collect();
+        assert_eq!(counts, &[1, 1, 1, 1, 2]);
+        // And pointed back at by the one after it, plus our own Weak
+        let counts: Vec<_> = nodes.iter().map(Weak::weak_count).collect();
+        assert_eq!(counts, &[2, 2, 2, 2, 1]);
+
+        assert_eq!(list.stuck_nodes(), &[]);
+        drop(list);
+        assert_eq!(live(&nodes), 0);
+        assert_no_live_nodes(ListKind::BadSafeDeque);
+    }
+
+    #[test]
+    fn head_frees_chain() {
+        let mut list = List::new();
+        for i in 0..5 {
+            list.push_front(i);
+        }
+        let nodes = nodes(&list);
+
+        // Leak the list, but not its head and tail
+        let head = list.head.take();
+        let tail = list.tail.take();
+        std::mem::forget(list);
+        assert_eq!(live(&nodes), 5);
+        // With strong prevs this would free nothing, every node kept alive by its neighbour
+        // Now the whole chain goes with the head, all but the tail we're still holding on to
+        drop(head);
+        assert_eq!(live(&nodes), 1);
+        drop(tail);
+        assert_eq!(live(&nodes), 0);
+    }
+
+    #[test]
+    fn panic_in_drop_frees_nodes() {
+        // Blows up once, while being dropped
+        struct Bomb<'a>(&'a Cell<bool>);
+
+        impl Drop for Bomb<'_> {
+            fn drop(&mut self) {
+                if self.0.replace(false) {
+                    panic!("boom");
+                }
+            }
+        }
+
+        let armed = Cell::new(true);
+        let mut list = List::new();
+        for _ in 0..10 {
+            list.push_back(Bomb(&armed));
+        }
+        let nodes = nodes(&list);
+
+        // Our Drop never gets past the first node, the rest is left to the drop glue
+        // which used to leak every node after the bomb, they were all in cycles
+        let result = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| drop(list)));
+        assert!(result.is_err());
+        assert_eq!(live(&nodes), 0);
+        assert_no_live_nodes(ListKind::BadSafeDeque);
+    }
+
+    #[test]
+    fn long_list_drop() {
+        let mut list = List::new();
+        for i in 0..200_000 {
+            list.push_back(i);
+        }
+        assert_eq!(live_nodes(ListKind::BadSafeDeque), 200_000);
+        drop(list);
+        assert_no_live_nodes(ListKind::BadSafeDeque);
+    }
+
+    #[test]
+    fn stuck_nodes() {
+        // An element that can hold on to a node of its own list
+        struct Back(RefCell<Option<Rc<RefCell<Node<Back>>>>>);
+
+        let mut list = List::new();
+        for _ in 0..3 {
+            list.push_back(Back(RefCell::new(None)));
+        }
+        assert_eq!(live_nodes(ListKind::BadSafeDeque), 3);
+        assert_eq!(list.stuck_nodes(), &[]);
+
+        // The middle one owns itself now, a cycle our Drop can't see
+        let middle = list.head.as_ref().unwrap().borrow().next.clone().unwrap();
+        *middle.borrow().elem.0.borrow_mut() = Some(middle.clone());
+        let weak = Rc::downgrade(&middle);
+        drop(middle);
+        let stuck = StuckNode {
+            index: 1,
+            strong_count: 2,
+            expected: 1,
+        };
+        assert_eq!(list.stuck_nodes(), &[stuck]);
+
+        drop(list);
+        assert_eq!(live_nodes(ListKind::BadSafeDeque), 1);
+
+        // Break it by hand, and it's all gone
+        let middle = weak.upgrade().unwrap();
+        middle.borrow().elem.0.take();
+        drop(middle);
+        assert_no_live_nodes(ListKind::BadSafeDeque);
+    }
+
+    #[test]
+    fn try_api() {
+        let mut list = List::new();
+        assert_eq!(list.try_pop_front(), Err(ListError::Empty));
+        assert_eq!(list.try_pop_back(), Err(ListError::Empty));
+        assert_eq!(list.try_peek_front().err(), Some(ListError::Empty));
+        assert_eq!(list.try_peek_back_mut().err(), Some(ListError::Empty));
+
+        list.push_back(1);
+        assert_eq!(*list.try_peek_front().unwrap(), 1);
+        *list.try_peek_back_mut().unwrap() = 10;
+        assert_eq!(list.try_pop_back(), Ok(10));
+        for i in 1..=4 {
+            list.push_back(i);
+        }
+        assert_eq!(list.try_pop_front(), Ok(1));
+        assert_eq!(list.try_pop_back(), Ok(4));
+        *list.try_peek_front_mut().unwrap() *= 10;
+        assert_eq!(*list.try_peek_back().unwrap(), 3);
+        assert_eq!(list.try_pop_front(), Ok(20));
+        // Last one, owned by head and tail both
+        assert_eq!(list.try_pop_front(), Ok(3));
+        assert_eq!(list.try_pop_back(), Err(ListError::Empty));
+        assert_eq!(
+            ListError::Shared.to_string(),
+            "list node is shared outside of the list"
+        );
+    }
+
+    #[test]
+    fn try_api_conflicts() {
+        // The public API can't get into these states, the borrow checker won't let it
+        // so hold on to the nodes directly, the way the list's own code could
+        let mut list = List::new();
+        for i in 1..=3 {
+            list.push_back(i);
+        }
+        let head = list.head.clone().unwrap();
+        let tail = list.tail.clone().unwrap();
+
+        {
+            let guard = head.borrow_mut();
+            assert_eq!(list.try_pop_front(), Err(ListError::Borrowed));
+            assert_eq!(list.try_peek_front().err(), Some(ListError::Borrowed));
+            assert_eq!(list.try_peek_front_mut().err(), Some(ListError::Borrowed));
+            drop(guard);
+        }
+        // Only shared now, popping would have to move the element out of our Rc
+        assert_eq!(list.try_pop_front(), Err(ListError::Shared));
+        assert_eq!(list.try_pop_back(), Err(ListError::Shared));
+        drop(tail);
+
+        // The neighbour gets touched too
+        let middle = head.borrow().next.clone().unwrap();
+        drop(head);
+        {
+            let guard = middle.borrow();
+            assert_eq!(list.try_pop_front(), Err(ListError::Borrowed));
+            assert_eq!(list.try_pop_back(), Err(ListError::Borrowed));
+            // Reads are fine next to other reads
+            assert_eq!(*list.try_peek_front().unwrap(), 1);
+            drop(guard);
+        }
+        drop(middle);
+
+        // Nothing changed on the way
+        assert_eq!(list.try_pop_front(), Ok(1));
+        assert_eq!(list.try_pop_back(), Ok(3));
+        assert_eq!(list.try_pop_back(), Ok(2));
+    }
 }
@@ -58,6 +58,12 @@ impl List {
     }
 }
 
+impl Default for List {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 // We need a custom Drop (C++ destructor equivalent) to avoid stack overflow
 // Each item in list will require a new function call
 // Tail Recursion can't help us here :(
@@ -0,0 +1,92 @@
+use std::cell::Cell;
+
+/*
+    Leak hunting for the Rc based lists
+    Their bugs don't crash, they just leave nodes nobody can reach and nobody frees
+    So every node carries a Live token, which counts itself in when the node is made and out when it's dropped
+    A test can then check that everything it made is gone
+
+    Counts are per thread, Rc lists can't leave the thread they were made on, so their nodes are born and die there
+    That also keeps tests running in parallel from seeing each other's nodes
+    Only compiled in with the diagnostics feature (and in our own tests), the nodes stay as small as before otherwise
+*/
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum ListKind {
+    BadSafeDeque,
+    PersistentLinkedList,
+}
+
+const KINDS: usize = 2;
+
+thread_local! {
+    static LIVE: [Cell<usize>; KINDS] = const { [const { Cell::new(0) }; KINDS] };
+}
+
+// Nodes of kind made on this thread and not dropped yet
+pub fn live_nodes(kind: ListKind) -> usize {
+    LIVE.with(|live| live[kind as usize].get())
+}
+
+#[track_caller]
+pub fn assert_no_live_nodes(kind: ListKind) {
+    let live = live_nodes(kind);
+    assert!(live == 0, "{live} {kind:?} nodes are still alive");
+}
+
+// A node whose strong count is more than the list's own links account for
+// Some other node holds on to it too, so it will keep both alive after the list is gone
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct StuckNode {
+    // From the front of the list
+    pub index: usize,
+    pub strong_count: usize,
+    pub expected: usize,
+}
+
+// Goes in every node, counts as long as the node lives
+pub(crate) struct Live(ListKind);
+
+impl Live {
+    pub(crate) fn new(kind: ListKind) -> Self {
+        LIVE.with(|live| live[kind as usize].set(live[kind as usize].get() + 1));
+        Live(kind)
+    }
+}
+
+impl Drop for Live {
+    fn drop(&mut self) {
+        LIVE.with(|live| live[self.0 as usize].set(live[self.0 as usize].get() - 1));
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use std::thread;
+
+    #[test]
+    fn counts() {
+        let a = Live::new(ListKind::BadSafeDeque);
+        let b = Live::new(ListKind::BadSafeDeque);
+        assert_eq!(live_nodes(ListKind::BadSafeDeque), 2);
+        assert_no_live_nodes(ListKind::PersistentLinkedList);
+
+        // Other threads have their own counts
+        thread::spawn(|| assert_no_live_nodes(ListKind::BadSafeDeque))
+            .join()
+            .unwrap();
+
+        drop(a);
+        assert_eq!(live_nodes(ListKind::BadSafeDeque), 1);
+        drop(b);
+        assert_no_live_nodes(ListKind::BadSafeDeque);
+    }
+
+    #[test]
+    #[should_panic(expected = "1 PersistentLinkedList nodes are still alive")]
+    fn leak() {
+        std::mem::forget(Live::new(ListKind::PersistentLinkedList));
+        assert_no_live_nodes(ListKind::PersistentLinkedList);
+    }
+}
@@ -0,0 +1,236 @@
+use std::{cell::UnsafeCell, marker::PhantomData};
+
+/*
+    GhostCell, splitting the permission to touch data from the data itself
+    A GhostCell<'brand, T> can only be read with a &GhostToken<'brand>, and written with a &mut GhostToken<'brand>
+    So the usual borrow rules apply to the token, and through it to every cell of that brand at once
+    Many readers or one writer, checked at compile time, no flags like RefCell has
+
+    Brands come from GhostToken::new, every call makes up a brand new lifetime nobody else has
+    That, and the token not being Clone, is all the soundness argument there is
+
+    SAFETY, all of it in one place: every reference out of a cell borrows its brand's one token,
+    shared for reads, mutably for writes, so the borrow checker does the aliasing rules for us
+    The one exception is borrow_mut_unchecked, where the &mut T only borrows the cell
+    That's for handing out &mut to many different cells under one &mut token, like ghost_list's IterMut,
+    and keeping those cells different is on the caller
+*/
+
+// Invariant in 'brand, so the compiler can't stretch or shrink one brand into another
+type Brand<'brand> = PhantomData<fn(&'brand ()) -> &'brand ()>;
+
+/// Tokens of different brands don't mix:
+///
+/// ```compile_fail
+/// use lists::ghost_cell::{GhostCell, GhostToken};
+///
+/// GhostToken::new(|token_a| {
+///     GhostToken::new(|mut token_b| {
+///         let cell = GhostCell::new(1);
+///         let _ = cell.borrow(&token_a);
+///         *cell.borrow_mut(&mut token_b) = 2;
+///     })
+/// });
+/// ```
+///
+/// And a read through the token blocks writes for as long as it lives:
+///
+/// ```compile_fail
+/// use lists::ghost_cell::{GhostCell, GhostToken};
+///
+/// GhostToken::new(|mut token| {
+///     let cell = GhostCell::new(1);
+///     let read = cell.borrow(&token);
+///     *cell.borrow_mut(&mut token) = 2;
+///     assert_eq!(*read, 1);
+/// });
+/// ```
+pub struct GhostToken<'brand> {
+    _brand: Brand<'brand>,
+}
+
+impl GhostToken<'_> {
+    // The closure has to work for any 'new, so it can't assume it's equal to any other brand
+    // Not returning Self is the whole point, the token must not escape the closure
+    #[allow(clippy::new_ret_no_self)]
+    pub fn new<R>(f: impl for<'new> FnOnce(GhostToken<'new>) -> R) -> R {
+        f(GhostToken {
+            _brand: PhantomData,
+        })
+    }
+}
+
+#[repr(transparent)]
+pub struct GhostCell<'brand, T: ?Sized> {
+    _brand: Brand<'brand>,
+    value: UnsafeCell<T>,
+}
+
+impl<'brand, T> GhostCell<'brand, T> {
+    pub const fn new(value: T) -> Self {
+        GhostCell {
+            _brand: PhantomData,
+            value: UnsafeCell::new(value),
+        }
+    }
+
+    pub fn into_inner(self) -> T {
+        self.value.into_inner()
+    }
+}
+
+impl<'brand, T: ?Sized> GhostCell<'brand, T> {
+    // Owning the cell is enough, no token needed
+    pub fn get_mut(&mut self) -> &mut T {
+        self.value.get_mut()
+    }
+
+    pub fn borrow<'a>(&'a self, _token: &'a GhostToken<'brand>) -> &'a T {
+        // SAFETY: the shared token borrow lives as long as the result,
+        // and nobody can get a &mut GhostToken<'brand> meanwhile, so no writes to any cell of our brand
+        unsafe { &*self.value.get() }
+    }
+
+    pub fn borrow_mut<'a>(&'a self, _token: &'a mut GhostToken<'brand>) -> &'a mut T {
+        // SAFETY: the one and only token of our brand is borrowed mutably for as long as the result lives,
+        // so no other borrow of any cell of our brand can exist until it's gone
+        unsafe { &mut *self.value.get() }
+    }
+
+    /// `borrow_mut`, except the result only borrows the cell, not the token.
+    /// With a `&'a mut` token held for all of `'a`, that's enough to give out a `&'a mut T` from each of many cells.
+    ///
+    /// # Safety
+    ///
+    /// While the result lives, nothing else may reference this cell's value:
+    /// not another `borrow_mut_unchecked` of it, nor a `borrow` or `borrow_mut` through the token.
+    /// Holding on to the token mutably for that long, and only using it on other cells, does it.
+    #[allow(clippy::mut_from_ref)]
+    pub unsafe fn borrow_mut_unchecked<'a>(&'a self, _token: &mut GhostToken<'brand>) -> &'a mut T {
+        // SAFETY: the &mut token means no borrows through it are alive right now,
+        // and the caller promised none of this cell come after, until the result is gone
+        unsafe { &mut *self.value.get() }
+    }
+}
+
+impl<T: Default> Default for GhostCell<'_, T> {
+    fn default() -> Self {
+        Self::new(T::default())
+    }
+}
+
+// Sending a cell sends the value, T: Send is enough
+unsafe impl<T: ?Sized + Send> Send for GhostCell<'_, T> {}
+// Sharing a cell lets the token holder on any thread read (needs Sync) or write (needs Send) it
+unsafe impl<T: ?Sized + Send + Sync> Sync for GhostCell<'_, T> {}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use std::thread;
+
+    #[test]
+    fn test_borrow() {
+        GhostToken::new(|mut token| {
+            let a = GhostCell::new(1);
+            let b = GhostCell::new(2);
+
+            // Any number of reads at the same time
+            let (x, y) = (a.borrow(&token), b.borrow(&token));
+            assert_eq!(*x + *y, 3);
+
+            // One write at a time
+            *a.borrow_mut(&mut token) += 10;
+            *b.borrow_mut(&mut token) += 20;
+            assert_eq!(*a.borrow(&token), 11);
+            assert_eq!(*b.borrow(&token), 22);
+        });
+    }
+
+    #[test]
+    fn test_borrow_mut_unchecked() {
+        GhostToken::new(|mut token| {
+            let cells: Vec<_> = (0..4).map(GhostCell::new).collect();
+
+            // A &mut to every cell at once, all under the one token
+            let elems: Vec<&mut i32> = cells
+                .iter()
+                // SAFETY: every cell once, and the token is only used for this until elems is gone
+                .map(|cell| unsafe { cell.borrow_mut_unchecked(&mut token) })
+                .collect();
+            for (i, elem) in elems.into_iter().enumerate() {
+                *elem += 10 * i as i32;
+            }
+
+            // Back to borrowing the token
+            let values: Vec<_> = cells.iter().map(|cell| *cell.borrow(&token)).collect();
+            assert_eq!(values, &[0, 11, 22, 33]);
+        });
+    }
+
+    #[test]
+    fn test_owned() {
+        let result = GhostToken::new(|_token| {
+            let mut cell = GhostCell::new(vec![1, 2]);
+            // No token needed when we own the cell
+            cell.get_mut().push(3);
+            cell.into_inner()
+        });
+        assert_eq!(result, &[1, 2, 3]);
+
+        let cell: GhostCell<String> = GhostCell::default();
+        assert!(cell.into_inner().is_empty());
+    }
+
+    #[test]
+    fn test_unsized() {
+        GhostToken::new(|mut token| {
+            let cell: Box<GhostCell<[i32]>> = Box::new(GhostCell::new([1, 2, 3]));
+            cell.borrow_mut(&mut token)[1] = 20;
+            assert_eq!(cell.borrow(&token), &[1, 20, 3]);
+        });
+    }
+
+    #[test]
+    fn test_threads() {
+        GhostToken::new(|mut token| {
+            let cells: Vec<_> = (0..4).map(GhostCell::new).collect();
+
+            // Shared token, every thread reads every cell
+            let token_ref = &token;
+            let sums: Vec<i32> = thread::scope(|s| {
+                let handles: Vec<_> = (0..4)
+                    .map(|_| s.spawn(|| cells.iter().map(|cell| *cell.borrow(token_ref)).sum()))
+                    .collect();
+                handles.into_iter().map(|h| h.join().unwrap()).collect()
+            });
+            assert_eq!(sums, &[6; 4]);
+
+            // Token moves to another thread, which gets to write
+            thread::scope(|s| {
+                s.spawn(|| {
+                    for cell in &cells {
+                        *cell.borrow_mut(&mut token) *= 2;
+                    }
+                });
+            });
+            let values: Vec<_> = cells.iter().map(|cell| *cell.borrow(&token)).collect();
+            assert_eq!(values, &[0, 2, 4, 6]);
+        });
+    }
+
+    #[allow(dead_code)]
+    fn assert_properties() {
+        fn is_send<T: Send>() {}
+        fn is_sync<T: Sync>() {}
+
+        is_send::<GhostToken>();
+        is_sync::<GhostToken>();
+        is_send::<GhostCell<i32>>();
+        is_sync::<GhostCell<i32>>();
+        is_send::<GhostCell<std::cell::Cell<i32>>>();
+        // Would be a data race, writer on one thread, reader on another
+        // is_sync::<GhostCell<std::cell::Cell<i32>>>();
+        // is_send::<GhostCell<std::rc::Rc<i32>>>();
+    }
+}
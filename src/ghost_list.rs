@@ -0,0 +1,688 @@
+// The unsafe lives in ghost_cell, all but the one borrow_mut_unchecked call IterMut makes
+#![deny(unsafe_code)]
+
+use crate::ghost_cell::{GhostCell, GhostToken};
+use std::{
+    iter::FusedIterator,
+    rc::{Rc, Weak},
+};
+
+/*
+    bad_safe_deque again, but with GhostCell instead of RefCell
+    RefCell checks every borrow at runtime, and every Ref keeps its node borrowed,
+    which is why iterating there was such a nightmare
+    Here a node is read with &GhostToken and written with &mut GhostToken, checked at compile time
+    So a &'a T out of a node lives as long as the token borrow, no Ref guards chained together
+
+    Every operation takes the token, and lists with the same brand share it,
+    which means they can swap nodes around between them, like append does
+
+    prev links are Weak, so there are no cycles and dropping the list doesn't need the token
+    The flip side is that going backwards needs an upgrade, a temporary Rc,
+    and references can't outlive that, so Iter's first next_back walks forwards once and remembers the way
+    Cursors keep their own Rc, so they go both ways for free
+
+    IterMut holds the &mut token for as long as its &mut T live, and every node is a different cell,
+    so it can hand out all of them with GhostCell::borrow_mut_unchecked
+    CursorMut does the rest, inserting and removing on the way
+*/
+
+type NodeRef<'brand, T> = Rc<GhostCell<'brand, Node<'brand, T>>>;
+type WeakRef<'brand, T> = Weak<GhostCell<'brand, Node<'brand, T>>>;
+
+struct Node<'brand, T> {
+    elem: T,
+    next: Option<NodeRef<'brand, T>>,
+    prev: Option<WeakRef<'brand, T>>,
+}
+
+pub struct GhostList<'brand, T> {
+    front: Option<NodeRef<'brand, T>>,
+    // Strong, so back() can hand out references, the last node has two owners
+    back: Option<NodeRef<'brand, T>>,
+    len: usize,
+}
+
+impl<'brand, T> GhostList<'brand, T> {
+    pub fn new() -> Self {
+        GhostList {
+            front: None,
+            back: None,
+            len: 0,
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    pub fn push_front(&mut self, elem: T, token: &mut GhostToken<'brand>) {
+        let next = self.front.clone();
+        self.link_between(None, next, elem, token);
+    }
+
+    pub fn push_back(&mut self, elem: T, token: &mut GhostToken<'brand>) {
+        let prev = self.back.clone();
+        self.link_between(prev, None, elem, token);
+    }
+
+    pub fn pop_front(&mut self, token: &mut GhostToken<'brand>) -> Option<T> {
+        let node = self.front.clone()?;
+        Some(self.unlink(node, token))
+    }
+
+    pub fn pop_back(&mut self, token: &mut GhostToken<'brand>) -> Option<T> {
+        let node = self.back.clone()?;
+        Some(self.unlink(node, token))
+    }
+
+    pub fn front<'a>(&'a self, token: &'a GhostToken<'brand>) -> Option<&'a T> {
+        self.front.as_ref().map(|node| &node.borrow(token).elem)
+    }
+
+    pub fn front_mut<'a>(&'a mut self, token: &'a mut GhostToken<'brand>) -> Option<&'a mut T> {
+        self.front
+            .as_ref()
+            .map(|node| &mut node.borrow_mut(token).elem)
+    }
+
+    pub fn back<'a>(&'a self, token: &'a GhostToken<'brand>) -> Option<&'a T> {
+        self.back.as_ref().map(|node| &node.borrow(token).elem)
+    }
+
+    pub fn back_mut<'a>(&'a mut self, token: &'a mut GhostToken<'brand>) -> Option<&'a mut T> {
+        self.back
+            .as_ref()
+            .map(|node| &mut node.borrow_mut(token).elem)
+    }
+
+    // Moves all of other's nodes to our back, O(1)
+    // Only works because other has our brand, the token lets us touch nodes of both lists
+    pub fn append(&mut self, other: &mut Self, token: &mut GhostToken<'brand>) {
+        let Some(other_front) = other.front.take() else {
+            return;
+        };
+        match self.back.take() {
+            Some(back) => {
+                other_front.borrow_mut(token).prev = Some(Rc::downgrade(&back));
+                back.borrow_mut(token).next = Some(other_front);
+            }
+            None => self.front = Some(other_front),
+        }
+        self.back = other.back.take();
+        self.len += std::mem::take(&mut other.len);
+    }
+
+    // No token needed, same as Drop
+    pub fn clear(&mut self) {
+        *self = Self::new();
+    }
+
+    // Make a node for elem and put it between prev and next, which have to be neighbours (or the ends)
+    fn link_between(
+        &mut self,
+        prev: Option<NodeRef<'brand, T>>,
+        next: Option<NodeRef<'brand, T>>,
+        elem: T,
+        token: &mut GhostToken<'brand>,
+    ) -> NodeRef<'brand, T> {
+        let node = Rc::new(GhostCell::new(Node {
+            elem,
+            next: next.clone(),
+            prev: prev.as_ref().map(Rc::downgrade),
+        }));
+        match next {
+            Some(next) => next.borrow_mut(token).prev = Some(Rc::downgrade(&node)),
+            None => self.back = Some(node.clone()),
+        }
+        match prev {
+            Some(prev) => prev.borrow_mut(token).next = Some(node.clone()),
+            None => self.front = Some(node.clone()),
+        }
+        self.len += 1;
+        node
+    }
+
+    // Cut the node out and take it apart
+    // Nobody else may hold on to it, other than the list itself
+    fn unlink(&mut self, node: NodeRef<'brand, T>, token: &mut GhostToken<'brand>) -> T {
+        let (prev, next) = {
+            let node = node.borrow_mut(token);
+            (
+                node.prev.take().and_then(|prev| prev.upgrade()),
+                node.next.take(),
+            )
+        };
+        match &next {
+            Some(next) => next.borrow_mut(token).prev = prev.as_ref().map(Rc::downgrade),
+            None => self.back = prev.clone(),
+        }
+        match prev {
+            Some(prev) => prev.borrow_mut(token).next = next,
+            None => self.front = next,
+        }
+        self.len -= 1;
+        // Still the same Rc::try_unwrap dance as bad_safe_deque, but it can't fail now
+        Rc::try_unwrap(node)
+            .ok()
+            .expect("unlinked node is still shared")
+            .into_inner()
+            .elem
+    }
+}
+
+impl<T> Default for GhostList<'_, T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T> Drop for GhostList<'_, T> {
+    fn drop(&mut self) {
+        // Every node is owned by the one before it (and the last one by back too)
+        // So once back lets go, each node is ours alone, and we can take it apart without the token
+        // One by one, dropping front as is would recurse all the way down the list
+        self.back.take();
+        let mut next = self.front.take();
+        while let Some(node) = next {
+            next = Rc::try_unwrap(node)
+                .ok()
+                .and_then(|node| node.into_inner().next);
+        }
+    }
+}
+
+pub struct Iter<'a, 'brand, T> {
+    next: Option<&'a NodeRef<'brand, T>>,
+    token: &'a GhostToken<'brand>,
+    len: usize,
+    // Everything not handed out yet, once next_back needed it, see there
+    rest: Option<std::vec::IntoIter<&'a T>>,
+}
+
+impl<'brand, T> GhostList<'brand, T> {
+    // The whole list stays readable for as long as the token is borrowed
+    pub fn iter<'a>(&'a self, token: &'a GhostToken<'brand>) -> Iter<'a, 'brand, T> {
+        Iter {
+            next: self.front.as_ref(),
+            token,
+            len: self.len,
+            rest: None,
+        }
+    }
+}
+
+impl<'a, T> Iterator for Iter<'a, '_, T> {
+    type Item = &'a T;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if let Some(rest) = &mut self.rest {
+            let elem = rest.next();
+            self.len = rest.len();
+            return elem;
+        }
+        self.next.take().map(|node| {
+            let node = node.borrow(self.token);
+            self.next = node.next.as_ref();
+            self.len -= 1;
+            &node.elem
+        })
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.len, Some(self.len))
+    }
+}
+
+// A &'a T has to come through a &'a Rc, and the only ones of those are the next links, going forwards
+// The Weak prev links only give temporary Rcs, so the first next_back walks the rest forwards,
+// O(len) once, and keeps the references to hand out from both ends after that
+impl<T> DoubleEndedIterator for Iter<'_, '_, T> {
+    fn next_back(&mut self) -> Option<Self::Item> {
+        if self.rest.is_none() {
+            let rest: Vec<_> = self.by_ref().collect();
+            self.rest = Some(rest.into_iter());
+        }
+        let rest = self.rest.as_mut()?;
+        let elem = rest.next_back();
+        self.len = rest.len();
+        elem
+    }
+}
+
+impl<T> ExactSizeIterator for Iter<'_, '_, T> {}
+
+impl<T> FusedIterator for Iter<'_, '_, T> {}
+
+impl<T> Clone for Iter<'_, '_, T> {
+    fn clone(&self) -> Self {
+        Iter {
+            next: self.next,
+            token: self.token,
+            len: self.len,
+            rest: self.rest.clone(),
+        }
+    }
+}
+
+// Each &'a mut T is its own node's elem, and the node's next link comes along as a &'a, for the next call
+// Goes backwards the same way Iter does
+pub struct IterMut<'a, 'brand, T> {
+    next: Option<&'a NodeRef<'brand, T>>,
+    token: &'a mut GhostToken<'brand>,
+    len: usize,
+    rest: Option<std::vec::IntoIter<&'a mut T>>,
+}
+
+impl<'brand, T> GhostList<'brand, T> {
+    // The &mut token is what counts, nothing gets unlinked without it, the &mut list is just like the other deques
+    pub fn iter_mut<'a>(&'a mut self, token: &'a mut GhostToken<'brand>) -> IterMut<'a, 'brand, T> {
+        IterMut {
+            next: self.front.as_ref(),
+            token,
+            len: self.len,
+            rest: None,
+        }
+    }
+}
+
+impl<'a, T> Iterator for IterMut<'a, '_, T> {
+    type Item = &'a mut T;
+
+    #[allow(unsafe_code)]
+    fn next(&mut self) -> Option<Self::Item> {
+        if let Some(rest) = &mut self.rest {
+            let elem = rest.next();
+            self.len = rest.len();
+            return elem;
+        }
+        self.next.take().map(|node| {
+            // SAFETY: the token is ours for all of 'a and we only ever use it here,
+            // and the links go front to back without cycles, so no node comes up twice
+            let Node { elem, next, .. } = unsafe { node.borrow_mut_unchecked(self.token) };
+            self.next = next.as_ref();
+            self.len -= 1;
+            elem
+        })
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.len, Some(self.len))
+    }
+}
+
+impl<T> DoubleEndedIterator for IterMut<'_, '_, T> {
+    fn next_back(&mut self) -> Option<Self::Item> {
+        if self.rest.is_none() {
+            let rest: Vec<_> = self.by_ref().collect();
+            self.rest = Some(rest.into_iter());
+        }
+        let rest = self.rest.as_mut()?;
+        let elem = rest.next_back();
+        self.len = rest.len();
+        elem
+    }
+}
+
+impl<T> ExactSizeIterator for IterMut<'_, '_, T> {}
+
+impl<T> FusedIterator for IterMut<'_, '_, T> {}
+
+// Cursors keep their own Rc to the node they're on, so they can go backwards too
+// Same ghost element semantics as the other deques
+pub struct Cursor<'a, 'brand, T> {
+    list: &'a GhostList<'brand, T>,
+    token: &'a GhostToken<'brand>,
+    cur: Option<NodeRef<'brand, T>>,
+    index: Option<usize>,
+}
+
+pub struct CursorMut<'a, 'brand, T> {
+    list: &'a mut GhostList<'brand, T>,
+    token: &'a mut GhostToken<'brand>,
+    cur: Option<NodeRef<'brand, T>>,
+    index: Option<usize>,
+}
+
+impl<'brand, T> GhostList<'brand, T> {
+    pub fn cursor_front<'a>(&'a self, token: &'a GhostToken<'brand>) -> Cursor<'a, 'brand, T> {
+        Cursor {
+            list: self,
+            token,
+            cur: self.front.clone(),
+            index: self.front.as_ref().map(|_| 0),
+        }
+    }
+
+    pub fn cursor_back<'a>(&'a self, token: &'a GhostToken<'brand>) -> Cursor<'a, 'brand, T> {
+        Cursor {
+            list: self,
+            token,
+            cur: self.back.clone(),
+            index: self.len.checked_sub(1),
+        }
+    }
+
+    pub fn cursor_mut<'a>(
+        &'a mut self,
+        token: &'a mut GhostToken<'brand>,
+    ) -> CursorMut<'a, 'brand, T> {
+        CursorMut {
+            list: self,
+            token,
+            cur: None,
+            index: None,
+        }
+    }
+
+    // Neighbours of cur, going through the ghost at the ends
+    fn next_of(
+        &self,
+        cur: &Option<NodeRef<'brand, T>>,
+        token: &GhostToken<'brand>,
+    ) -> Option<NodeRef<'brand, T>> {
+        match cur {
+            Some(cur) => cur.borrow(token).next.clone(),
+            None => self.front.clone(),
+        }
+    }
+
+    fn prev_of(
+        &self,
+        cur: &Option<NodeRef<'brand, T>>,
+        token: &GhostToken<'brand>,
+    ) -> Option<NodeRef<'brand, T>> {
+        match cur {
+            Some(cur) => cur.borrow(token).prev.as_ref().and_then(Weak::upgrade),
+            None => self.back.clone(),
+        }
+    }
+}
+
+impl<T> Cursor<'_, '_, T> {
+    pub fn index(&self) -> Option<usize> {
+        self.index
+    }
+
+    pub fn move_next(&mut self) {
+        self.cur = self.list.next_of(&self.cur, self.token);
+        self.index = match (&self.cur, self.index) {
+            (None, _) => None,
+            (Some(_), Some(index)) => Some(index + 1),
+            (Some(_), None) => Some(0),
+        };
+    }
+
+    pub fn move_prev(&mut self) {
+        self.cur = self.list.prev_of(&self.cur, self.token);
+        self.index = match self.index {
+            Some(index) => index.checked_sub(1),
+            None => self.list.len.checked_sub(1),
+        };
+    }
+
+    pub fn current(&self) -> Option<&T> {
+        self.cur.as_ref().map(|node| &node.borrow(self.token).elem)
+    }
+}
+
+impl<'brand, T> CursorMut<'_, 'brand, T> {
+    pub fn index(&self) -> Option<usize> {
+        self.index
+    }
+
+    pub fn move_next(&mut self) {
+        self.cur = self.list.next_of(&self.cur, self.token);
+        self.index = match (&self.cur, self.index) {
+            (None, _) => None,
+            (Some(_), Some(index)) => Some(index + 1),
+            (Some(_), None) => Some(0),
+        };
+    }
+
+    pub fn move_prev(&mut self) {
+        self.cur = self.list.prev_of(&self.cur, self.token);
+        self.index = match self.index {
+            Some(index) => index.checked_sub(1),
+            None => self.list.len.checked_sub(1),
+        };
+    }
+
+    pub fn current(&mut self) -> Option<&mut T> {
+        self.cur
+            .as_ref()
+            .map(|node| &mut node.borrow_mut(self.token).elem)
+    }
+
+    // Before the ghost is the back of the list
+    pub fn insert_before(&mut self, elem: T) {
+        let prev = self.list.prev_of(&self.cur, self.token);
+        self.list
+            .link_between(prev, self.cur.clone(), elem, self.token);
+        if let Some(index) = self.index.as_mut() {
+            *index += 1;
+        }
+    }
+
+    // After the ghost is the front of the list
+    pub fn insert_after(&mut self, elem: T) {
+        let next = self.list.next_of(&self.cur, self.token);
+        self.list
+            .link_between(self.cur.clone(), next, elem, self.token);
+    }
+
+    // Takes the current element out, the cursor moves on to the next one
+    pub fn remove_current(&mut self) -> Option<T> {
+        let cur = self.cur.take()?;
+        self.cur = cur.borrow(self.token).next.clone();
+        if self.cur.is_none() {
+            self.index = None;
+        }
+        Some(self.list.unlink(cur, self.token))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn to_vec<'brand, T: Clone + PartialEq + std::fmt::Debug>(
+        list: &GhostList<'brand, T>,
+        token: &GhostToken<'brand>,
+    ) -> Vec<T> {
+        let forward: Vec<_> = list.iter(token).cloned().collect();
+        assert_eq!(forward.len(), list.len());
+        // And backwards, through a cursor
+        let mut backward = Vec::new();
+        let mut cursor = list.cursor_back(token);
+        while let Some(elem) = cursor.current() {
+            backward.push(elem.clone());
+            cursor.move_prev();
+        }
+        backward.reverse();
+        assert_eq!(forward, backward);
+        forward
+    }
+
+    #[test]
+    fn test_basic() {
+        GhostToken::new(|mut token| {
+            let mut list = GhostList::new();
+            assert_eq!(list.pop_front(&mut token), None);
+            assert_eq!(list.pop_back(&mut token), None);
+            assert_eq!(list.front(&token), None);
+
+            list.push_back(2, &mut token);
+            list.push_back(3, &mut token);
+            list.push_front(1, &mut token);
+            assert_eq!(list.len(), 3);
+            assert_eq!(list.front(&token), Some(&1));
+            assert_eq!(list.back(&token), Some(&3));
+            *list.front_mut(&mut token).unwrap() = 10;
+            *list.back_mut(&mut token).unwrap() = 30;
+
+            assert_eq!(list.pop_front(&mut token), Some(10));
+            assert_eq!(list.pop_back(&mut token), Some(30));
+            assert_eq!(list.pop_back(&mut token), Some(2));
+            assert_eq!(list.pop_front(&mut token), None);
+            assert!(list.is_empty());
+
+            list.push_front(4, &mut token);
+            list.push_front(5, &mut token);
+            assert_eq!(list.pop_back(&mut token), Some(4));
+            assert_eq!(list.pop_back(&mut token), Some(5));
+            assert_eq!(list.back(&token), None);
+        });
+    }
+
+    #[test]
+    fn test_iter() {
+        GhostToken::new(|mut token| {
+            let mut list = GhostList::new();
+            for i in 0..5 {
+                list.push_back(i, &mut token);
+            }
+
+            // Plain references, as many as we like, all alive at once
+            let refs: Vec<&i32> = list.iter(&token).collect();
+            let mut iter = list.iter(&token);
+            assert_eq!(iter.len(), 5);
+            assert_eq!(iter.next(), Some(&0));
+            assert_eq!(iter.clone().count(), 4);
+            assert_eq!(refs, &[&0, &1, &2, &3, &4]);
+            assert_eq!(list.front(&token), Some(refs[0]));
+
+            // Both ends, meeting in the middle
+            assert_eq!(iter.next_back(), Some(&4));
+            assert_eq!(iter.len(), 3);
+            assert_eq!(iter.next(), Some(&1));
+            assert_eq!(iter.clone().rev().collect::<Vec<_>>(), &[&3, &2]);
+            assert_eq!(iter.next_back(), Some(&3));
+            assert_eq!(iter.next(), Some(&2));
+            assert_eq!(iter.len(), 0);
+            assert_eq!(iter.next_back(), None);
+            assert_eq!(iter.next(), None);
+            let back: Vec<_> = list.iter(&token).rev().copied().collect();
+            assert_eq!(back, &[4, 3, 2, 1, 0]);
+
+            // And every &mut at once
+            let elems: Vec<&mut i32> = list.iter_mut(&mut token).collect();
+            for elem in elems {
+                *elem *= 10;
+            }
+            assert_eq!(to_vec(&list, &token), &[0, 10, 20, 30, 40]);
+
+            let mut iter = list.iter_mut(&mut token);
+            assert_eq!(iter.next(), Some(&mut 0));
+            assert_eq!(iter.next_back(), Some(&mut 40));
+            assert_eq!(iter.len(), 3);
+            for elem in iter.rev() {
+                *elem += 1;
+            }
+            assert_eq!(to_vec(&list, &token), &[0, 11, 21, 31, 40]);
+            assert_eq!(GhostList::<i32>::new().iter_mut(&mut token).next(), None);
+        });
+    }
+
+    #[test]
+    fn test_cursor() {
+        GhostToken::new(|mut token| {
+            let mut list = GhostList::new();
+            for i in 1..=6 {
+                list.push_back(i, &mut token);
+            }
+
+            let mut cursor = list.cursor_front(&token);
+            assert_eq!(cursor.current(), Some(&1));
+            assert_eq!(cursor.index(), Some(0));
+            cursor.move_prev();
+            assert_eq!(cursor.current(), None);
+            assert_eq!(cursor.index(), None);
+            cursor.move_prev();
+            assert_eq!(cursor.current(), Some(&6));
+            assert_eq!(cursor.index(), Some(5));
+            cursor.move_next();
+            cursor.move_next();
+            assert_eq!(cursor.current(), Some(&1));
+
+            let mut cursor = list.cursor_mut(&mut token);
+            cursor.move_next();
+            cursor.move_next();
+            assert_eq!(cursor.current(), Some(&mut 2));
+            cursor.insert_before(10);
+            cursor.insert_after(20);
+            assert_eq!(cursor.index(), Some(2));
+            *cursor.current().unwrap() = 200;
+            assert_eq!(cursor.remove_current(), Some(200));
+            assert_eq!(cursor.current(), Some(&mut 20));
+            assert_eq!(cursor.index(), Some(2));
+            cursor.move_prev();
+            cursor.move_prev();
+            cursor.move_prev();
+            assert_eq!(cursor.index(), None);
+            cursor.insert_after(0);
+            cursor.insert_before(7);
+            cursor.move_prev();
+            assert_eq!(cursor.remove_current(), Some(7));
+            assert_eq!(cursor.index(), None);
+            cursor.move_prev();
+            assert_eq!(cursor.remove_current(), Some(6));
+            assert_eq!(to_vec(&list, &token), &[0, 1, 10, 20, 3, 4, 5]);
+            assert_eq!(list.back(&token), Some(&5));
+        });
+    }
+
+    #[test]
+    fn test_append() {
+        GhostToken::new(|mut token| {
+            // Two lists, one brand, one token for both
+            let mut a = GhostList::new();
+            let mut b = GhostList::new();
+            for i in 0..3 {
+                a.push_back(i, &mut token);
+                b.push_back(i + 3, &mut token);
+            }
+            a.append(&mut b, &mut token);
+            assert!(b.is_empty());
+            assert_eq!(b.front(&token), None);
+            assert_eq!(to_vec(&a, &token), &[0, 1, 2, 3, 4, 5]);
+
+            // Appending to or from an empty list
+            b.append(&mut a, &mut token);
+            assert!(a.is_empty());
+            b.append(&mut a, &mut token);
+            assert_eq!(to_vec(&b, &token), &[0, 1, 2, 3, 4, 5]);
+            assert_eq!(b.pop_back(&mut token), Some(5));
+            b.push_back(6, &mut token);
+            assert_eq!(to_vec(&b, &token), &[0, 1, 2, 3, 4, 6]);
+        });
+    }
+
+    #[test]
+    fn test_drop() {
+        let elem = Rc::new(());
+        GhostToken::new(|mut token| {
+            let mut list = GhostList::new();
+            for _ in 0..10 {
+                list.push_back(elem.clone(), &mut token);
+            }
+            list.pop_front(&mut token);
+            assert_eq!(Rc::strong_count(&elem), 10);
+            // No token needed to get rid of it
+            drop(list);
+            assert_eq!(Rc::strong_count(&elem), 1);
+
+            // Long enough to blow the stack if drop recursed
+            let mut list = GhostList::new();
+            for i in 0..200_000 {
+                list.push_front(i, &mut token);
+            }
+            list.clear();
+            assert!(list.is_empty());
+        });
+    }
+}
@@ -0,0 +1,867 @@
+use std::{
+    cell::Cell,
+    fmt::{self, Debug},
+    iter::FusedIterator,
+    marker::PhantomData,
+    pin::Pin,
+    ptr::NonNull,
+    rc::Rc,
+    sync::{
+        Arc,
+        atomic::{
+            AtomicU64, Ordering::AcqRel, Ordering::Acquire, Ordering::Relaxed, Ordering::Release,
+        },
+    },
+};
+
+/*
+    Intrusive doubly linked list
+    Instead of the list allocating a node around every element, the element carries the links itself
+    struct Timer { deadline: u64, links: Links }
+    So putting something in a list costs no allocation at all, and removing it is O(1) given just a &Timer
+
+    The list holds on to the element's owner (Box, Rc, Arc, Pin<&mut T>) while it's linked,
+    so an element can't be dropped, or moved, while it is in a list, the API doesn't allow it
+    Each Links also remembers which list it is in, so
+    - linking something that is already in a list is caught at runtime
+    - removing something through the wrong list is caught at runtime
+*/
+
+// Lives inside the element, all of it is private, only the list touches it
+pub struct Links {
+    // Id of the list we're in, 0 when we're not in one
+    // Atomic, so two threads racing to link the same Arc'd element can't both win
+    list: AtomicU64,
+    // Same naming as in the deque, front is towards the front (prev), back is next
+    // Only ever touched by the list that won the element, and that takes &mut list
+    front: Cell<Link>,
+    back: Cell<Link>,
+}
+
+type Link = Option<NonNull<Links>>;
+
+impl Links {
+    pub const fn new() -> Self {
+        Links {
+            list: AtomicU64::new(0),
+            front: Cell::new(None),
+            back: Cell::new(None),
+        }
+    }
+
+    pub fn is_linked(&self) -> bool {
+        self.list.load(Relaxed) != 0
+    }
+}
+
+impl Default for Links {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+// Cloning an element doesn't clone its list membership
+impl Clone for Links {
+    fn clone(&self) -> Self {
+        Self::new()
+    }
+}
+
+impl Debug for Links {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Links")
+            .field("linked", &self.is_linked())
+            .finish()
+    }
+}
+
+// The Cells are only touched by the one list that claimed us through the atomic,
+// and only while it's borrowed mutably, so sharing Links between threads is fine
+unsafe impl Send for Links {}
+unsafe impl Sync for Links {}
+
+static NEXT_LIST_ID: AtomicU64 = AtomicU64::new(1);
+
+/// Something that owns or exclusively borrows a value with a stable address.
+/// While linked, the list keeps it as a raw pointer, and turns it back when unlinking.
+///
+/// # Safety
+///
+/// The value must stay put, and alive, from `into_raw` until `from_raw` gives the owner back.
+pub unsafe trait Owner {
+    type Target;
+
+    fn into_raw(self) -> NonNull<Self::Target>;
+
+    /// # Safety
+    ///
+    /// `ptr` has to come from `into_raw` on the same owner type.
+    unsafe fn from_raw(ptr: NonNull<Self::Target>) -> Self;
+}
+
+unsafe impl<T> Owner for Box<T> {
+    type Target = T;
+
+    fn into_raw(self) -> NonNull<T> {
+        unsafe { NonNull::new_unchecked(Box::into_raw(self)) }
+    }
+
+    unsafe fn from_raw(ptr: NonNull<T>) -> Self {
+        unsafe { Box::from_raw(ptr.as_ptr()) }
+    }
+}
+
+// Only keeps one strong count, other clones can still look at the element
+unsafe impl<T> Owner for Rc<T> {
+    type Target = T;
+
+    fn into_raw(self) -> NonNull<T> {
+        unsafe { NonNull::new_unchecked(Rc::into_raw(self).cast_mut()) }
+    }
+
+    unsafe fn from_raw(ptr: NonNull<T>) -> Self {
+        unsafe { Rc::from_raw(ptr.as_ptr()) }
+    }
+}
+
+unsafe impl<T> Owner for Arc<T> {
+    type Target = T;
+
+    fn into_raw(self) -> NonNull<T> {
+        unsafe { NonNull::new_unchecked(Arc::into_raw(self).cast_mut()) }
+    }
+
+    unsafe fn from_raw(ptr: NonNull<T>) -> Self {
+        unsafe { Arc::from_raw(ptr.as_ptr()) }
+    }
+}
+
+// Elements living somewhere else, like on the stack
+// The list holds the &'a mut borrow, so it can't outlive the element, and nobody can touch it meanwhile
+unsafe impl<T> Owner for Pin<&mut T> {
+    type Target = T;
+
+    fn into_raw(self) -> NonNull<T> {
+        // We never move out of it, it's only a pointer to us
+        NonNull::from(unsafe { Pin::into_inner_unchecked(self) })
+    }
+
+    unsafe fn from_raw(ptr: NonNull<T>) -> Self {
+        unsafe { Pin::new_unchecked(&mut *ptr.as_ptr()) }
+    }
+}
+
+/// Ties it all together, which owner owns what value, and where in the value the `Links` are.
+/// Use `intrusive_adapter!` instead of implementing it by hand.
+///
+/// # Safety
+///
+/// `LINKS_OFFSET` has to be the offset of a `Links` field inside `Value`.
+pub unsafe trait Adapter {
+    type Value;
+    type Pointer: Owner<Target = Self::Value>;
+    const LINKS_OFFSET: usize;
+}
+
+// intrusive_adapter!(pub TimerAdapter = Box<Timer>: Timer { links });
+// intrusive_adapter!(StackAdapter<'a> = Pin<&'a mut Timer>: Timer { links });
+#[macro_export]
+macro_rules! intrusive_adapter {
+    ($vis:vis $name:ident $(<$lt:lifetime>)? = $pointer:ty : $value:ty { $field:ident }) => {
+        $vis struct $name $(<$lt>)? (::std::marker::PhantomData<($(&$lt (),)?)>);
+
+        unsafe impl $(<$lt>)? $crate::intrusive::Adapter for $name $(<$lt>)? {
+            type Value = $value;
+            type Pointer = $pointer;
+            const LINKS_OFFSET: usize = {
+                // Doesn't compile unless the field really is Links
+                let _: fn(&$value) -> &$crate::intrusive::Links = |value| &value.$field;
+                ::std::mem::offset_of!($value, $field)
+            };
+        }
+    };
+}
+
+// The links live inside the value, so it's just pointer arithmetic both ways
+// The pointers keep the provenance of the whole value, so we can hand it back to its owner later
+fn links_of<A: Adapter>(value: NonNull<A::Value>) -> NonNull<Links> {
+    unsafe { value.byte_add(A::LINKS_OFFSET).cast() }
+}
+
+fn value_of<A: Adapter>(links: NonNull<Links>) -> NonNull<A::Value> {
+    unsafe { links.byte_sub(A::LINKS_OFFSET).cast() }
+}
+
+pub struct IntrusiveList<A: Adapter> {
+    front: Link,
+    back: Link,
+    len: usize,
+    id: u64,
+    // We own the pointers of everything that's linked
+    _boo: PhantomData<A::Pointer>,
+}
+
+impl<A: Adapter> IntrusiveList<A> {
+    pub fn new() -> Self {
+        IntrusiveList {
+            front: None,
+            back: None,
+            len: 0,
+            id: NEXT_LIST_ID.fetch_add(1, Relaxed),
+            _boo: PhantomData,
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    // Panics if the element is already in a list (this one or any other)
+    pub fn push_front(&mut self, ptr: A::Pointer) {
+        let links = self.claim(ptr);
+        unsafe { self.link_before(links, self.front) };
+    }
+
+    pub fn push_back(&mut self, ptr: A::Pointer) {
+        let links = self.claim(ptr);
+        unsafe { self.link_before(links, None) };
+    }
+
+    pub fn pop_front(&mut self) -> Option<A::Pointer> {
+        self.front.map(|links| unsafe { self.unlink(links) })
+    }
+
+    pub fn pop_back(&mut self) -> Option<A::Pointer> {
+        self.back.map(|links| unsafe { self.unlink(links) })
+    }
+
+    pub fn front(&self) -> Option<&A::Value> {
+        self.front
+            .map(|links| unsafe { value_of::<A>(links).as_ref() })
+    }
+
+    pub fn back(&self) -> Option<&A::Value> {
+        self.back
+            .map(|links| unsafe { value_of::<A>(links).as_ref() })
+    }
+
+    // Is this exact element linked into this list? O(1)
+    pub fn contains(&self, value: &A::Value) -> bool {
+        self.owns(value).is_some()
+    }
+
+    // Takes the element out, wherever it is, O(1)
+    // None if it isn't in this list
+    pub fn remove(&mut self, value: &A::Value) -> Option<A::Pointer> {
+        let links = self.owns(value)?;
+        Some(unsafe { self.unlink(links) })
+    }
+
+    pub fn clear(&mut self) {
+        while self.pop_front().is_some() {}
+    }
+
+    pub fn iter(&self) -> Iter<'_, A> {
+        Iter {
+            front: self.front,
+            back: self.back,
+            len: self.len,
+            _boo: PhantomData,
+        }
+    }
+
+    // Turn the owner into a raw pointer, and mark the element as ours
+    fn claim(&mut self, ptr: A::Pointer) -> NonNull<Links> {
+        let value = ptr.into_raw();
+        let links = links_of::<A>(value);
+        // Acquire pairs with the Release in unlink, we see the old list's last writes to the links
+        let claimed = unsafe {
+            (*links.as_ptr())
+                .list
+                .compare_exchange(0, self.id, AcqRel, Relaxed)
+        };
+        if claimed.is_err() {
+            // Give the owner back before panicking, so it doesn't leak
+            drop(unsafe { A::Pointer::from_raw(value) });
+            panic!("element is already linked into a list");
+        }
+        links
+    }
+
+    // The element's links, with the provenance of the pointer we got in claim, if it's in our list
+    // value is only a reference, so we go through our neighbours to get to the real pointer
+    fn owns(&self, value: &A::Value) -> Option<NonNull<Links>> {
+        let links = links_of::<A>(NonNull::from(value));
+        unsafe {
+            if (*links.as_ptr()).list.load(Acquire) != self.id {
+                return None;
+            }
+            Some(match (*links.as_ptr()).front.get() {
+                Some(prev) => (*prev.as_ptr()).back.get().unwrap(),
+                None => self.front.unwrap(),
+            })
+        }
+    }
+
+    // Put claimed links right before next, or at the back if there's no next
+    unsafe fn link_before(&mut self, links: NonNull<Links>, next: Link) {
+        unsafe {
+            let prev = match next {
+                Some(next) => (*next.as_ptr()).front.get(),
+                None => self.back,
+            };
+            (*links.as_ptr()).front.set(prev);
+            (*links.as_ptr()).back.set(next);
+            match prev {
+                Some(prev) => (*prev.as_ptr()).back.set(Some(links)),
+                None => self.front = Some(links),
+            }
+            match next {
+                Some(next) => (*next.as_ptr()).front.set(Some(links)),
+                None => self.back = Some(links),
+            }
+            self.len += 1;
+        }
+    }
+
+    // Cut the element out, release it, and hand its owner back
+    unsafe fn unlink(&mut self, links: NonNull<Links>) -> A::Pointer {
+        unsafe {
+            let prev = (*links.as_ptr()).front.take();
+            let next = (*links.as_ptr()).back.take();
+            match prev {
+                Some(prev) => (*prev.as_ptr()).back.set(next),
+                None => self.front = next,
+            }
+            match next {
+                Some(next) => (*next.as_ptr()).front.set(prev),
+                None => self.back = prev,
+            }
+            self.len -= 1;
+            (*links.as_ptr()).list.store(0, Release);
+            A::Pointer::from_raw(value_of::<A>(links))
+        }
+    }
+}
+
+impl<A: Adapter> Default for IntrusiveList<A> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<A: Adapter> Drop for IntrusiveList<A> {
+    fn drop(&mut self) {
+        // Every element gets unlinked, and its owner dropped
+        // So if we were the last one holding it, it goes away too
+        self.clear();
+    }
+}
+
+impl<A: Adapter> Debug for IntrusiveList<A>
+where
+    A::Value: Debug,
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_list().entries(self).finish()
+    }
+}
+
+impl<A: Adapter> Extend<A::Pointer> for IntrusiveList<A> {
+    fn extend<I: IntoIterator<Item = A::Pointer>>(&mut self, iter: I) {
+        for ptr in iter {
+            self.push_back(ptr);
+        }
+    }
+}
+
+impl<A: Adapter> FromIterator<A::Pointer> for IntrusiveList<A> {
+    fn from_iter<I: IntoIterator<Item = A::Pointer>>(iter: I) -> Self {
+        let mut list = Self::new();
+        list.extend(iter);
+        list
+    }
+}
+
+pub struct Iter<'a, A: Adapter> {
+    front: Link,
+    back: Link,
+    len: usize,
+    _boo: PhantomData<&'a A::Value>,
+}
+
+impl<'a, A: Adapter> IntoIterator for &'a IntrusiveList<A> {
+    type IntoIter = Iter<'a, A>;
+    type Item = &'a A::Value;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+impl<'a, A: Adapter> Iterator for Iter<'a, A> {
+    type Item = &'a A::Value;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.len > 0 {
+            self.front.map(|links| unsafe {
+                self.len -= 1;
+                self.front = (*links.as_ptr()).back.get();
+                value_of::<A>(links).as_ref()
+            })
+        } else {
+            None
+        }
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.len, Some(self.len))
+    }
+}
+
+impl<A: Adapter> DoubleEndedIterator for Iter<'_, A> {
+    fn next_back(&mut self) -> Option<Self::Item> {
+        if self.len > 0 {
+            self.back.map(|links| unsafe {
+                self.len -= 1;
+                self.back = (*links.as_ptr()).front.get();
+                value_of::<A>(links).as_ref()
+            })
+        } else {
+            None
+        }
+    }
+}
+
+impl<A: Adapter> ExactSizeIterator for Iter<'_, A> {}
+
+impl<A: Adapter> FusedIterator for Iter<'_, A> {}
+
+pub struct IntoIter<A: Adapter> {
+    list: IntrusiveList<A>,
+}
+
+impl<A: Adapter> IntoIterator for IntrusiveList<A> {
+    type IntoIter = IntoIter<A>;
+    type Item = A::Pointer;
+
+    fn into_iter(self) -> Self::IntoIter {
+        IntoIter { list: self }
+    }
+}
+
+impl<A: Adapter> Iterator for IntoIter<A> {
+    type Item = A::Pointer;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.list.pop_front()
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.list.len, Some(self.list.len))
+    }
+}
+
+impl<A: Adapter> DoubleEndedIterator for IntoIter<A> {
+    fn next_back(&mut self) -> Option<Self::Item> {
+        self.list.pop_back()
+    }
+}
+
+impl<A: Adapter> ExactSizeIterator for IntoIter<A> {}
+
+// Same ghost element semantics as the deque's CursorMut
+// Only hands out &Value, a &mut would let people swap the Links out from under us
+pub struct CursorMut<'a, A: Adapter> {
+    cur: Link,
+    list: &'a mut IntrusiveList<A>,
+    index: Option<usize>,
+}
+
+impl<A: Adapter> IntrusiveList<A> {
+    pub fn cursor_mut(&mut self) -> CursorMut<'_, A> {
+        CursorMut {
+            cur: None,
+            list: self,
+            index: None,
+        }
+    }
+
+    // Cursor sitting on value, None if it isn't in this list
+    // Cursor needs an index, so this walks to the front, O(index)
+    pub fn cursor_mut_from(&mut self, value: &A::Value) -> Option<CursorMut<'_, A>> {
+        let links = self.owns(value)?;
+        let mut index = 0;
+        let mut cur = links;
+        unsafe {
+            while let Some(prev) = (*cur.as_ptr()).front.get() {
+                index += 1;
+                cur = prev;
+            }
+        }
+        Some(CursorMut {
+            cur: Some(links),
+            list: self,
+            index: Some(index),
+        })
+    }
+}
+
+impl<A: Adapter> CursorMut<'_, A> {
+    pub fn index(&self) -> Option<usize> {
+        self.index
+    }
+
+    pub fn move_next(&mut self) {
+        unsafe {
+            match self.cur {
+                Some(cur) => {
+                    self.cur = (*cur.as_ptr()).back.get();
+                    self.index = self.cur.and(self.index.map(|index| index + 1));
+                }
+                None => {
+                    // Ghost goes to the front
+                    self.cur = self.list.front;
+                    self.index = self.cur.and(Some(0));
+                }
+            }
+        }
+    }
+
+    pub fn move_prev(&mut self) {
+        unsafe {
+            match self.cur {
+                Some(cur) => {
+                    self.cur = (*cur.as_ptr()).front.get();
+                    self.index = self.cur.and(self.index.map(|index| index - 1));
+                }
+                None => {
+                    // Ghost goes to the back
+                    self.cur = self.list.back;
+                    self.index = self.cur.and(Some(self.list.len.wrapping_sub(1)));
+                }
+            }
+        }
+    }
+
+    pub fn current(&self) -> Option<&A::Value> {
+        self.cur
+            .map(|links| unsafe { value_of::<A>(links).as_ref() })
+    }
+
+    pub fn peek_next(&self) -> Option<&A::Value> {
+        unsafe {
+            let next = match self.cur {
+                Some(cur) => (*cur.as_ptr()).back.get(),
+                None => self.list.front,
+            };
+            next.map(|links| value_of::<A>(links).as_ref())
+        }
+    }
+
+    pub fn peek_prev(&self) -> Option<&A::Value> {
+        unsafe {
+            let prev = match self.cur {
+                Some(cur) => (*cur.as_ptr()).front.get(),
+                None => self.list.back,
+            };
+            prev.map(|links| value_of::<A>(links).as_ref())
+        }
+    }
+
+    // Panics if the element is already in a list
+    pub fn insert_before(&mut self, ptr: A::Pointer) {
+        let links = self.list.claim(ptr);
+        match self.cur {
+            Some(cur) => unsafe {
+                self.list.link_before(links, Some(cur));
+                *self.index.as_mut().unwrap() += 1;
+            },
+            // Before the ghost is the back of the list
+            None => unsafe { self.list.link_before(links, None) },
+        }
+    }
+
+    pub fn insert_after(&mut self, ptr: A::Pointer) {
+        let links = self.list.claim(ptr);
+        unsafe {
+            let next = match self.cur {
+                Some(cur) => (*cur.as_ptr()).back.get(),
+                // After the ghost is the front of the list
+                None => self.list.front,
+            };
+            self.list.link_before(links, next);
+        }
+    }
+
+    // Takes the current element out, the cursor moves on to the next one
+    pub fn remove_current(&mut self) -> Option<A::Pointer> {
+        let cur = self.cur?;
+        unsafe {
+            self.cur = (*cur.as_ptr()).back.get();
+            if self.cur.is_none() {
+                self.index = None;
+            }
+            Some(self.list.unlink(cur))
+        }
+    }
+}
+
+// We own pointers to the values, so we can go wherever they can
+unsafe impl<A: Adapter> Send for IntrusiveList<A> where A::Pointer: Send {}
+unsafe impl<A: Adapter> Sync for IntrusiveList<A> where A::Pointer: Sync {}
+
+unsafe impl<A: Adapter> Send for Iter<'_, A> where A::Value: Sync {}
+unsafe impl<A: Adapter> Sync for Iter<'_, A> where A::Value: Sync {}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use std::thread;
+
+    #[derive(Debug)]
+    struct Timer {
+        deadline: u32,
+        links: Links,
+    }
+
+    impl Timer {
+        fn new(deadline: u32) -> Self {
+            Timer {
+                deadline,
+                links: Links::new(),
+            }
+        }
+    }
+
+    crate::intrusive_adapter!(BoxTimers = Box<Timer>: Timer { links });
+    crate::intrusive_adapter!(RcTimers = Rc<Timer>: Timer { links });
+    crate::intrusive_adapter!(ArcTimers = Arc<Timer>: Timer { links });
+    crate::intrusive_adapter!(PinTimers<'a> = Pin<&'a mut Timer>: Timer { links });
+
+    fn deadlines<A: Adapter<Value = Timer>>(list: &IntrusiveList<A>) -> Vec<u32> {
+        let forward: Vec<_> = list.iter().map(|timer| timer.deadline).collect();
+        let mut backward: Vec<_> = list.iter().rev().map(|timer| timer.deadline).collect();
+        backward.reverse();
+        assert_eq!(forward, backward);
+        assert_eq!(forward.len(), list.len());
+        forward
+    }
+
+    #[test]
+    fn test_box() {
+        let mut list = IntrusiveList::<BoxTimers>::new();
+        assert!(list.pop_front().is_none());
+        list.push_back(Box::new(Timer::new(2)));
+        list.push_back(Box::new(Timer::new(3)));
+        list.push_front(Box::new(Timer::new(1)));
+        assert_eq!(deadlines(&list), &[1, 2, 3]);
+        assert_eq!(list.front().unwrap().deadline, 1);
+        assert_eq!(list.back().unwrap().deadline, 3);
+
+        let timer = list.pop_front().unwrap();
+        assert_eq!(timer.deadline, 1);
+        assert!(!timer.links.is_linked());
+        assert_eq!(list.pop_back().unwrap().deadline, 3);
+        assert_eq!(deadlines(&list), &[2]);
+
+        // An unlinked element can go into another list
+        let mut other = IntrusiveList::<BoxTimers>::new();
+        other.push_back(timer);
+        assert!(other.front().unwrap().links.is_linked());
+
+        let all: Vec<_> = list
+            .into_iter()
+            .chain(other)
+            .map(|timer| timer.deadline)
+            .collect();
+        assert_eq!(all, &[2, 1]);
+    }
+
+    #[test]
+    fn test_rc_remove() {
+        let timers: Vec<_> = (0..5).map(|i| Rc::new(Timer::new(i))).collect();
+        let mut list: IntrusiveList<RcTimers> = timers.iter().cloned().collect();
+        assert_eq!(Rc::strong_count(&timers[0]), 2);
+
+        // O(1) removal straight from a reference to the element
+        assert!(list.contains(&timers[2]));
+        let removed = list.remove(&timers[2]).unwrap();
+        assert!(Rc::ptr_eq(&removed, &timers[2]));
+        assert!(!list.contains(&timers[2]));
+        assert!(list.remove(&timers[2]).is_none());
+        list.remove(&timers[0]);
+        list.remove(&timers[4]);
+        assert_eq!(deadlines(&list), &[1, 3]);
+
+        // Elements of another list are left alone
+        let mut other = IntrusiveList::<RcTimers>::new();
+        other.push_back(timers[0].clone());
+        assert!(list.remove(&timers[0]).is_none());
+        assert!(other.remove(&timers[1]).is_none());
+        assert_eq!(other.len(), 1);
+
+        // Dropping the lists gives back their strong counts
+        drop(removed);
+        drop(list);
+        drop(other);
+        assert!(timers.iter().all(|timer| Rc::strong_count(timer) == 1));
+        assert!(timers.iter().all(|timer| !timer.links.is_linked()));
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_link_twice() {
+        let timer = Rc::new(Timer::new(1));
+        let mut list = IntrusiveList::<RcTimers>::new();
+        let mut other = IntrusiveList::<RcTimers>::new();
+        list.push_back(timer.clone());
+        other.push_back(timer);
+    }
+
+    #[test]
+    fn test_link_twice_no_leak() {
+        let timer = Rc::new(Timer::new(1));
+        let mut list = IntrusiveList::<RcTimers>::new();
+        list.push_back(timer.clone());
+        let result = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
+            list.push_front(timer.clone())
+        }));
+        assert!(result.is_err());
+        assert_eq!(list.len(), 1);
+        assert_eq!(Rc::strong_count(&timer), 2);
+    }
+
+    #[test]
+    fn test_pin() {
+        let mut a = Timer::new(1);
+        let mut b = Timer::new(2);
+        {
+            let mut list = IntrusiveList::<PinTimers>::new();
+            list.push_back(Pin::new(&mut a));
+            list.push_back(Pin::new(&mut b));
+            assert_eq!(deadlines(&list), &[1, 2]);
+            let popped = list.pop_front().unwrap();
+            assert_eq!(popped.deadline, 1);
+            // Borrow of b ends with the list, which unlinks it on the way out
+        }
+        assert!(!a.links.is_linked());
+        assert!(!b.links.is_linked());
+        b.deadline = 20;
+
+        // A forgotten list can't unlink its elements, they stay marked
+        // Never touched again, but also never allowed into another list
+        let mut list = IntrusiveList::<PinTimers>::new();
+        list.push_back(Pin::new(&mut b));
+        std::mem::forget(list);
+        assert!(b.links.is_linked());
+        let result = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
+            IntrusiveList::<PinTimers>::new().push_back(Pin::new(&mut b))
+        }));
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn test_cursor() {
+        let timers: Vec<_> = (0..6).map(|i| Rc::new(Timer::new(i * 10))).collect();
+        let mut list: IntrusiveList<RcTimers> = timers.iter().cloned().collect();
+
+        let mut cursor = list.cursor_mut();
+        assert!(cursor.current().is_none());
+        assert_eq!(cursor.peek_next().unwrap().deadline, 0);
+        assert_eq!(cursor.peek_prev().unwrap().deadline, 50);
+        cursor.move_prev();
+        assert_eq!(cursor.index(), Some(5));
+        cursor.move_next();
+        assert_eq!(cursor.index(), None);
+
+        let mut cursor = list.cursor_mut_from(&timers[3]).unwrap();
+        assert_eq!(cursor.index(), Some(3));
+        assert_eq!(cursor.current().unwrap().deadline, 30);
+        cursor.insert_before(Rc::new(Timer::new(25)));
+        cursor.insert_after(Rc::new(Timer::new(35)));
+        assert_eq!(cursor.index(), Some(4));
+        assert_eq!(cursor.peek_prev().unwrap().deadline, 25);
+        assert_eq!(cursor.peek_next().unwrap().deadline, 35);
+
+        let removed = cursor.remove_current().unwrap();
+        assert!(Rc::ptr_eq(&removed, &timers[3]));
+        assert_eq!(cursor.current().unwrap().deadline, 35);
+        cursor.move_prev();
+        cursor.move_prev();
+        assert_eq!(cursor.current().unwrap().deadline, 20);
+        assert_eq!(cursor.index(), Some(2));
+
+        // Removing the back puts us on the ghost
+        let mut cursor = list.cursor_mut();
+        cursor.move_prev();
+        assert_eq!(cursor.remove_current().unwrap().deadline, 50);
+        assert_eq!(cursor.index(), None);
+        cursor.insert_after(Rc::new(Timer::new(5)));
+        cursor.insert_before(Rc::new(Timer::new(55)));
+        assert_eq!(deadlines(&list), &[5, 0, 10, 20, 25, 35, 40, 55]);
+
+        let stranger = Timer::new(99);
+        assert!(list.cursor_mut_from(&stranger).is_none());
+    }
+
+    #[test]
+    fn test_arc_threads() {
+        let timers: Vec<_> = (0..100).map(|i| Arc::new(Timer::new(i))).collect();
+
+        // Every thread tries to grab every timer, each one ends up in exactly one list
+        let lists: Vec<IntrusiveList<ArcTimers>> = thread::scope(|s| {
+            let handles: Vec<_> = (0..4)
+                .map(|_| {
+                    s.spawn(|| {
+                        let mut list = IntrusiveList::<ArcTimers>::new();
+                        for timer in &timers {
+                            if !timer.links.is_linked() {
+                                let _ =
+                                    std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
+                                        list.push_back(timer.clone())
+                                    }));
+                            }
+                        }
+                        list
+                    })
+                })
+                .collect();
+            handles.into_iter().map(|h| h.join().unwrap()).collect()
+        });
+
+        assert_eq!(lists.iter().map(IntrusiveList::len).sum::<usize>(), 100);
+        let mut all: Vec<_> = lists.iter().flat_map(deadlines).collect();
+        all.sort();
+        assert_eq!(all, (0..100).collect::<Vec<_>>());
+
+        // Lists can go to other threads too
+        let total: u32 = thread::scope(|s| {
+            lists
+                .into_iter()
+                .map(|list| s.spawn(move || list.iter().map(|timer| timer.deadline).sum::<u32>()))
+                .collect::<Vec<_>>()
+                .into_iter()
+                .map(|h| h.join().unwrap())
+                .sum()
+        });
+        assert_eq!(total, (0..100).sum());
+        assert!(timers.iter().all(|timer| Arc::strong_count(timer) == 1));
+    }
+
+    #[allow(dead_code)]
+    fn assert_properties() {
+        fn is_send<T: Send>() {}
+        fn is_sync<T: Sync>() {}
+
+        is_send::<IntrusiveList<BoxTimers>>();
+        is_sync::<IntrusiveList<BoxTimers>>();
+        is_send::<IntrusiveList<ArcTimers>>();
+        is_sync::<IntrusiveList<ArcTimers>>();
+        is_send::<Iter<ArcTimers>>();
+    }
+}
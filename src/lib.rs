@@ -1,6 +1,16 @@
 pub mod bad_safe_deque;
 pub mod bad_single_linked_list;
+#[cfg(any(test, feature = "diagnostics"))]
+pub mod diagnostics;
+pub mod ghost_cell;
+pub mod ghost_list;
+pub mod intrusive;
+pub mod locked_deque;
 pub mod ok_single_linked_list;
 pub mod ok_unsafe_singly_linked_queue;
 pub mod persistent_linked_list;
 pub mod production_unsafe_deque;
+mod slab;
+pub mod slab_deque;
+pub mod treiber_stack;
+pub mod unrolled_list;
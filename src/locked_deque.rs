@@ -0,0 +1,769 @@
+use std::sync::{
+    Arc, Mutex, MutexGuard, PoisonError, Weak,
+    atomic::{AtomicUsize, Ordering::Relaxed},
+};
+
+/*
+    bad_safe_deque, but for threads
+    Rc -> Arc, RefCell -> Mutex, same Weak back links so there are no cycles
+    Every node has its own lock, so threads working on different parts of the list don't wait on each other
+
+    Two sentinel nodes, head and tail, that are always there and never hold an element
+    So every real node always has a node on both sides, and there are no special cases for the ends
+    The tail sentinel doubles as the cursors' ghost element
+
+    Deadlocks are avoided by always locking left to right, prev before node before next
+    Anything that has to go right to left (like finding a node's prev) reads the link, lets go,
+    locks the nodes in the right order, and checks nothing changed in between, if it did, try again
+*/
+
+type NodeRef<T> = Arc<Mutex<Node<T>>>;
+type WeakRef<T> = Weak<Mutex<Node<T>>>;
+
+struct Node<T> {
+    // None in the sentinels, and in nodes that were removed
+    elem: Option<T>,
+    next: Option<NodeRef<T>>,
+    prev: Option<WeakRef<T>>,
+    // Set when the node leaves the list, under the locks of it and both its neighbours
+    // A removed node keeps its next, so a cursor that was sitting on it can still find its way back
+    // That next can get removed too, and keep its own next, so a parked cursor can hold a long chain
+    removed: bool,
+}
+
+pub struct List<T> {
+    head: NodeRef<T>,
+    tail: NodeRef<T>,
+    len: AtomicUsize,
+}
+
+// A panic while holding a lock can only come from a closure given to Cursor::with_current,
+// which can only touch the element, never the links, so the list is still fine, carry on
+// That closure runs with the cursor's node locked, and Mutex isn't reentrant, so any list operation
+// from inside it that needs that node (pushes or pops next to it, moving a cursor over it, to_vec) deadlocks
+fn lock<T>(node: &NodeRef<T>) -> MutexGuard<'_, Node<T>> {
+    node.lock().unwrap_or_else(PoisonError::into_inner)
+}
+
+fn is<T>(link: &Option<NodeRef<T>>, node: &NodeRef<T>) -> bool {
+    link.as_ref().is_some_and(|link| Arc::ptr_eq(link, node))
+}
+
+// Let go of a node that may be the start of a chain of removed nodes
+// Dropping the Arc would drop the chain recursively, and blow the stack if it's long, so one by one
+// Stops at the first node somebody else still has, every live node is held by the one before it
+fn release<T>(node: NodeRef<T>) {
+    let mut cur = Some(node);
+    while let Some(node) = cur {
+        cur = Arc::try_unwrap(node).ok().and_then(|node| {
+            node.into_inner()
+                .unwrap_or_else(PoisonError::into_inner)
+                .next
+        });
+    }
+}
+
+impl<T> List<T> {
+    pub fn new() -> Self {
+        let tail = Arc::new(Mutex::new(Node {
+            elem: None,
+            next: None,
+            prev: None,
+            removed: false,
+        }));
+        let head = Arc::new(Mutex::new(Node {
+            elem: None,
+            next: Some(tail.clone()),
+            prev: None,
+            removed: false,
+        }));
+        lock(&tail).prev = Some(Arc::downgrade(&head));
+        List {
+            head,
+            tail,
+            len: AtomicUsize::new(0),
+        }
+    }
+
+    // Only a snapshot, other threads may change it right after
+    pub fn len(&self) -> usize {
+        self.len.load(Relaxed)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    pub fn push_front(&self, elem: T) {
+        if self.link_after(&self.head, None, elem).is_err() {
+            unreachable!("sentinels are never removed");
+        }
+    }
+
+    pub fn push_back(&self, elem: T) {
+        if self.link_before(&self.tail, elem).is_err() {
+            unreachable!("sentinels are never removed");
+        }
+    }
+
+    pub fn pop_front(&self) -> Option<T> {
+        loop {
+            let first = lock(&self.head).next.clone().unwrap();
+            if Arc::ptr_eq(&first, &self.tail) {
+                return None;
+            }
+            // Somebody else might take it first, then just go for the new first one
+            if let Some(elem) = self.unlink(&first) {
+                return Some(elem);
+            }
+        }
+    }
+
+    pub fn pop_back(&self) -> Option<T> {
+        loop {
+            // Can't upgrade if the node is being freed, it's on its way out, look again
+            let Some(last) = lock(&self.tail).prev.as_ref().and_then(Weak::upgrade) else {
+                continue;
+            };
+            if Arc::ptr_eq(&last, &self.head) {
+                return None;
+            }
+            if let Some(elem) = self.unlink(&last) {
+                return Some(elem);
+            }
+        }
+    }
+
+    // Everything in order, each node locked only while we read it
+    // Not atomic, other threads can get stuff in and out while we're walking
+    pub fn to_vec(&self) -> Vec<T>
+    where
+        T: Clone,
+    {
+        let mut elems = Vec::new();
+        let mut cur = lock(&self.head).next.clone().unwrap();
+        while !Arc::ptr_eq(&cur, &self.tail) {
+            let next = {
+                let node = lock(&cur);
+                // Removed from under us, its elem is gone, but next still leads back in
+                elems.extend(node.elem.clone());
+                node.next.clone().unwrap()
+            };
+            cur = next;
+        }
+        elems
+    }
+
+    // New node for elem right after prev, gives elem back if prev isn't in the list anymore
+    // or, when we know what next should be, if something else is after prev by now
+    fn link_after(
+        &self,
+        prev: &NodeRef<T>,
+        expected: Option<&NodeRef<T>>,
+        elem: T,
+    ) -> Result<NodeRef<T>, T> {
+        let mut prev_node = lock(prev);
+        if prev_node.removed || Arc::ptr_eq(prev, &self.tail) {
+            return Err(elem);
+        }
+        if expected.is_some_and(|next| !is(&prev_node.next, next)) {
+            return Err(elem);
+        }
+        let next = prev_node.next.clone().unwrap();
+        let mut next_node = lock(&next);
+        let node = Arc::new(Mutex::new(Node {
+            elem: Some(elem),
+            next: Some(next.clone()),
+            prev: Some(Arc::downgrade(prev)),
+            removed: false,
+        }));
+        next_node.prev = Some(Arc::downgrade(&node));
+        prev_node.next = Some(node.clone());
+        self.len.fetch_add(1, Relaxed);
+        Ok(node)
+    }
+
+    // Same, but right before next, which means finding out what's before it first
+    fn link_before(&self, next: &NodeRef<T>, mut elem: T) -> Result<NodeRef<T>, T> {
+        loop {
+            let prev = {
+                let next_node = lock(next);
+                if next_node.removed || Arc::ptr_eq(next, &self.head) {
+                    return Err(elem);
+                }
+                next_node.prev.as_ref().and_then(Weak::upgrade)
+            };
+            let Some(prev) = prev else {
+                continue;
+            };
+            // Between reading next's prev and locking it, either one could have changed,
+            // link_after checks prev is still right before next, if not, look again
+            match self.link_after(&prev, Some(next), elem) {
+                Ok(node) => return Ok(node),
+                Err(back) => elem = back,
+            }
+        }
+    }
+
+    // Take node out of the list, None if somebody else already did
+    // Locks prev, node and next, in that order
+    fn unlink(&self, node: &NodeRef<T>) -> Option<T> {
+        loop {
+            let prev = {
+                let node = lock(node);
+                if node.removed {
+                    return None;
+                }
+                node.prev.as_ref().and_then(Weak::upgrade)
+            };
+            let Some(prev) = prev else {
+                continue;
+            };
+            let mut prev_node = lock(&prev);
+            if prev_node.removed || !is(&prev_node.next, node) {
+                continue;
+            }
+            let mut node = lock(node);
+            if node.removed {
+                return None;
+            }
+            let next = node.next.clone().unwrap();
+            let mut next_node = lock(&next);
+            next_node.prev = Some(Arc::downgrade(&prev));
+            prev_node.next = Some(next.clone());
+            node.removed = true;
+            self.len.fetch_sub(1, Relaxed);
+            return node.elem.take();
+        }
+    }
+}
+
+impl<T> Default for List<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T> Drop for List<T> {
+    fn drop(&mut self) {
+        // Nobody else can be using the list now, so every node is ours alone
+        // Unlink them one by one instead of letting the Arcs drop each other recursively
+        let mut cur = lock(&self.head).next.take();
+        while let Some(node) = cur {
+            cur = match Arc::try_unwrap(node) {
+                Ok(node) => {
+                    node.into_inner()
+                        .unwrap_or_else(PoisonError::into_inner)
+                        .next
+                }
+                // Only the tail sentinel, we still have it
+                Err(_) => None,
+            };
+        }
+    }
+}
+
+// Cursor that many threads can have at once, each one editing as it goes
+// It holds an Arc to the node it's on, and locks it (and its neighbours) only during each call
+// Other threads can remove the node from under it, then
+// - with_current gives None, and inserting next to it gives the element back
+// - move_next/move_prev still work, and go to where the node used to be
+// The tail sentinel is the ghost, same semantics as the other deques' cursors
+pub struct Cursor<'a, T> {
+    list: &'a List<T>,
+    cur: NodeRef<T>,
+}
+
+impl<T> List<T> {
+    // Starts on the ghost
+    pub fn cursor(&self) -> Cursor<'_, T> {
+        Cursor {
+            list: self,
+            cur: self.tail.clone(),
+        }
+    }
+
+    pub fn cursor_front(&self) -> Cursor<'_, T> {
+        let mut cursor = self.cursor();
+        cursor.move_next();
+        cursor
+    }
+
+    pub fn cursor_back(&self) -> Cursor<'_, T> {
+        let mut cursor = self.cursor();
+        cursor.move_prev();
+        cursor
+    }
+}
+
+impl<T> Cursor<'_, T> {
+    pub fn is_ghost(&self) -> bool {
+        Arc::ptr_eq(&self.cur, &self.list.tail)
+    }
+
+    // Whatever was after us, while we had our node locked
+    // If our node was already removed, keep going until a node that isn't
+    pub fn move_next(&mut self) {
+        let from = if self.is_ghost() {
+            &self.list.head
+        } else {
+            &self.cur
+        };
+        let mut next = lock(from).next.clone().unwrap();
+        loop {
+            let skip = {
+                let node = lock(&next);
+                node.removed.then(|| node.next.clone().unwrap())
+            };
+            match skip {
+                Some(skip) => next = skip,
+                None => break,
+            }
+        }
+        self.set_cur(next);
+    }
+
+    // Hand over hand, the node before us has to still point at us once it's locked
+    pub fn move_prev(&mut self) {
+        loop {
+            let prev = {
+                let node = lock(&self.cur);
+                if node.removed {
+                    // Back to where we were first, then look behind that
+                    drop(node);
+                    self.move_next();
+                    continue;
+                }
+                node.prev.as_ref().and_then(Weak::upgrade)
+            };
+            let Some(prev) = prev else {
+                continue;
+            };
+            if Arc::ptr_eq(&prev, &self.list.head) {
+                self.set_cur(self.list.tail.clone());
+                return;
+            }
+            let still_prev = {
+                let prev_node = lock(&prev);
+                !prev_node.removed && is(&prev_node.next, &self.cur)
+            };
+            if still_prev {
+                self.set_cur(prev);
+                return;
+            }
+        }
+    }
+
+    // The node we leave might have been removed, and be holding a chain of other removed nodes
+    fn set_cur(&mut self, node: NodeRef<T>) {
+        release(std::mem::replace(&mut self.cur, node));
+    }
+
+    // The element, locked for the duration of f, None on the ghost or if it's been removed
+    // Don't touch the list from inside f, anything that locks this node waits for f forever, see lock
+    pub fn with_current<R>(&self, f: impl FnOnce(&mut T) -> R) -> Option<R> {
+        lock(&self.cur).elem.as_mut().map(f)
+    }
+
+    pub fn current(&self) -> Option<T>
+    where
+        T: Clone,
+    {
+        self.with_current(|elem| elem.clone())
+    }
+
+    // Before the ghost is the back of the list
+    // Gives elem back if our node has been removed by someone else
+    pub fn insert_before(&mut self, elem: T) -> Result<(), T> {
+        self.list.link_before(&self.cur, elem).map(|_| ())
+    }
+
+    // After the ghost is the front of the list
+    pub fn insert_after(&mut self, elem: T) -> Result<(), T> {
+        let prev = if self.is_ghost() {
+            &self.list.head
+        } else {
+            &self.cur
+        };
+        self.list.link_after(prev, None, elem).map(|_| ())
+    }
+
+    // Takes the current element out, the cursor moves on to the next one
+    // None on the ghost, or if somebody else got to it first (the cursor still moves on)
+    pub fn remove_current(&mut self) -> Option<T> {
+        if self.is_ghost() {
+            return None;
+        }
+        let elem = self.list.unlink(&self.cur);
+        self.move_next();
+        elem
+    }
+}
+
+impl<T> Drop for Cursor<'_, T> {
+    fn drop(&mut self) {
+        let ghost = self.list.tail.clone();
+        self.set_cur(ghost);
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use std::thread;
+
+    // Check every link both ways, and that len adds up, only when no other thread is touching it
+    fn check_links<T: Clone>(list: &List<T>) -> Vec<T> {
+        let mut len = 0;
+        let mut prev = list.head.clone();
+        loop {
+            let next = lock(&prev).next.clone().unwrap();
+            let next_node = lock(&next);
+            assert!(!next_node.removed);
+            let back = next_node.prev.as_ref().and_then(Weak::upgrade).unwrap();
+            assert!(Arc::ptr_eq(&back, &prev));
+            if Arc::ptr_eq(&next, &list.tail) {
+                break;
+            }
+            assert!(next_node.elem.is_some());
+            drop(next_node);
+            len += 1;
+            prev = next;
+        }
+        assert_eq!(len, list.len());
+
+        let elems = list.to_vec();
+        let mut backward = Vec::new();
+        let mut cursor = list.cursor_back();
+        while let Some(elem) = cursor.current() {
+            backward.push(elem);
+            cursor.move_prev();
+        }
+        assert_eq!(backward.len(), elems.len());
+        elems
+    }
+
+    #[test]
+    fn basics() {
+        let list = List::new();
+        assert_eq!(list.pop_front(), None);
+        assert_eq!(list.pop_back(), None);
+
+        list.push_back(2);
+        list.push_back(3);
+        list.push_front(1);
+        assert_eq!(check_links(&list), &[1, 2, 3]);
+        assert_eq!(list.pop_back(), Some(3));
+        assert_eq!(list.pop_front(), Some(1));
+        assert_eq!(list.pop_front(), Some(2));
+        assert_eq!(list.pop_back(), None);
+        assert!(list.is_empty());
+        assert_eq!(check_links(&list), &[] as &[i32]);
+    }
+
+    #[test]
+    fn cursor() {
+        let list = List::new();
+        for i in 1..=5 {
+            list.push_back(i);
+        }
+
+        let mut cursor = list.cursor();
+        assert!(cursor.is_ghost());
+        assert_eq!(cursor.current(), None);
+        cursor.move_next();
+        assert_eq!(cursor.current(), Some(1));
+        cursor.move_prev();
+        assert!(cursor.is_ghost());
+        cursor.move_prev();
+        assert_eq!(cursor.current(), Some(5));
+
+        let mut cursor = list.cursor_front();
+        cursor.move_next();
+        assert_eq!(
+            cursor.with_current(|elem| std::mem::replace(elem, 20)),
+            Some(2)
+        );
+        cursor.insert_before(10).unwrap();
+        cursor.insert_after(30).unwrap();
+        assert_eq!(cursor.remove_current(), Some(20));
+        assert_eq!(cursor.current(), Some(30));
+        cursor.move_prev();
+        assert_eq!(cursor.current(), Some(10));
+        assert_eq!(check_links(&list), &[1, 10, 30, 3, 4, 5]);
+
+        // Ghost inserts at the ends
+        let mut cursor = list.cursor();
+        cursor.insert_after(0).unwrap();
+        cursor.insert_before(6).unwrap();
+        assert_eq!(cursor.remove_current(), None);
+        let mut cursor = list.cursor_back();
+        assert_eq!(cursor.remove_current(), Some(6));
+        assert!(cursor.is_ghost());
+        assert_eq!(check_links(&list), &[0, 1, 10, 30, 3, 4, 5]);
+    }
+
+    #[test]
+    fn cursor_on_removed_node() {
+        let list = List::new();
+        for i in 1..=4 {
+            list.push_back(i);
+        }
+        let mut a = list.cursor_front();
+        a.move_next();
+        let mut b = list.cursor_front();
+        b.move_next();
+
+        // b takes 2 out from under a
+        assert_eq!(b.remove_current(), Some(2));
+        assert_eq!(b.current(), Some(3));
+        assert_eq!(a.current(), None);
+        assert_eq!(a.remove_current(), None);
+        assert_eq!(a.current(), Some(3));
+        a.move_prev();
+        assert_eq!(a.current(), Some(1));
+
+        let mut a = list.cursor_front();
+        a.move_next();
+        assert_eq!(b.remove_current(), Some(3));
+        // Gives the element back instead of linking it next to a node that's not in the list
+        assert_eq!(a.insert_after(7), Err(7));
+        assert_eq!(a.insert_before(7), Err(7));
+        a.move_prev();
+        assert_eq!(a.current(), Some(1));
+        b.move_next();
+        assert!(b.is_ghost());
+        assert_eq!(check_links(&list), &[1, 4]);
+    }
+
+    #[test]
+    fn cursor_holding_long_removed_chain() {
+        // The cursor's node keeps every node popped after it alive, through their nexts
+        let list = List::new();
+        for i in 0..300_000 {
+            list.push_back(i);
+        }
+        let cursor = list.cursor_front();
+        while list.pop_front().is_some() {}
+        drop(cursor);
+
+        // Same, but moving off the node lets go of the chain
+        for i in 0..300_000 {
+            list.push_back(i);
+        }
+        let mut cursor = list.cursor_front();
+        while list.pop_front().is_some() {}
+        list.push_back(1);
+        cursor.move_next();
+        assert!(cursor.is_ghost());
+        cursor.move_next();
+        assert_eq!(cursor.current(), Some(1));
+        assert_eq!(check_links(&list), &[1]);
+    }
+
+    #[test]
+    fn concurrent_push() {
+        let list = List::new();
+        thread::scope(|s| {
+            for t in 0..4 {
+                let list = &list;
+                s.spawn(move || {
+                    for i in 0..1000 {
+                        if t % 2 == 0 {
+                            list.push_back((t, i));
+                        } else {
+                            list.push_front((t, i));
+                        }
+                    }
+                });
+            }
+        });
+
+        let elems = check_links(&list);
+        assert_eq!(elems.len(), 4000);
+        // Every thread's pushes show up in the order it made them
+        for t in 0..4 {
+            let mine: Vec<_> = elems
+                .iter()
+                .filter(|(from, _)| *from == t)
+                .map(|&(_, i)| i)
+                .collect();
+            let expected: Vec<_> = if t % 2 == 0 {
+                (0..1000).collect()
+            } else {
+                (0..1000).rev().collect()
+            };
+            assert_eq!(mine, expected);
+        }
+    }
+
+    #[test]
+    fn concurrent_queue() {
+        // Two producers at the back, two consumers at the front, every element comes out exactly once
+        // and each consumer sees each producer's elements in the order they went in
+        let list = List::new();
+        let consumed = AtomicUsize::new(0);
+        let results: Vec<Vec<(usize, usize)>> = thread::scope(|s| {
+            for t in 0..2 {
+                let list = &list;
+                s.spawn(move || {
+                    for i in 0..2000 {
+                        list.push_back((t, i));
+                    }
+                });
+            }
+            let consumers: Vec<_> = (0..2)
+                .map(|_| {
+                    s.spawn(|| {
+                        let mut got = Vec::new();
+                        while consumed.load(Relaxed) < 4000 {
+                            if let Some(elem) = list.pop_front() {
+                                consumed.fetch_add(1, Relaxed);
+                                got.push(elem);
+                            }
+                        }
+                        got
+                    })
+                })
+                .collect();
+            consumers.into_iter().map(|h| h.join().unwrap()).collect()
+        });
+
+        for got in &results {
+            for t in 0..2 {
+                let mine: Vec<_> = got
+                    .iter()
+                    .filter(|(from, _)| *from == t)
+                    .map(|&(_, i)| i)
+                    .collect();
+                assert!(mine.windows(2).all(|w| w[0] < w[1]));
+            }
+        }
+        let mut all: Vec<_> = results.concat();
+        all.sort();
+        let expected: Vec<_> = (0..2)
+            .flat_map(|t| (0..2000).map(move |i| (t, i)))
+            .collect();
+        assert_eq!(all, expected);
+        assert!(list.is_empty());
+    }
+
+    #[test]
+    fn concurrent_cursors() {
+        // Walkers take out the odd numbers mid list and put a sentinel after each even one,
+        // while others pop at both ends, nobody deadlocks, and the list stays whole
+        // Thread t's sentinel for i is (t + 1) * 10_000 + i, even, so no walker takes it out again
+        const N: usize = 2000;
+        let list = List::new();
+        for i in 0..N {
+            list.push_back(i);
+        }
+        let (walked, popped) = thread::scope(|s| {
+            let walkers: Vec<_> = (0..3)
+                .map(|t| {
+                    let list = &list;
+                    s.spawn(move || {
+                        let (mut inserted, mut removed) = (Vec::new(), Vec::new());
+                        let mut cursor = list.cursor_front();
+                        while !cursor.is_ghost() {
+                            match cursor.current() {
+                                Some(i) if i < N && i % 2 == 1 => {
+                                    removed.extend(cursor.remove_current());
+                                }
+                                Some(i) if i < N => {
+                                    let sentinel = (t + 1) * 10_000 + i;
+                                    if cursor.insert_after(sentinel).is_ok() {
+                                        inserted.push(sentinel);
+                                    }
+                                    cursor.move_next();
+                                }
+                                _ => cursor.move_next(),
+                            }
+                        }
+                        (inserted, removed)
+                    })
+                })
+                .collect();
+            for _ in 0..3 {
+                s.spawn(|| {
+                    let mut cursor = list.cursor_back();
+                    for _ in 0..500 {
+                        cursor.move_prev();
+                        if cursor.is_ghost() {
+                            break;
+                        }
+                    }
+                });
+            }
+            let poppers: Vec<_> = (0..2)
+                .map(|t| {
+                    let list = &list;
+                    s.spawn(move || {
+                        (0..100)
+                            .filter_map(|_| {
+                                if t == 0 {
+                                    list.pop_front()
+                                } else {
+                                    list.pop_back()
+                                }
+                            })
+                            .collect::<Vec<_>>()
+                    })
+                })
+                .collect();
+            let walked: Vec<_> = walkers.into_iter().map(|h| h.join().unwrap()).collect();
+            let popped: Vec<_> = poppers
+                .into_iter()
+                .flat_map(|h| h.join().unwrap())
+                .collect();
+            (walked, popped)
+        });
+
+        let elems = check_links(&list);
+        assert_eq!(popped.len(), 200);
+
+        // Exactly what went in, minus exactly what came out, nothing lost or duplicated
+        let mut went_in: Vec<_> = (0..N).collect();
+        let mut came_out = elems.clone();
+        came_out.extend(&popped);
+        for (inserted, removed) in &walked {
+            went_in.extend(inserted);
+            came_out.extend(removed);
+        }
+        went_in.sort();
+        came_out.sort();
+        assert_eq!(came_out, went_in);
+
+        // Every walker went past every odd number still there, so there are none left
+        assert!(elems.iter().all(|&i| i >= N || i % 2 == 0));
+        // And each sentinel sits after the even number it was put next to, unless that got popped
+        let mut last = None;
+        for &i in &elems {
+            if i < N {
+                last = Some(i);
+            } else {
+                let of = i % 10_000;
+                assert!(last == Some(of) || popped.contains(&of));
+            }
+        }
+    }
+
+    #[test]
+    fn drop_long_list() {
+        let list = List::new();
+        for i in 0..200_000 {
+            list.push_back(i);
+        }
+        drop(list);
+    }
+
+    #[allow(dead_code)]
+    fn assert_properties() {
+        fn is_send<T: Send>() {}
+        fn is_sync<T: Sync>() {}
+
+        is_send::<List<i32>>();
+        is_sync::<List<i32>>();
+        is_send::<Cursor<i32>>();
+    }
+}
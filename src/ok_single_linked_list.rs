@@ -1,9 +1,16 @@
+use std::cmp::Ordering;
+use std::fmt;
+use std::hash::{Hash, Hasher};
+use std::iter::FusedIterator;
+
 // T -> generic parameter
 // More or less template arguments from C++, more constraints, but easier to work with
 // in Rust if we want to use T objects in any way, we need to specify their constraints/traits
 // like T: Display, like concepts in C++, but we can only use the methods from concepts
 pub struct List<T> {
     head: Link<T>,
+    // Counting as we go, otherwise len would have to walk the whole list
+    len: usize,
 }
 
 // Previous Link was just a bad implementation of Option
@@ -17,7 +24,7 @@ struct Node<T> {
 
 impl<T> List<T> {
     pub fn new() -> Self {
-        List { head: None }
+        List { head: None, len: 0 }
     }
 
     pub fn push(&mut self, elem: T) {
@@ -29,6 +36,7 @@ impl<T> List<T> {
         });
 
         self.head = Some(new_node);
+        self.len += 1;
     }
 
     pub fn pop(&mut self) -> Option<T> {
@@ -39,6 +47,7 @@ impl<T> List<T> {
         // without specifying how we want to capture it for now
         self.head.take().map(|node| {
             self.head = node.next;
+            self.len -= 1;
             node.elem
         })
     }
@@ -55,8 +64,18 @@ impl<T> List<T> {
         self.head.as_mut().map(|node| &mut node.elem)
     }
 
-    pub fn into_iter(self) -> IntoIter<T> {
-        IntoIter(self)
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+}
+
+impl<T> Default for List<T> {
+    fn default() -> Self {
+        Self::new()
     }
 }
 
@@ -86,6 +105,15 @@ impl<T> Drop for List<T> {
 // into_iter that uses IntoIter lives above but Rust sees it anyway
 pub struct IntoIter<T>(List<T>);
 
+impl<T> IntoIterator for List<T> {
+    type IntoIter = IntoIter<T>;
+    type Item = T;
+
+    fn into_iter(self) -> Self::IntoIter {
+        IntoIter(self)
+    }
+}
+
 // Into Iter consumes the List as it iterates over it
 impl<T> Iterator for IntoIter<T> {
     type Item = T;
@@ -93,8 +121,19 @@ impl<T> Iterator for IntoIter<T> {
         // access fields of a tuple struct numerically
         self.0.pop()
     }
+
+    // Lets collect and friends allocate the right amount up front
+    // Has to be exact, ExactSizeIterator relies on it
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.0.len, Some(self.0.len))
+    }
 }
 
+impl<T> ExactSizeIterator for IntoIter<T> {}
+
+// Once it returned None, it will keep returning None, some adapters can skip their own checks then
+impl<T> FusedIterator for IntoIter<T> {}
+
 // Iter doesn't consume the List, only iterates over it
 // Harder as we need to track lifetime of everything now
 // The 'a template/generic paremeter is lifetime specifier
@@ -102,22 +141,34 @@ impl<T> Iterator for IntoIter<T> {
 // We need compiler to track how long it needs to live
 pub struct Iter<'a, T> {
     next: Option<&'a Node<T>>,
+    len: usize,
 }
 
 // Multiple imple  blocks for single struct allowed as well
 impl<T> List<T> {
     // No explicit lifetime here, due to lifetime elision this is equivalent to
     // fn iter<'a>(&'a self) -> Iter<'a, T>
-    pub fn iter(&self) -> Iter<T> {
+    pub fn iter(&self) -> Iter<'_, T> {
         Iter {
             // Equivalent to map(|node| &**node)
             // Or self.next = node.next.as_ref().map::<&Node<T>, _>(|node| &node);
             // ::<> is called turbofish, which is somehow worse syntax then C++ has
             next: self.head.as_deref(),
+            len: self.len,
         }
     }
 }
 
+// So that for x in &list works, for loop calls into_iter on whatever it gets
+impl<'a, T> IntoIterator for &'a List<T> {
+    type IntoIter = Iter<'a, T>;
+    type Item = &'a T;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
 // Lifetime needed here
 impl<'a, T> Iterator for Iter<'a, T> {
     type Item = &'a T;
@@ -126,25 +177,55 @@ impl<'a, T> Iterator for Iter<'a, T> {
         self.next.take().map(|node| {
             // Equivalent to map(|node| &mut**node)
             self.next = node.next.as_deref();
+            self.len -= 1;
             &node.elem
         })
     }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.len, Some(self.len))
+    }
+}
+
+impl<T> ExactSizeIterator for Iter<'_, T> {}
+
+impl<T> FusedIterator for Iter<'_, T> {}
+
+// Iter only holds shared references, so copying it is fine, unlike IterMut
+impl<T> Clone for Iter<'_, T> {
+    fn clone(&self) -> Self {
+        Iter {
+            next: self.next,
+            len: self.len,
+        }
+    }
 }
 
 // In general IterMut is way harder then IntoIter/Iter
 // because shared/mutable references only allow one reference to exist
 pub struct IterMut<'a, T> {
     next: Option<&'a mut Node<T>>,
+    len: usize,
 }
 
 impl<T> List<T> {
     pub fn iter_mut(&mut self) -> IterMut<'_, T> {
         IterMut {
             next: self.head.as_deref_mut(),
+            len: self.len,
         }
     }
 }
 
+impl<'a, T> IntoIterator for &'a mut List<T> {
+    type IntoIter = IterMut<'a, T>;
+    type Item = &'a mut T;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter_mut()
+    }
+}
+
 impl<'a, T> Iterator for IterMut<'a, T> {
     type Item = &'a mut T;
 
@@ -153,12 +234,317 @@ impl<'a, T> Iterator for IterMut<'a, T> {
         // With things like take
         self.next.take().map(|node| {
             self.next = node.next.as_deref_mut();
+            self.len -= 1;
             &mut node.elem
         })
     }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.len, Some(self.len))
+    }
+}
+
+impl<T> ExactSizeIterator for IterMut<'_, T> {}
+
+impl<T> FusedIterator for IterMut<'_, T> {}
+
+// Std traits, so the list works with collect, ==, {:?}, sets and maps
+// Most of them just go through iter
+
+// Collecting keeps the order, so list.iter().cloned().collect() gives the same list back
+// Same as new plus extend, like for production_unsafe_deque::LinkedList
+impl<T> FromIterator<T> for List<T> {
+    fn from_iter<I: IntoIterator<Item = T>>(iter: I) -> Self {
+        let mut list = List::new();
+        list.extend(iter);
+        list
+    }
+}
+
+// Extend adds at the bottom, in order, like Vec::extend
+// Pushing would reverse it, so we walk to the last next link once, then keep a reference to it
+impl<T> Extend<T> for List<T> {
+    fn extend<I: IntoIterator<Item = T>>(&mut self, iter: I) {
+        let mut tail = nth_link(&mut self.head, self.len);
+        for elem in iter {
+            // insert puts the value in and gives us a reference to it, so we can move on to its next
+            let node = tail.insert(Box::new(Node { elem, next: None }));
+            tail = &mut node.next;
+            // Borrowing head and len at the same time is fine, they're different fields
+            self.len += 1;
+        }
+    }
+}
+
+impl<T: Clone> Clone for List<T> {
+    fn clone(&self) -> Self {
+        self.iter().cloned().collect()
+    }
+}
+
+impl<T: fmt::Debug> fmt::Debug for List<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_list().entries(self).finish()
+    }
+}
+
+impl<T: PartialEq> PartialEq for List<T> {
+    fn eq(&self, other: &Self) -> bool {
+        // Different lengths can't be equal, and now that len is O(1) it's a free check
+        self.len == other.len && self.iter().eq(other)
+    }
+}
+
+impl<T: Eq> Eq for List<T> {}
+
+// Compared like strings, element by element from the top, shorter first if one is a prefix of the other
+impl<T: PartialOrd> PartialOrd for List<T> {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        self.iter().partial_cmp(other)
+    }
+}
+
+impl<T: Ord> Ord for List<T> {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.iter().cmp(other)
+    }
+}
+
+impl<T: Hash> Hash for List<T> {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        // Length first, so [[1], [2]] and [[1, 2]] don't hash the same way, same as std does
+        self.len.hash(state);
+        for elem in self {
+            elem.hash(state);
+        }
+    }
+}
+
+// Editing in the middle, by moving Boxes from one link to another
+// Nothing gets allocated or copied, only the next pointers change
+
+// The link n nodes down from link, n = 0 is link itself
+// Panics if the list runs out first
+fn nth_link<T>(mut link: &mut Link<T>, n: usize) -> &mut Link<T> {
+    for _ in 0..n {
+        link = &mut link.as_mut().expect("list is shorter than its len").next;
+    }
+    link
+}
+
+impl<T> List<T> {
+    // Top becomes bottom, every next pointer flipped on the way
+    pub fn reverse(&mut self) {
+        let mut reversed = None;
+        let mut cur = self.head.take();
+        while let Some(mut node) = cur {
+            cur = node.next.take();
+            node.next = reversed;
+            reversed = Some(node);
+        }
+        self.head = reversed;
+    }
+
+    // Everything from index at down, self keeps the first at
+    // Panics if at > len, same as Vec::split_off
+    pub fn split_off(&mut self, at: usize) -> List<T> {
+        assert!(
+            at <= self.len,
+            "split index (is {at}) should be <= len (is {})",
+            self.len
+        );
+        let head = nth_link(&mut self.head, at).take();
+        let len = self.len - at;
+        self.len = at;
+        List { head, len }
+    }
+
+    // Moves all of other below our last element, other ends up empty
+    // Has to walk to our end first, so O(len)
+    pub fn append(&mut self, other: &mut List<T>) {
+        *nth_link(&mut self.head, self.len) = other.head.take();
+        self.len += other.len;
+        other.len = 0;
+    }
+
+    // Stable merge sort, top to bottom in ascending order
+    pub fn sort(&mut self)
+    where
+        T: Ord,
+    {
+        self.sort_by(T::cmp);
+    }
+
+    pub fn sort_by<F: FnMut(&T, &T) -> Ordering>(&mut self, mut compare: F) {
+        merge_sort(self, &mut compare);
+    }
+
+    // Whole nodes in and out of the top, for moving them between lists without touching the Box
+    fn pop_node(&mut self) -> Option<Box<Node<T>>> {
+        self.head.take().map(|mut node| {
+            self.head = node.next.take();
+            self.len -= 1;
+            node
+        })
+    }
+
+    fn push_node(&mut self, mut node: Box<Node<T>>) {
+        node.next = self.head.take();
+        self.head = Some(node);
+        self.len += 1;
+    }
+}
+
+// Split in half, sort each half, merge them back
+// Only recurses log(len) deep, the merging is a loop
+// Every node is in exactly one of the Merge's lists at all times, and its Drop glues them back into list
+// So if compare panics, at any depth, every level puts its part back on the way out, nothing is lost
+fn merge_sort<T, F: FnMut(&T, &T) -> Ordering>(list: &mut List<T>, compare: &mut F) {
+    if list.len < 2 {
+        return;
+    }
+    let right = list.split_off(list.len / 2);
+    let mut merge = Merge {
+        left: std::mem::take(list),
+        right,
+        merged: List::new(),
+        list,
+    };
+    merge_sort(&mut merge.left, compare);
+    merge_sort(&mut merge.right, compare);
+
+    loop {
+        // Ties go to the left, that's what keeps it stable
+        let from = match (&merge.left.head, &merge.right.head) {
+            (Some(l), Some(r)) if compare(&l.elem, &r.elem) != Ordering::Greater => &mut merge.left,
+            (Some(_), Some(_)) => &mut merge.right,
+            // One side ran out, the rest of the other one is already in order, Drop hangs it on the end
+            _ => break,
+        };
+        let node = from.pop_node().unwrap();
+        merge.merged.push_node(node);
+    }
+}
+
+struct Merge<'a, T> {
+    list: &'a mut List<T>,
+    left: List<T>,
+    right: List<T>,
+    // Pushed on top as they come off left and right, so it's the merged run upside down
+    merged: List<T>,
+}
+
+impl<T> Drop for Merge<'_, T> {
+    fn drop(&mut self) {
+        // After a finished merge one of left and right is empty, so this only walks anything after a panic
+        let mut rest = std::mem::take(&mut self.left);
+        if !self.right.is_empty() {
+            rest.append(&mut self.right);
+        }
+        // Flipping merged back over onto rest puts it right side up, in front of it
+        while let Some(node) = self.merged.pop_node() {
+            rest.push_node(node);
+        }
+        *self.list = rest;
+    }
+}
+
+// Forward only cursor, there are no back links to go back on
+// It can't wrap around like the deque cursors either, so there are two ghosts,
+// one before the top, which it starts on, and one after the bottom, where moving off the last element leaves it
+// Everything happens on the link right after the cursor, the top of the list when on the first ghost
+pub struct CursorMut<'a, T> {
+    // The element and the next link of the same node, borrowed separately so we can hold on to both
+    current: Option<&'a mut T>,
+    // Always Some, Option only so move_next can take it out and put the next one back
+    next: Option<&'a mut Link<T>>,
+    // The list's len, to keep it right as we insert and remove
+    len: &'a mut usize,
+    // How many elements are before next
+    pos: usize,
+}
+
+impl<T> List<T> {
+    pub fn cursor_mut(&mut self) -> CursorMut<'_, T> {
+        CursorMut {
+            current: None,
+            next: Some(&mut self.head),
+            len: &mut self.len,
+            pos: 0,
+        }
+    }
+}
+
+impl<T> CursorMut<'_, T> {
+    pub fn index(&self) -> Option<usize> {
+        self.current.as_ref().map(|_| self.pos - 1)
+    }
+
+    pub fn current(&mut self) -> Option<&mut T> {
+        self.current.as_deref_mut()
+    }
+
+    // Once past the bottom it stays on the ghost there, moving does nothing
+    pub fn move_next(&mut self) {
+        let next = self.next.take().unwrap();
+        match next {
+            Some(node) => {
+                // Splitting the borrow of the node, elem for current, next for the next move
+                let Node { elem, next } = &mut **node;
+                self.current = Some(elem);
+                self.next = Some(next);
+                self.pos += 1;
+            }
+            None => {
+                self.current = None;
+                self.next = Some(next);
+            }
+        }
+    }
+
+    fn next_link(&mut self) -> &mut Link<T> {
+        self.next.as_deref_mut().unwrap()
+    }
+
+    // After the top ghost is the top of the list, after the bottom one is the new bottom
+    // The cursor stays where it is
+    pub fn insert_after(&mut self, elem: T) {
+        let next = self.next_link();
+        let rest = next.take();
+        *next = Some(Box::new(Node { elem, next: rest }));
+        *self.len += 1;
+    }
+
+    pub fn remove_after(&mut self) -> Option<T> {
+        let next = self.next_link();
+        let node = next.take()?;
+        *next = node.next;
+        *self.len -= 1;
+        Some(node.elem)
+    }
+
+    // Everything after the cursor, as a new list
+    pub fn split_after(&mut self) -> List<T> {
+        let head = self.next_link().take();
+        let len = *self.len - self.pos;
+        *self.len = self.pos;
+        List { head, len }
+    }
+
+    // All of other goes right after the cursor, in order, the cursor stays where it is
+    // Walks to other's bottom to hang our rest on it, so O(other.len())
+    pub fn splice_after(&mut self, mut other: List<T>) {
+        let other_len = other.len;
+        let rest = self.next_link().take();
+        *nth_link(&mut other.head, other_len) = rest;
+        *self.next_link() = other.head.take();
+        *self.len += other_len;
+        other.len = 0;
+    }
 }
 
 #[cfg(test)]
+#[allow(clippy::option_map_unit_fn)]
 mod test {
     use super::List;
 
@@ -254,4 +640,272 @@ mod test {
         assert_eq!(iter.next(), Some(&mut 2));
         assert_eq!(iter.next(), Some(&mut 1));
     }
+
+    #[test]
+    fn len() {
+        let mut list = List::new();
+        assert!(list.is_empty());
+        list.push(1);
+        list.push(2);
+        assert_eq!(list.len(), 2);
+        list.pop();
+        assert_eq!(list.len(), 1);
+        list.pop();
+        list.pop();
+        assert_eq!(list.len(), 0);
+        assert!(list.is_empty());
+    }
+
+    #[test]
+    fn size_hint() {
+        let mut list: List<_> = (1..=3).collect();
+
+        let mut iter = list.iter();
+        assert_eq!(iter.len(), 3);
+        iter.next();
+        assert_eq!(iter.size_hint(), (2, Some(2)));
+        iter.by_ref().count();
+        assert_eq!(iter.len(), 0);
+        // Fused, stays done
+        assert_eq!(iter.next(), None);
+
+        let mut iter = list.iter_mut();
+        iter.next();
+        assert_eq!(iter.len(), 2);
+
+        let mut iter = list.into_iter();
+        assert_eq!(iter.len(), 3);
+        iter.next();
+        assert_eq!(iter.size_hint(), (2, Some(2)));
+    }
+
+    #[test]
+    fn for_loops() {
+        let mut list: List<_> = (1..=3).collect();
+        for elem in &mut list {
+            *elem *= 10;
+        }
+        let mut seen = Vec::new();
+        for elem in &list {
+            seen.push(*elem);
+        }
+        assert_eq!(seen, &[10, 20, 30]);
+        assert_eq!(list.into_iter().collect::<Vec<_>>(), &[10, 20, 30]);
+    }
+
+    #[test]
+    fn traits() {
+        use std::collections::HashSet;
+
+        // Collect and extend both keep the order, and add at the bottom
+        let mut list: List<_> = vec![1, 2, 3].into_iter().collect();
+        assert_eq!(list.peek(), Some(&1));
+        assert_eq!(format!("{list:?}"), "[1, 2, 3]");
+        list.extend([4, 5]);
+        assert_eq!(format!("{list:?}"), "[1, 2, 3, 4, 5]");
+        assert_eq!(list.len(), 5);
+        let mut extended = List::new();
+        extended.extend(1..=5);
+        assert_eq!(extended, (1..=5).collect());
+
+        let copy = list.clone();
+        assert_eq!(copy, list);
+        assert_eq!(copy.len(), 5);
+        assert_eq!(List::<i32>::default(), List::new());
+
+        let shorter: List<_> = [1, 2, 3].into_iter().collect();
+        assert_ne!(shorter, list);
+        assert!(shorter < list);
+        let bigger: List<_> = [6].into_iter().collect();
+        assert!(bigger > list);
+        assert_eq!(list.cmp(&copy), std::cmp::Ordering::Equal);
+
+        let mut set = HashSet::new();
+        assert!(set.insert(list));
+        assert!(!set.insert(copy));
+        assert!(set.insert(shorter));
+        assert_eq!(set.len(), 2);
+    }
+
+    fn to_vec<T: Clone>(list: &List<T>) -> Vec<T> {
+        let elems: Vec<_> = list.iter().cloned().collect();
+        assert_eq!(elems.len(), list.len());
+        elems
+    }
+
+    #[test]
+    fn cursor() {
+        let mut list: List<_> = (1..=4).collect();
+        let mut cursor = list.cursor_mut();
+        assert_eq!(cursor.current(), None);
+        assert_eq!(cursor.index(), None);
+
+        // Top ghost, inserting puts it on top
+        cursor.insert_after(0);
+        cursor.move_next();
+        assert_eq!(cursor.current(), Some(&mut 0));
+        assert_eq!(cursor.index(), Some(0));
+        cursor.move_next();
+        cursor.move_next();
+        assert_eq!(cursor.current(), Some(&mut 2));
+        *cursor.current().unwrap() = 20;
+
+        cursor.insert_after(25);
+        assert_eq!(cursor.remove_after(), Some(25));
+        assert_eq!(cursor.remove_after(), Some(3));
+        cursor.insert_after(30);
+        cursor.move_next();
+        assert_eq!(cursor.current(), Some(&mut 30));
+        cursor.move_next();
+        assert_eq!(cursor.index(), Some(4));
+
+        // Off the bottom, stays there
+        cursor.move_next();
+        assert_eq!(cursor.current(), None);
+        cursor.move_next();
+        assert_eq!(cursor.index(), None);
+        assert_eq!(cursor.remove_after(), None);
+        cursor.insert_after(5);
+        assert_eq!(to_vec(&list), &[0, 1, 20, 30, 4, 5]);
+
+        let mut empty = List::<i32>::new();
+        let mut cursor = empty.cursor_mut();
+        cursor.move_next();
+        assert_eq!(cursor.current(), None);
+        cursor.insert_after(1);
+        assert_eq!(to_vec(&empty), &[1]);
+    }
+
+    #[test]
+    fn cursor_split_splice() {
+        let mut list: List<_> = (1..=5).collect();
+        let mut cursor = list.cursor_mut();
+        cursor.move_next();
+        cursor.move_next();
+        let rest = cursor.split_after();
+        assert_eq!(to_vec(&rest), &[3, 4, 5]);
+        assert_eq!(cursor.split_after().len(), 0);
+
+        cursor.splice_after((10..=12).collect());
+        assert_eq!(cursor.current(), Some(&mut 2));
+        cursor.move_next();
+        cursor.move_next();
+        cursor.move_next();
+        cursor.splice_after(rest);
+        cursor.splice_after(List::new());
+        assert_eq!(to_vec(&list), &[1, 2, 10, 11, 12, 3, 4, 5]);
+
+        // From the top ghost, everything goes
+        let mut cursor = list.cursor_mut();
+        let all = cursor.split_after();
+        cursor.splice_after((0..2).collect());
+        assert_eq!(all.len(), 8);
+        assert_eq!(to_vec(&list), &[0, 1]);
+    }
+
+    #[test]
+    fn reverse() {
+        let mut list: List<_> = (1..=4).collect();
+        list.reverse();
+        assert_eq!(to_vec(&list), &[4, 3, 2, 1]);
+
+        let mut empty = List::<i32>::new();
+        empty.reverse();
+        assert!(empty.is_empty());
+    }
+
+    #[test]
+    fn split_off_append() {
+        let mut list: List<_> = (1..=5).collect();
+        let mut back = list.split_off(2);
+        assert_eq!(to_vec(&list), &[1, 2]);
+        assert_eq!(to_vec(&back), &[3, 4, 5]);
+
+        assert_eq!(list.split_off(2).len(), 0);
+        let mut all = list.split_off(0);
+        assert!(list.is_empty());
+
+        all.append(&mut back);
+        assert!(back.is_empty());
+        list.append(&mut all);
+        assert_eq!(to_vec(&list), &[1, 2, 3, 4, 5]);
+    }
+
+    #[test]
+    #[should_panic]
+    fn split_off_past_end() {
+        let mut list: List<_> = (1..=2).collect();
+        list.split_off(3);
+    }
+
+    #[test]
+    fn sort() {
+        // Same pseudo random numbers every run
+        let mut seed = 12345u32;
+        let mut next = move || {
+            seed = seed.wrapping_mul(1103515245).wrapping_add(12345);
+            (seed >> 16) % 100
+        };
+
+        for len in [0, 1, 2, 3, 10, 1000] {
+            let values: Vec<_> = (0..len).map(|_| next()).collect();
+            let mut list: List<_> = values.iter().copied().collect();
+            list.sort();
+            let mut expected = values;
+            expected.sort();
+            assert_eq!(to_vec(&list), expected);
+        }
+
+        // Stable, equal keys keep their order
+        let mut list: List<_> = [(2, 'a'), (1, 'b'), (2, 'c'), (1, 'd')]
+            .into_iter()
+            .collect();
+        list.sort_by(|a, b| a.0.cmp(&b.0));
+        assert_eq!(to_vec(&list), &[(1, 'b'), (1, 'd'), (2, 'a'), (2, 'c')]);
+
+        // Long enough to blow the stack if the merge recursed per node
+        let mut list: List<_> = (0..200_000).rev().collect();
+        list.sort();
+        assert!(list.iter().copied().eq(0..200_000));
+    }
+
+    #[test]
+    fn sort_panicking_compare() {
+        // Panic after every possible number of compares, everything has to still be there
+        for panic_at in 0..30 {
+            let mut list: List<_> = [3, 9, 0, 7, 1, 8, 2, 6, 4, 5].into_iter().collect();
+            let mut calls = 0;
+            let result = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
+                list.sort_by(|a, b| {
+                    assert!(calls != panic_at, "no order here");
+                    calls += 1;
+                    a.cmp(b)
+                });
+            }));
+            // Past the last compare it just sorts
+            assert!(result.is_err() || calls <= panic_at);
+            assert_eq!(list.len(), 10);
+            let mut elems = to_vec(&list);
+            elems.sort();
+            assert_eq!(elems, (0..10).collect::<Vec<_>>());
+            let mut other: List<_> = (10..13).collect();
+            list.append(&mut other);
+            assert_eq!(list.len(), 13);
+        }
+
+        // Panics in the last merge, with every node in the runs being merged
+        // 150_000 and 100_000 start out in different halves, so only the last merge compares them
+        let mut list: List<_> = (0..300_000).rev().collect();
+        let result = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
+            list.sort_by(|a: &i32, b| {
+                assert!((*a, *b) != (150_000, 100_000), "enough comparing");
+                a.cmp(b)
+            });
+        }));
+        assert!(result.is_err());
+        assert_eq!(list.len(), 300_000);
+        let mut elems = to_vec(&list);
+        elems.sort();
+        assert!(elems.into_iter().eq(0..300_000));
+    }
 }
@@ -4,7 +4,15 @@
 // Otherwise, poping from end would be harder, as we need to have access to node one before end
 // We could have cached it I guess?
 
-use std::ptr;
+use std::{
+    alloc::{Layout, handle_alloc_error},
+    ptr,
+    ptr::NonNull,
+};
+
+// Borrowing the allocator plumbing from the production deque
+// Same nodes-come-from-somewhere idea, so the same pools can feed both
+use crate::production_unsafe_deque::{Global, NodeAllocator, TryReserveError};
 
 // C++ with extra steps
 // We're full on pointers in here
@@ -15,7 +23,7 @@ use std::ptr;
 // will be ok enoguh
 // Implementation of the class itself isn't really THAT much safer then what we could achieve in C++
 // But every usage of it will be, nasty parts are restricted to unsafe code blocks
-pub struct List<T> {
+pub struct List<T, A: NodeAllocator = Global> {
     head: Link<T>,
     // C/C++ raw pointer
     // No lifetime
@@ -29,6 +37,8 @@ pub struct List<T> {
     // *const T -> C/C++ const T*
     // *mut T -> C/C++ T*
     tail: *mut Node<T>,
+    // Where the nodes come from, Global is just Box without the Box
+    alloc: A,
 }
 
 type Link<T> = *mut Node<T>;
@@ -40,19 +50,29 @@ struct Node<T> {
 
 impl<T> List<T> {
     pub fn new() -> Self {
+        Self::new_in(Global)
+    }
+}
+
+impl<T, A: NodeAllocator> List<T, A> {
+    pub fn new_in(alloc: A) -> Self {
         List {
             head: ptr::null_mut(),
             tail: ptr::null_mut(),
+            alloc,
         }
     }
 
     pub fn push(&mut self, elem: T) {
-        // std::unique_ptr::release
-        // Return the raw pointer to allocatd thing
-        let new_tail = Box::into_raw(Box::new(Node {
-            elem,
-            next: ptr::null_mut(),
-        }));
+        if let Err(err) = self.try_push(elem) {
+            // What Box would do, abort
+            handle_alloc_error(err.layout())
+        }
+    }
+
+    // When there's no memory left we get an error instead, and the queue stays untouched
+    pub fn try_push(&mut self, elem: T) -> Result<(), TryReserveError> {
+        let new_tail = self.try_alloc_node(elem)?;
 
         // .is_null checks for null, equivalent to checking for None
         if !self.tail.is_null() {
@@ -73,6 +93,7 @@ impl<T> List<T> {
         }
 
         self.tail = new_tail;
+        Ok(())
     }
 
     pub fn pop(&mut self) -> Option<T> {
@@ -80,10 +101,9 @@ impl<T> List<T> {
             None
         } else {
             unsafe {
-                // std::unique_ptr{pointer}
-                // Adopt existing alocation into a Box that will free it
-                let node = Box::from_raw(self.head);
-                self.head = (*node).next;
+                // Move the node out of its memory, the memory goes back to the allocator
+                let node = self.dealloc_node(self.head);
+                self.head = node.next;
 
                 if self.head.is_null() {
                     self.tail = ptr::null_mut();
@@ -101,20 +121,67 @@ impl<T> List<T> {
     pub fn peek_mut(&mut self) -> Option<&mut T> {
         unsafe { self.head.as_mut().map(|node| &mut node.elem) }
     }
+
+    // Basically Box::into_raw(Box::new(...)), just with our allocator
+    // And it tells us when it fails, instead of aborting
+    fn try_alloc_node(&self, elem: T) -> Result<*mut Node<T>, TryReserveError> {
+        let layout = Layout::new::<Node<T>>();
+        let node = self
+            .alloc
+            .allocate(layout)
+            .map_err(|_| TryReserveError::new(layout))?
+            .cast::<Node<T>>()
+            .as_ptr();
+        unsafe {
+            node.write(Node {
+                elem,
+                next: ptr::null_mut(),
+            });
+        }
+        Ok(node)
+    }
+
+    // And Box::from_raw, the node is moved out and its memory is given back
+    unsafe fn dealloc_node(&self, node: *mut Node<T>) -> Node<T> {
+        unsafe {
+            let old = node.read();
+            self.alloc.deallocate(
+                NonNull::new_unchecked(node).cast(),
+                Layout::new::<Node<T>>(),
+            );
+            old
+        }
+    }
+}
+
+impl<T, A: NodeAllocator + Default> Default for List<T, A> {
+    fn default() -> Self {
+        Self::new_in(A::default())
+    }
 }
 
-impl<T> Drop for List<T> {
+impl<T, A: NodeAllocator> Drop for List<T, A> {
     fn drop(&mut self) {
         while !self.head.is_null() {
             unsafe {
                 let next = (*self.head).next;
-                drop(Box::from_raw(std::mem::replace(&mut self.head, next)));
+                let old = std::mem::replace(&mut self.head, next);
+                drop(self.dealloc_node(old));
             }
         }
     }
 }
 
-pub struct IntoIter<T>(List<T>);
+pub struct IntoIter<T, A: NodeAllocator = Global>(List<T, A>);
+
+impl<T, A: NodeAllocator> IntoIterator for List<T, A> {
+    type IntoIter = IntoIter<T, A>;
+    type Item = T;
+
+    fn into_iter(self) -> Self::IntoIter {
+        IntoIter(self)
+    }
+}
 
 pub struct Iter<'a, T> {
     next: Option<&'a Node<T>>,
@@ -124,11 +191,7 @@ pub struct IterMut<'a, T> {
     next: Option<&'a mut Node<T>>,
 }
 
-impl<T> List<T> {
-    pub fn into_iter(self) -> IntoIter<T> {
-        IntoIter(self)
-    }
-
+impl<T, A: NodeAllocator> List<T, A> {
     pub fn iter(&self) -> Iter<'_, T> {
         unsafe {
             Iter {
@@ -151,7 +214,7 @@ impl<T> List<T> {
     }
 }
 
-impl<T> Iterator for IntoIter<T> {
+impl<T, A: NodeAllocator> Iterator for IntoIter<T, A> {
     type Item = T;
     fn next(&mut self) -> Option<Self::Item> {
         self.0.pop()
@@ -185,8 +248,11 @@ impl<'a, T> Iterator for IterMut<'a, T> {
 }
 
 #[cfg(test)]
+#[allow(clippy::option_map_unit_fn)]
 mod test {
     use super::List;
+    use crate::production_unsafe_deque::{FailingAlloc, LinkedList, NodePool};
+    use std::{cell::Cell, rc::Rc};
     #[test]
     fn basics() {
         let mut list = List::new();
@@ -304,4 +370,54 @@ mod test {
 
         // Drop it on the ground and let the dtor exercise itself
     }
+
+    #[test]
+    fn node_pool() {
+        let pool = NodePool::new(8);
+        let mut queue = List::new_in(pool.clone());
+        let mut deque: LinkedList<i32, _> = pool.list();
+
+        // Queue churning through a pool only allocates when it grows
+        // Every round pops one, and the next round gets it back
+        for i in 0..50 {
+            queue.push(i);
+            queue.push(i);
+            assert_eq!(queue.pop(), Some(i / 2));
+        }
+        assert_eq!(pool.stats().misses, 51);
+        assert_eq!(pool.stats().hits, 49);
+        while queue.pop().is_some() {}
+        assert_eq!(pool.stats().retained, 8);
+
+        // Deque nodes are a different shape, they don't get the queue's nodes
+        deque.push_back(1);
+        assert_eq!(pool.stats().misses, 52);
+        assert_eq!(pool.stats().retained, 8);
+
+        // Queue outlives the pool handle it was made from
+        drop(pool);
+        queue.push(1);
+        queue.push(2);
+        assert_eq!(queue.pop(), Some(1));
+        drop(deque);
+        assert_eq!(queue.into_iter().collect::<Vec<_>>(), [2]);
+    }
+
+    #[test]
+    fn try_push() {
+        let budget = Rc::new(Cell::new(2));
+        let mut list = List::new_in(FailingAlloc {
+            budget: budget.clone(),
+        });
+        assert!(list.try_push(1).is_ok());
+        assert!(list.try_push(2).is_ok());
+        assert!(list.try_push(3).is_err());
+
+        // Queue is still fine, and pushing works again once there's memory
+        assert_eq!(list.iter().collect::<Vec<_>>(), [&1, &2]);
+        assert_eq!(list.pop(), Some(1));
+        budget.set(1);
+        assert!(list.try_push(4).is_ok());
+        assert_eq!(list.into_iter().collect::<Vec<_>>(), [2, 4]);
+    }
 }
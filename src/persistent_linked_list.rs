@@ -11,6 +11,9 @@
 
 use std::rc::Rc;
 
+#[cfg(any(test, feature = "diagnostics"))]
+use crate::diagnostics::{ListKind, Live};
+
 pub struct List<T> {
     head: Link<T>,
 }
@@ -23,6 +26,8 @@ type Link<T> = Option<Rc<Node<T>>>;
 struct Node<T> {
     elem: T,
     next: Link<T>,
+    #[cfg(any(test, feature = "diagnostics"))]
+    _live: Live,
 }
 
 impl<T> List<T> {
@@ -40,6 +45,8 @@ impl<T> List<T> {
             head: Some(Rc::new(Node {
                 elem,
                 next: self.head.clone(),
+                #[cfg(any(test, feature = "diagnostics"))]
+                _live: Live::new(ListKind::PersistentLinkedList),
             })),
         }
     }
@@ -62,6 +69,12 @@ impl<T> List<T> {
     }
 }
 
+impl<T> Default for List<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 // Different drop, as Rc is a more complex then Box
 // We can't just take the value out of it easily
 // Drop still needed to avoid stack overflow if the list is too big
@@ -114,6 +127,7 @@ impl<'a, T> Iterator for Iter<'a, T> {
 #[cfg(test)]
 mod test {
     use super::List;
+    use crate::diagnostics::{ListKind, assert_no_live_nodes, live_nodes};
 
     #[test]
     fn basics() {
@@ -146,4 +160,24 @@ mod test {
         assert_eq!(iter.next(), Some(&2));
         assert_eq!(iter.next(), Some(&1));
     }
+
+    #[test]
+    fn shared_nodes_freed() {
+        let list = List::new().prepend(1).prepend(2);
+        let a = list.prepend(3);
+        let b = list.prepend(4).prepend(5);
+        assert_eq!(live_nodes(ListKind::PersistentLinkedList), 5);
+
+        // 2 and 1 are still shared by a and b
+        drop(list);
+        drop(a);
+        assert_eq!(live_nodes(ListKind::PersistentLinkedList), 4);
+        assert_eq!(b.iter().copied().collect::<Vec<_>>(), &[5, 4, 2, 1]);
+
+        let tail = b.tail().tail();
+        drop(b);
+        assert_eq!(live_nodes(ListKind::PersistentLinkedList), 2);
+        drop(tail);
+        assert_no_live_nodes(ListKind::PersistentLinkedList);
+    }
 }
@@ -1,10 +1,20 @@
 use core::fmt;
 use std::{
+    alloc::{self, Layout, handle_alloc_error},
+    cell::{Cell, RefCell},
     cmp::Ordering,
+    collections::HashMap,
     fmt::Debug,
     hash::{Hash, Hasher},
+    iter::FusedIterator,
     marker::PhantomData,
+    ops::{Bound, Index, IndexMut, RangeBounds},
     ptr::NonNull,
+    rc::Rc,
+    sync::{
+        Arc, Mutex,
+        atomic::{AtomicU64, Ordering::Relaxed},
+    },
 };
 
 /*
@@ -40,10 +50,20 @@ use std::{
     Here, only Variance will be important for us
 */
 
-pub struct LinkedList<T> {
+pub struct LinkedList<T, A: NodeAllocator = Global> {
     front: Link<T>,
     back: Link<T>,
     len: usize,
+    // Only used by NodeHandle, 0 until we hand out the first handle
+    id: u64,
+    // Owners of the nodes that have a NodeHandle pointing at them, keyed by node
+    // Kept out of the nodes, so lists that never hand out handles don't pay for it (an empty HashMap doesn't allocate)
+    // As long as it's empty, moving nodes between lists doesn't have to care about handles
+    handles: Handled,
+    // Last position looked up by index, so the next lookup can start walking from there
+    finger: Finger,
+    // Where our nodes come from, and where they go back to
+    alloc: A,
     // Spooky field 👻👻👻, indicates it will be unused so rust/cargo doesn't complain
     // It's size is 0 (yes Rust allows 0 size objects that will be completely removed during compile)
     // only purpose is to hint to compiler that "hey we are kind-of storing T and you should consider that, wink-wink"
@@ -65,26 +85,424 @@ struct Node<T> {
     elem: T,
 }
 
+// Shared between a node's entry in its list and the node's NodeHandle(s)
+// Holds the id of the list the node lives in, 0 once the node is freed
+// That way a handle can check if its node is still alive without ever touching it
+type Owner = Arc<AtomicU64>;
+
+// Node pointer is type-erased, same as in the finger, so the list stays covariant over T
+type Handled = HashMap<NonNull<()>, Owner>;
+
+static NEXT_LIST_ID: AtomicU64 = AtomicU64::new(1);
+
+// (index, node) of the last indexed lookup
+// get only has &self, so it needs interior mutability, and LinkedList is Sync, so it's a Mutex
+// We only ever try_lock it, if another thread has it we just walk from the ends
+// Node pointer is type-erased: Mutex<...T...> would make the list invariant over T
+// Anything that reshuffles nodes just forgets it, it's only a hint
+type Finger = Mutex<Option<(usize, NonNull<()>)>>;
+
+// std has an Allocator trait for this, but it's nightly only, so here is our own tiny version
+// The list clones its allocator into every list split off from it, so it should be a cheap handle
+// (a ZST, a reference, an Rc...)
+
+/// Where the nodes of a list live.
+/// Lets us plug in pools, arenas, counting allocators for tests, etc.
+///
+/// # Safety
+/// The list trusts it blindly:
+/// - `allocate` hands out memory fitting the layout, that nobody else touches until it's deallocated
+/// - memory allocated by one allocator can be deallocated by any of its clones,
+///   and by any allocator it says it `is_compatible_with`
+pub unsafe trait NodeAllocator: Clone {
+    fn allocate(&self, layout: Layout) -> Result<NonNull<u8>, AllocError>;
+
+    /// # Safety
+    /// `ptr` came from `allocate` on this or a compatible allocator, with the same layout
+    unsafe fn deallocate(&self, ptr: NonNull<u8>, layout: Layout);
+
+    // Can each of us free what the other one allocated?
+    // Splicing hands our nodes to another list, so it's only allowed between compatible allocators
+    fn is_compatible_with(&self, other: &Self) -> bool;
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct AllocError;
+
+impl fmt::Display for AllocError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("node allocation failed")
+    }
+}
+
+impl std::error::Error for AllocError {}
+
+// What the try_ functions give back when there's no memory for a new node
+// Like std's TryReserveError, which we can't create ourselves
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TryReserveError {
+    layout: Layout,
+}
+
+impl TryReserveError {
+    pub(crate) fn new(layout: Layout) -> Self {
+        TryReserveError { layout }
+    }
+
+    // What we tried to allocate
+    pub fn layout(&self) -> Layout {
+        self.layout
+    }
+}
+
+impl fmt::Display for TryReserveError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "memory allocation of {} bytes failed",
+            self.layout.size()
+        )
+    }
+}
+
+impl std::error::Error for TryReserveError {}
+
+// The global allocator, the same thing Box uses
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Global;
+
+unsafe impl NodeAllocator for Global {
+    fn allocate(&self, layout: Layout) -> Result<NonNull<u8>, AllocError> {
+        // Node always has a couple of pointers in it, so layout is never zero-sized
+        NonNull::new(unsafe { alloc::alloc(layout) }).ok_or(AllocError)
+    }
+
+    unsafe fn deallocate(&self, ptr: NonNull<u8>, layout: Layout) {
+        unsafe { alloc::dealloc(ptr.as_ptr(), layout) }
+    }
+
+    fn is_compatible_with(&self, _other: &Self) -> bool {
+        true
+    }
+}
+
+// Lets budget allocations through, and fails every one after that
+// For the tests of every list that takes a NodeAllocator
+#[cfg(test)]
+#[derive(Clone)]
+pub(crate) struct FailingAlloc {
+    pub(crate) budget: Rc<Cell<usize>>,
+}
+
+#[cfg(test)]
+unsafe impl NodeAllocator for FailingAlloc {
+    fn allocate(&self, layout: Layout) -> Result<NonNull<u8>, AllocError> {
+        if self.budget.get() == 0 {
+            return Err(AllocError);
+        }
+        self.budget.set(self.budget.get() - 1);
+        Global.allocate(layout)
+    }
+
+    unsafe fn deallocate(&self, ptr: NonNull<u8>, layout: Layout) {
+        unsafe { Global.deallocate(ptr, layout) }
+    }
+
+    fn is_compatible_with(&self, _other: &Self) -> bool {
+        true
+    }
+}
+
+// Bump allocator for lists that all die together (like everything built for one request)
+// Grabs memory in big chunks and hands out nodes from them by just moving a pointer
+// Freeing a node does nothing, all the chunks go back at once when the arena is dropped
+// So memory of popped nodes isn't reused until then
+pub struct ListArena {
+    chunks: RefCell<Vec<(NonNull<u8>, Layout)>>,
+    // Next free byte in the newest chunk, and how many bytes it has left
+    cur: Cell<*mut u8>,
+    left: Cell<usize>,
+    chunk_size: usize,
+}
+
+// Lists just borrow the arena, so they can't outlive it
+// Dropping them still runs the element destructors, it just doesn't free anything
+pub type ArenaList<'arena, T> = LinkedList<T, &'arena ListArena>;
+
+impl ListArena {
+    pub fn new() -> Self {
+        Self::with_chunk_size(4096)
+    }
+
+    // chunk_size in bytes, nodes bigger than that get a chunk of their own
+    pub fn with_chunk_size(chunk_size: usize) -> Self {
+        ListArena {
+            chunks: RefCell::new(Vec::new()),
+            cur: Cell::new(std::ptr::null_mut()),
+            left: Cell::new(0),
+            chunk_size,
+        }
+    }
+
+    pub fn list<T>(&self) -> ArenaList<'_, T> {
+        LinkedList::new_in(self)
+    }
+
+    pub fn chunk_count(&self) -> usize {
+        self.chunks.borrow().len()
+    }
+
+    // Bytes taken from the global allocator, used or not
+    pub fn allocated_bytes(&self) -> usize {
+        self.chunks
+            .borrow()
+            .iter()
+            .map(|(_, layout)| layout.size())
+            .sum()
+    }
+
+    fn new_chunk(&self, layout: Layout) -> Result<(), AllocError> {
+        let size = self.chunk_size.max(layout.size());
+        let chunk_layout =
+            Layout::from_size_align(size, layout.align().max(16)).map_err(|_| AllocError)?;
+        let chunk = NonNull::new(unsafe { alloc::alloc(chunk_layout) }).ok_or(AllocError)?;
+        self.chunks.borrow_mut().push((chunk, chunk_layout));
+        self.cur.set(chunk.as_ptr());
+        self.left.set(size);
+        Ok(())
+    }
+}
+
+impl Default for ListArena {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Drop for ListArena {
+    fn drop(&mut self) {
+        // Every list borrowing us is gone by now, nobody can touch the nodes anymore
+        for (chunk, layout) in self.chunks.get_mut().drain(..) {
+            unsafe { alloc::dealloc(chunk.as_ptr(), layout) };
+        }
+    }
+}
+
+unsafe impl NodeAllocator for &ListArena {
+    fn allocate(&self, layout: Layout) -> Result<NonNull<u8>, AllocError> {
+        let padding = self.cur.get().align_offset(layout.align());
+        let fits = padding
+            .checked_add(layout.size())
+            .is_some_and(|needed| needed <= self.left.get());
+        let padding = if fits {
+            padding
+        } else {
+            // Fresh chunk is aligned for the layout already
+            self.new_chunk(layout)?;
+            0
+        };
+
+        unsafe {
+            let ptr = self.cur.get().add(padding);
+            self.cur.set(ptr.add(layout.size()));
+            self.left.set(self.left.get() - padding - layout.size());
+            Ok(NonNull::new_unchecked(ptr))
+        }
+    }
+
+    unsafe fn deallocate(&self, _ptr: NonNull<u8>, _layout: Layout) {
+        // Freed all at once in ListArena::drop
+    }
+
+    fn is_compatible_with(&self, other: &Self) -> bool {
+        std::ptr::eq(*self, *other)
+    }
+}
+
+// The arena owns its chunks, nothing else points into them while it's being moved around
+unsafe impl Send for ListArena {}
+
+// Keeps freed nodes around, and hands them out again instead of asking the global allocator
+// Great for queues that push on one end and pop on the other all day long
+// Cloning it gives another handle to the same pool, and every list using it holds one
+// So lists can outlive the pool we created, it only goes away together with the last handle
+// Not thread-safe, it's an Rc inside, so lists using it stay on one thread
+pub struct NodePool<T> {
+    inner: Rc<PoolInner>,
+    // Only says what kind of lists the pool is meant for, we never own a T
+    _boo: PhantomData<fn() -> T>,
+}
+
+struct PoolInner {
+    // Freed blocks, sorted by their layout
+    // Deque and queue nodes look different, so there's a bucket for each kind of node
+    free: RefCell<Vec<(Layout, Vec<NonNull<u8>>)>>,
+    // At most this many blocks are kept, across all the buckets
+    cap: usize,
+    retained: Cell<usize>,
+    hits: Cell<usize>,
+    misses: Cell<usize>,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct PoolStats {
+    // Allocations served from a recycled node
+    pub hits: usize,
+    // Allocations that had to go to the global allocator
+    pub misses: usize,
+    // Nodes sitting in the pool right now
+    pub retained: usize,
+}
+
+impl<T> NodePool<T> {
+    // cap -> how many freed nodes to keep around at most, the rest is freed right away
+    pub fn new(cap: usize) -> Self {
+        NodePool {
+            inner: Rc::new(PoolInner {
+                free: RefCell::new(Vec::new()),
+                cap,
+                retained: Cell::new(0),
+                hits: Cell::new(0),
+                misses: Cell::new(0),
+            }),
+            _boo: PhantomData,
+        }
+    }
+
+    pub fn list(&self) -> LinkedList<T, Self> {
+        LinkedList::new_in(self.clone())
+    }
+
+    pub fn cap(&self) -> usize {
+        self.inner.cap
+    }
+
+    pub fn stats(&self) -> PoolStats {
+        PoolStats {
+            hits: self.inner.hits.get(),
+            misses: self.inner.misses.get(),
+            retained: self.inner.retained.get(),
+        }
+    }
+}
+
+impl<T> Clone for NodePool<T> {
+    fn clone(&self) -> Self {
+        NodePool {
+            inner: self.inner.clone(),
+            _boo: PhantomData,
+        }
+    }
+}
+
+impl Drop for PoolInner {
+    fn drop(&mut self) {
+        // Last handle is gone, so no list can give us anything back anymore
+        for (layout, blocks) in self.free.get_mut().drain(..) {
+            for block in blocks {
+                unsafe { Global.deallocate(block, layout) };
+            }
+        }
+    }
+}
+
+unsafe impl<T> NodeAllocator for NodePool<T> {
+    fn allocate(&self, layout: Layout) -> Result<NonNull<u8>, AllocError> {
+        let recycled = self
+            .inner
+            .free
+            .borrow_mut()
+            .iter_mut()
+            .find(|(bucket, _)| *bucket == layout)
+            .and_then(|(_, blocks)| blocks.pop());
+        match recycled {
+            Some(block) => {
+                self.inner.hits.set(self.inner.hits.get() + 1);
+                self.inner.retained.set(self.inner.retained.get() - 1);
+                Ok(block)
+            }
+            None => {
+                self.inner.misses.set(self.inner.misses.get() + 1);
+                Global.allocate(layout)
+            }
+        }
+    }
+
+    unsafe fn deallocate(&self, ptr: NonNull<u8>, layout: Layout) {
+        if self.inner.retained.get() >= self.inner.cap {
+            unsafe { Global.deallocate(ptr, layout) };
+            return;
+        }
+
+        let mut free = self.inner.free.borrow_mut();
+        match free.iter_mut().find(|(bucket, _)| *bucket == layout) {
+            Some((_, blocks)) => blocks.push(ptr),
+            None => free.push((layout, vec![ptr])),
+        }
+        self.inner.retained.set(self.inner.retained.get() + 1);
+    }
+
+    fn is_compatible_with(&self, _other: &Self) -> bool {
+        // Every block comes from Global and goes back there eventually,
+        // so any pool can take care of nodes from any other pool
+        true
+    }
+}
+
+impl<T: Copy> ArenaList<'_, T> {
+    // Copy types have no destructors, so dropping doesn't have to visit every node
+    // The nodes just stay in the arena until it goes away, O(1) instead of O(n)
+    pub fn forget(self) {
+        // Handles still need to hear that their nodes are gone, so those take the slow way
+        if self.handles.is_empty() {
+            std::mem::forget(self);
+        }
+    }
+}
+
 impl<T> LinkedList<T> {
     pub fn new() -> Self {
+        Self::new_in(Global)
+    }
+}
+
+impl<T, A: NodeAllocator> LinkedList<T, A> {
+    pub fn new_in(alloc: A) -> Self {
         Self {
             front: None,
             back: None,
             len: 0,
+            id: 0,
+            handles: HashMap::new(),
+            finger: Mutex::new(None),
+            alloc,
             // PhantomData has no fields, size of 0, so we don't need to say it's name to initialize
             _boo: PhantomData,
         }
     }
 
+    pub fn allocator(&self) -> &A {
+        &self.alloc
+    }
+
     pub fn push_front(&mut self, elem: T) {
+        if let Err(err) = self.try_push_front(elem) {
+            handle_alloc_error(err.layout())
+        }
+    }
+
+    pub fn push_back(&mut self, elem: T) {
+        if let Err(err) = self.try_push_back(elem) {
+            handle_alloc_error(err.layout())
+        }
+    }
+
+    // Same as push_front, but running out of memory is an error instead of an abort
+    // The list stays as it was, and elem gets dropped
+    pub fn try_push_front(&mut self, elem: T) -> Result<(), TryReserveError> {
         // SAFETY: it's a linked-list, what do you want?
         unsafe {
-            // Oh god
-            let new = NonNull::new_unchecked(Box::into_raw(Box::new(Node {
-                front: None,
-                back: None,
-                elem,
-            })));
+            let new = self.try_alloc_node(elem)?;
 
             if let Some(old) = self.front {
                 // Put the new front before the old one
@@ -101,17 +519,19 @@ impl<T> LinkedList<T> {
             }
             self.front = Some(new);
             self.len += 1;
+
+            // Everyone moved one step away from the front
+            if let Some((index, _)) = self.finger_mut() {
+                *index += 1;
+            }
         }
+        Ok(())
     }
 
-    pub fn push_back(&mut self, elem: T) {
+    pub fn try_push_back(&mut self, elem: T) -> Result<(), TryReserveError> {
         // SAFETY: it's a linked-list, what do you want?
         unsafe {
-            let new = NonNull::new_unchecked(Box::into_raw(Box::new(Node {
-                back: None,
-                front: None,
-                elem,
-            })));
+            let new = self.try_alloc_node(elem)?;
             if let Some(old) = self.back {
                 // Put the new back before the old one
                 (*old.as_ptr()).back = Some(new);
@@ -125,19 +545,35 @@ impl<T> LinkedList<T> {
             self.back = Some(new);
             self.len += 1;
         }
+        Ok(())
+    }
+
+    // All or nothing, if any node can't be allocated the list stays as it was
+    // Whatever was already taken out of iter gets dropped
+    pub fn try_extend<I: IntoIterator<Item = T>>(
+        &mut self,
+        iter: I,
+    ) -> Result<(), TryReserveError> {
+        let mut new = self.empty();
+        for elem in iter {
+            new.try_push_back(elem)?;
+        }
+        self.append(&mut new);
+        Ok(())
     }
 
     pub fn pop_front(&mut self) -> Option<T> {
         unsafe {
             // Only have to do stuff if there is a front node to pop.
             self.front.map(|node| {
-                // Bring the Box back to life so we can move out its value and
-                // Drop it (Box continues to magically understand this for us).
-                let boxed_node = Box::from_raw(node.as_ptr());
-                let result = boxed_node.elem;
+                // Move the node out of its memory and give the memory back
+                // Then we can move out its value and drop the rest
+                self.forget_handle(node);
+                let old_node = self.dealloc_node(node);
+                let result = old_node.elem;
 
                 // Make the next node into the new front.
-                self.front = boxed_node.back;
+                self.front = old_node.back;
                 if let Some(new) = self.front {
                     // Cleanup its reference to the removed node
                     (*new.as_ptr()).front = None;
@@ -147,8 +583,10 @@ impl<T> LinkedList<T> {
                 }
 
                 self.len -= 1;
+                // Everyone moved one step closer to the front, unless we just popped the finger
+                let finger = self.finger_mut();
+                *finger = finger.and_then(|(index, node)| Some((index.checked_sub(1)?, node)));
                 result
-                // Box gets implicitly freed here, knows there is no T.
             })
         }
     }
@@ -157,13 +595,12 @@ impl<T> LinkedList<T> {
         unsafe {
             // Only have to do stuff if there is a back node to pop.
             self.back.map(|node| {
-                // Bring the Box front to life so we can move out its value and
-                // Drop it (Box continues to magically understand this for us).
-                let boxed_node = Box::from_raw(node.as_ptr());
-                let result = boxed_node.elem;
+                self.forget_handle(node);
+                let old_node = self.dealloc_node(node);
+                let result = old_node.elem;
 
                 // Make the next node into the new back.
-                self.back = boxed_node.front;
+                self.back = old_node.front;
                 if let Some(new) = self.back {
                     // Cleanup its reference to the removed node
                     (*new.as_ptr()).back = None;
@@ -173,8 +610,10 @@ impl<T> LinkedList<T> {
                 }
 
                 self.len -= 1;
+                let len = self.len;
+                let finger = self.finger_mut();
+                *finger = finger.filter(|&(index, _)| index < len);
                 result
-                // Box gets implicitly freed here, knows there is no T.
             })
         }
     }
@@ -199,7 +638,7 @@ impl<T> LinkedList<T> {
         unsafe { self.back.map(|node| &mut (*node.as_ptr()).elem) }
     }
 
-    pub fn iter_mut(&mut self) -> IterMut<T> {
+    pub fn iter_mut(&mut self) -> IterMut<'_, T> {
         IterMut {
             front: self.front,
             back: self.back,
@@ -215,1024 +654,3987 @@ impl<T> LinkedList<T> {
     pub fn clear(&mut self) {
         // We actually want to be safe here
         // So let's use safer function
-        while let Some(_) = self.pop_front() {}
+        while self.pop_front().is_some() {}
     }
 }
 
-// That's a lot of code for iterators
-
-impl<T> Drop for LinkedList<T> {
-    fn drop(&mut self) {
-        // We are about to stop existing
-        // So... no real need to keep track of our state and update it constantly
-        // Just iterate over it and free as we go
-        while let Some(ptr) = self.front {
-            unsafe {
-                let node = Box::from_raw(ptr.as_ptr());
-                self.front = node.back;
-            }
+// Vec-like structural operations
+// Nothing here allocates, nodes just get relinked (or freed when elements go away)
+// Index based ones walk from whichever end is closer
+impl<T, A: NodeAllocator> LinkedList<T, A> {
+    // Moves all of other's nodes to our back, other is left empty
+    // O(1), unless other has handles that need to follow their nodes
+    pub fn append(&mut self, other: &mut Self) {
+        self.assert_compatible(other);
+        let Some(back) = self.back else {
+            // We're empty, just become other
+            std::mem::swap(self, other);
+            return;
+        };
+        let Some(other_front) = other.front else {
+            return;
+        };
+
+        self.adopt_handles(other);
+        other.forget_finger();
+        other.front = None;
+        unsafe {
+            (*back.as_ptr()).back = Some(other_front);
+            (*other_front.as_ptr()).front = Some(back);
         }
+        self.back = other.back.take();
+        self.len += std::mem::take(&mut other.len);
     }
-}
 
-pub struct Iter<'a, T> {
-    front: Link<T>,
-    back: Link<T>,
-    len: usize,
-    _boo: PhantomData<&'a T>,
-}
+    // Splits the list in two, we keep [0, at), the output gets [at, len)
+    pub fn split_off(&mut self, at: usize) -> Self {
+        let len = self.len;
+        assert!(
+            at <= len,
+            "cannot split off at a nonexistent index (is {at}, len {len})"
+        );
 
-impl<T> LinkedList<T> {
-    pub fn iter(&self) -> Iter<T> {
-        Iter {
-            front: self.front,
-            back: self.back,
-            len: self.len,
-            _boo: PhantomData,
+        if at == 0 {
+            return self.take_all();
+        }
+        if at == len {
+            return self.empty();
+        }
+
+        unsafe {
+            let node = self.node_at(at);
+            self.forget_finger();
+            let prev = (*node.as_ptr()).front.take().unwrap();
+            (*prev.as_ptr()).back = None;
+
+            let output_back = self.back;
+            self.back = Some(prev);
+            self.len = at;
+            self.split_chain(Some(node), output_back, len - at)
         }
     }
-}
 
-impl<'a, T> IntoIterator for &'a LinkedList<T> {
-    type IntoIter = Iter<'a, T>;
-    type Item = &'a T;
+    // Inserts elem so that it ends up at index at
+    pub fn insert(&mut self, at: usize, elem: T) {
+        let len = self.len;
+        assert!(
+            at <= len,
+            "insertion index (is {at}) should be <= len (is {len})"
+        );
 
-    fn into_iter(self) -> Self::IntoIter {
-        self.iter()
+        if at == len {
+            self.push_back(elem);
+        } else {
+            let node = self.node_at(at);
+            self.cursor_on(node, at).insert_before(elem);
+        }
     }
-}
 
-impl<'a, T> Iterator for Iter<'a, T> {
-    type Item = &'a T;
+    pub fn remove(&mut self, at: usize) -> T {
+        let len = self.len;
+        assert!(
+            at < len,
+            "removal index (is {at}) should be < len (is {len})"
+        );
 
-    fn next(&mut self) -> Option<Self::Item> {
-        // While self.front == self.back is a tempting condition to check here,
-        // it won't do the right for yielding the last element! That sort of
-        // thing only works for arrays because of "one-past-the-end" pointers.
-        if self.len > 0 {
-            // We could unwrap front, but this is safer and easier
-            self.front.map(|node| unsafe {
-                self.len -= 1;
-                self.front = (*node.as_ptr()).back;
-                &(*node.as_ptr()).elem
-            })
-        } else {
-            None
+        let node = self.node_at(at);
+        self.cursor_on(node, at).remove_current().unwrap()
+    }
+
+    // Keeps the first len elements, drops the rest (back to front)
+    pub fn truncate(&mut self, len: usize) {
+        while self.len > len {
+            self.pop_back();
         }
     }
 
-    fn size_hint(&self) -> (usize, Option<usize>) {
-        (self.len, Some(self.len))
+    pub fn retain<F: FnMut(&T) -> bool>(&mut self, mut f: F) {
+        self.retain_mut(|elem| f(elem));
     }
-}
 
-impl<'a, T> DoubleEndedIterator for Iter<'a, T> {
-    fn next_back(&mut self) -> Option<Self::Item> {
-        if self.len > 0 {
-            self.back.map(|node| unsafe {
-                self.len -= 1;
-                self.back = (*node.as_ptr()).front;
-                &(*node.as_ptr()).elem
-            })
-        } else {
-            None
+    // If f panics we stop right there, everything that wasn't visited yet stays
+    pub fn retain_mut<F: FnMut(&mut T) -> bool>(&mut self, mut f: F) {
+        let mut cursor = self.cursor_mut();
+        cursor.move_next();
+        while let Some(elem) = cursor.current() {
+            if f(elem) {
+                cursor.move_next();
+            } else {
+                // Moves us to the next element on its own
+                cursor.remove_current();
+            }
         }
     }
-}
 
-impl<'a, T> ExactSizeIterator for Iter<'a, T> {
-    fn len(&self) -> usize {
-        self.len
+    pub fn dedup(&mut self)
+    where
+        T: PartialEq,
+    {
+        self.dedup_by(|a, b| a == b);
     }
-}
 
-pub struct IntoIter<T> {
-    list: LinkedList<T>,
-}
+    pub fn dedup_by_key<K: PartialEq, F: FnMut(&mut T) -> K>(&mut self, mut key: F) {
+        self.dedup_by(|a, b| key(a) == key(b));
+    }
 
-impl<T> LinkedList<T> {
-    pub fn into_iter(self) -> IntoIter<T> {
-        IntoIter { list: self }
+    // Removes consecutive elements where same_bucket(current, previous kept) says true
+    // Same argument order as Vec::dedup_by
+    pub fn dedup_by<F: FnMut(&mut T, &mut T) -> bool>(&mut self, mut same_bucket: F) {
+        let mut cursor = self.cursor_mut();
+        cursor.move_next();
+        // prev is the last element we decided to keep
+        let Some(mut prev) = cursor.cur else {
+            return;
+        };
+        cursor.move_next();
+        while let Some(cur) = cursor.cur {
+            // Two distinct nodes, so two &mut are fine
+            let same =
+                unsafe { same_bucket(&mut (*cur.as_ptr()).elem, &mut (*prev.as_ptr()).elem) };
+            if same {
+                cursor.remove_current();
+            } else {
+                prev = cur;
+                cursor.move_next();
+            }
+        }
     }
-}
 
-impl<T> IntoIterator for LinkedList<T> {
-    type IntoIter = IntoIter<T>;
-    type Item = T;
+    pub fn contains(&self, x: &T) -> bool
+    where
+        T: PartialEq,
+    {
+        self.iter().any(|elem| elem == x)
+    }
 
-    fn into_iter(self) -> Self::IntoIter {
-        self.into_iter()
+    // Swaps the elements at a and b by swapping the nodes themselves
+    // So handles keep pointing at the same elements
+    pub fn swap(&mut self, a: usize, b: usize) {
+        let len = self.len;
+        assert!(
+            a < len && b < len,
+            "swap indices (are {a} and {b}) should be < len (is {len})"
+        );
+
+        if a == b {
+            return;
+        }
+        let (first, second) = (self.node_at(a.min(b)), self.node_at(a.max(b)));
+        self.forget_finger();
+        unsafe {
+            // ... first ... second after_second ...
+            // Move second in front of first, then first in front of after_second
+            // Works when they're neighbours too, first just stays where it landed
+            let after_second = (*second.as_ptr()).back;
+            self.unlink(second);
+            self.link_before(second, Some(first));
+            self.unlink(first);
+            self.link_before(first, after_second);
+        }
     }
-}
 
-impl<T> Iterator for IntoIter<T> {
-    type Item = T;
+    // Moves the first n elements to the back, like VecDeque::rotate_left
+    // Close the ring, walk to the new front (from the closer end), cut the ring there
+    pub fn rotate_left(&mut self, n: usize) {
+        let len = self.len;
+        assert!(n <= len, "rotation (is {n}) should be <= len (is {len})");
+        if n == 0 || n == len {
+            return;
+        }
 
-    fn next(&mut self) -> Option<Self::Item> {
-        self.list.pop_front()
+        let new_front = self.node_at(n);
+        self.forget_finger();
+        unsafe {
+            let (front, back) = (self.front.unwrap(), self.back.unwrap());
+            (*back.as_ptr()).back = Some(front);
+            (*front.as_ptr()).front = Some(back);
+
+            let new_back = (*new_front.as_ptr()).front.take().unwrap();
+            (*new_back.as_ptr()).back = None;
+            self.front = Some(new_front);
+            self.back = Some(new_back);
+        }
     }
 
-    fn size_hint(&self) -> (usize, Option<usize>) {
-        (self.list.len, Some(self.list.len))
+    // Moves the last n elements to the front
+    pub fn rotate_right(&mut self, n: usize) {
+        let len = self.len;
+        assert!(n <= len, "rotation (is {n}) should be <= len (is {len})");
+        self.rotate_left(len - n);
     }
-}
 
-impl<T> DoubleEndedIterator for IntoIter<T> {
-    fn next_back(&mut self) -> Option<Self::Item> {
-        self.list.pop_back()
+    // Every node just swaps what it considers front and back
+    // Elements never move, so handles and references stay right where they were
+    pub fn reverse(&mut self) {
+        self.forget_finger();
+        let mut cur = self.front;
+        while let Some(node) = cur {
+            unsafe {
+                let node = &mut *node.as_ptr();
+                std::mem::swap(&mut node.front, &mut node.back);
+                // What used to be the next node is now behind us
+                cur = node.front;
+            }
+        }
+        std::mem::swap(&mut self.front, &mut self.back);
     }
-}
 
-impl<T> ExactSizeIterator for IntoIter<T> {
-    fn len(&self) -> usize {
-        self.list.len
+    pub fn get(&self, at: usize) -> Option<&T> {
+        (at < self.len).then(|| unsafe { &(*self.node_at(at).as_ptr()).elem })
     }
-}
 
-pub struct IterMut<'a, T> {
-    front: Link<T>,
-    back: Link<T>,
-    len: usize,
-    _boo: PhantomData<&'a mut T>,
-}
+    pub fn get_mut(&mut self, at: usize) -> Option<&mut T> {
+        (at < self.len).then(|| unsafe { &mut (*self.node_at(at).as_ptr()).elem })
+    }
 
-impl<'a, T> Iterator for IterMut<'a, T> {
-    type Item = &'a mut T;
+    // Node at index at, walking from whichever is closer: front, back or the finger
+    // So going through the list by index is O(1) per step, not O(index)
+    fn node_at(&self, at: usize) -> NonNull<Node<T>> {
+        debug_assert!(at < self.len);
+        let mut finger = self.finger.try_lock().ok();
+        let from_ends = at.min(self.len - 1 - at);
+
+        let node = unsafe {
+            match finger.as_deref().copied().flatten() {
+                Some((index, node)) if index.abs_diff(at) < from_ends => {
+                    let mut node = node.cast::<Node<T>>();
+                    for _ in index..at {
+                        node = (*node.as_ptr()).back.unwrap();
+                    }
+                    for _ in at..index {
+                        node = (*node.as_ptr()).front.unwrap();
+                    }
+                    node
+                }
+                _ => nth_node(self.front, self.back, self.len, at),
+            }
+        };
 
-    fn next(&mut self) -> Option<Self::Item> {
-        // While self.front == self.back is a tempting condition to check here,
-        // it won't do the right for yielding the last element! That sort of
-        // thing only works for arrays because of "one-past-the-end" pointers.
-        if self.len > 0 {
-            // We could unwrap front, but this is safer and easier
-            self.front.map(|node| unsafe {
-                self.len -= 1;
-                self.front = (*node.as_ptr()).back;
-                &mut (*node.as_ptr()).elem
-            })
-        } else {
-            None
+        if let Some(finger) = finger.as_deref_mut() {
+            *finger = Some((at, node.cast()));
         }
+        node
     }
 
-    fn size_hint(&self) -> (usize, Option<usize>) {
-        (self.len, Some(self.len))
+    fn finger_mut(&mut self) -> &mut Option<(usize, NonNull<()>)> {
+        // Nothing can panic while the lock is held, so it never gets poisoned
+        self.finger.get_mut().unwrap()
+    }
+
+    // Anything that moves nodes around calls this, before it moves anything
+    fn forget_finger(&mut self) {
+        *self.finger_mut() = None;
+    }
+
+    // We already know where the node is, no need to walk there again
+    fn cursor_on(&mut self, node: NonNull<Node<T>>, index: usize) -> CursorMut<'_, T, A> {
+        // Cursor can move everything around, and the finger can't follow
+        self.forget_finger();
+        CursorMut {
+            cur: Some(node),
+            list: self,
+            index: Some(index),
+        }
     }
 }
 
-impl<'a, T> DoubleEndedIterator for IterMut<'a, T> {
-    fn next_back(&mut self) -> Option<Self::Item> {
-        if self.len > 0 {
-            self.back.map(|node| unsafe {
-                self.len -= 1;
-                self.back = (*node.as_ptr()).front;
-                &mut (*node.as_ptr()).elem
-            })
+// at-th node of the chain front..=back (len nodes long), walking from whichever end is closer
+unsafe fn nth_node<T>(front: Link<T>, back: Link<T>, len: usize, at: usize) -> NonNull<Node<T>> {
+    unsafe {
+        if at < len / 2 {
+            let mut node = front.unwrap();
+            for _ in 0..at {
+                node = (*node.as_ptr()).back.unwrap();
+            }
+            node
         } else {
-            None
+            let mut node = back.unwrap();
+            for _ in at..len - 1 {
+                node = (*node.as_ptr()).front.unwrap();
+            }
+            node
         }
     }
 }
 
-impl<'a, T> ExactSizeIterator for IterMut<'a, T> {
-    fn len(&self) -> usize {
-        self.len
+// First and last node of the subrange start..end of the chain front..=back
+unsafe fn sub_chain<T>(
+    front: Link<T>,
+    back: Link<T>,
+    len: usize,
+    (start, end): (usize, usize),
+) -> (Link<T>, Link<T>) {
+    if start < end {
+        unsafe {
+            (
+                Some(nth_node(front, back, len, start)),
+                Some(nth_node(front, back, len, end - 1)),
+            )
+        }
+    } else {
+        (None, None)
     }
 }
 
-impl<T> Default for LinkedList<T> {
-    fn default() -> Self {
-        Self::new()
-    }
+// Turns any kind of range into start..end, panicking if it doesn't fit in len
+fn range_bounds<R: RangeBounds<usize>>(range: R, len: usize) -> (usize, usize) {
+    let start = match range.start_bound() {
+        Bound::Included(&start) => start,
+        Bound::Excluded(&start) => start.checked_add(1).expect("range start overflowed"),
+        Bound::Unbounded => 0,
+    };
+    let end = match range.end_bound() {
+        Bound::Included(&end) => end.checked_add(1).expect("range end overflowed"),
+        Bound::Excluded(&end) => end,
+        Bound::Unbounded => len,
+    };
+    assert!(
+        start <= end,
+        "range start (is {start}) should be <= end (is {end})"
+    );
+    assert!(
+        end <= len,
+        "range end (is {end}) should be <= len (is {len})"
+    );
+    (start, end)
 }
 
-impl<T: Clone> Clone for LinkedList<T> {
-    fn clone(&self) -> Self {
-        let mut new_list = Self::new();
-        for item in self {
-            new_list.push_back(item.clone());
+// Plumbing for moving nodes around without allocating
+// All of them trust the caller that the nodes actually belong to self
+impl<T, A: NodeAllocator> LinkedList<T, A> {
+    // Cut the node out of the list, leaving it dangling on its own
+    // The node stays allocated, the caller decides what to do with it
+    unsafe fn unlink(&mut self, node: NonNull<Node<T>>) {
+        unsafe {
+            let prev = (*node.as_ptr()).front.take();
+            let next = (*node.as_ptr()).back.take();
+
+            if let Some(prev) = prev {
+                (*prev.as_ptr()).back = next;
+            } else {
+                self.front = next;
+            }
+            if let Some(next) = next {
+                (*next.as_ptr()).front = prev;
+            } else {
+                self.back = prev;
+            }
+            self.len -= 1;
         }
-        new_list
     }
-}
 
-impl<T> Extend<T> for LinkedList<T> {
-    fn extend<I: IntoIterator<Item=T>>(&mut self, iter: I) {
-        for item in iter {
-            self.push_back(item);
+    // Allocate a dangling node holding elem
+    fn alloc_node(&self, elem: T) -> NonNull<Node<T>> {
+        match self.try_alloc_node(elem) {
+            Ok(node) => node,
+            // Same thing Box does when it runs out of memory
+            Err(err) => handle_alloc_error(err.layout()),
         }
     }
-}
 
-impl<T> FromIterator<T> for LinkedList<T> {
-    fn from_iter<I: IntoIterator<Item=T>>(iter: I) -> Self {
-        let mut list = Self::new();
-        list.extend(iter);
-        list
+    fn try_alloc_node(&self, elem: T) -> Result<NonNull<Node<T>>, TryReserveError> {
+        let layout = Layout::new::<Node<T>>();
+        let node = self
+            .alloc
+            .allocate(layout)
+            .map_err(|_| TryReserveError::new(layout))?
+            .cast::<Node<T>>();
+        unsafe {
+            node.as_ptr().write(Node {
+                front: None,
+                back: None,
+                elem,
+            });
+        }
+        Ok(node)
     }
-}
 
-impl<T: Debug> Debug for LinkedList<T> {
-    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        f.debug_list().entries(self).finish()
+    // Move the node out of its memory, and give the memory back to the allocator
+    // The node has to come from our allocator (or a compatible one)
+    unsafe fn dealloc_node(&self, node: NonNull<Node<T>>) -> Node<T> {
+        unsafe {
+            let old_node = node.as_ptr().read();
+            self.alloc.deallocate(node.cast(), Layout::new::<Node<T>>());
+            old_node
+        }
     }
-}
 
-impl<T: PartialEq> PartialEq for LinkedList<T> {
-    fn eq(&self, other: &Self) -> bool {
-        self.len() == other.len() && self.iter().eq(other)
+    // Empty list sharing our allocator
+    fn empty(&self) -> Self {
+        Self::new_in(self.alloc.clone())
     }
 
-    fn ne(&self, other: &Self) -> bool {
-        self.len() != other.len() || self.iter().ne(other)
+    // Take all of our nodes (and handles) into a new list, we're left empty
+    fn take_all(&mut self) -> Self {
+        let empty = self.empty();
+        std::mem::replace(self, empty)
     }
-}
-
-impl<T: Eq> Eq for LinkedList<T> {}
 
-impl<T: PartialOrd> PartialOrd for LinkedList<T> {
-    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
-        self.iter().partial_cmp(other)
+    // Splicing makes the other list's nodes ours, and one day we'll free them
+    fn assert_compatible(&self, other: &Self) {
+        assert!(
+            self.alloc.is_compatible_with(&other.alloc),
+            "cannot splice lists with incompatible allocators"
+        );
     }
-}
 
-impl<T: Ord> Ord for LinkedList<T> {
-    fn cmp(&self, other: &Self) -> Ordering {
-        self.iter().cmp(other)
+    // Free a node that was already unlinked, and hand back its element
+    unsafe fn free(&mut self, node: NonNull<Node<T>>) -> T {
+        self.forget_handle(node);
+        unsafe { self.dealloc_node(node).elem }
     }
-}
 
-impl<T: Hash> Hash for LinkedList<T> {
-    fn hash<H: Hasher>(&self, state: &mut H) {
-        self.len().hash(state);
-        for item in self {
-            item.hash(state);
+    // The node is about to be freed, every copy of its handle has to know
+    fn forget_handle(&mut self, node: NonNull<Node<T>>) {
+        // Skips hashing when there are no handles at all, the usual case
+        if !self.handles.is_empty()
+            && let Some(owner) = self.handles.remove(&node.cast())
+        {
+            owner.store(0, Relaxed);
         }
     }
-}
 
-/*
-    Send -> allow sending to a different thread
-    Sync -> allow sharing with a different thread
+    // Put a dangling node at the front
+    unsafe fn link_front(&mut self, node: NonNull<Node<T>>) {
+        unsafe {
+            (*node.as_ptr()).front = None;
+            (*node.as_ptr()).back = self.front;
+            if let Some(old) = self.front {
+                (*old.as_ptr()).front = Some(node);
+            } else {
+                self.back = Some(node);
+            }
+            self.front = Some(node);
+            self.len += 1;
+        }
+    }
 
-    Auto-derived, so normally no need to opt-in, you rather opt-out
-    But we have raw-pointers through NonNull, they outed out of it
-    so we need to opt back in
-*/
-unsafe impl<T: Send> Send for LinkedList<T> {}
-unsafe impl<T: Sync> Sync for LinkedList<T> {}
+    // Put a dangling node at the back
+    unsafe fn link_back(&mut self, node: NonNull<Node<T>>) {
+        unsafe {
+            (*node.as_ptr()).back = None;
+            (*node.as_ptr()).front = self.back;
+            if let Some(old) = self.back {
+                (*old.as_ptr()).back = Some(node);
+            } else {
+                self.front = Some(node);
+            }
+            self.back = Some(node);
+            self.len += 1;
+        }
+    }
 
-unsafe impl<'a, T: Send> Send for Iter<'a, T> {}
-unsafe impl<'a, T: Sync> Sync for Iter<'a, T> {}
+    // Put a dangling node right before next, or at the back if there's no next
+    unsafe fn link_before(&mut self, node: NonNull<Node<T>>, next: Link<T>) {
+        unsafe {
+            let Some(next) = next else {
+                self.link_back(node);
+                return;
+            };
+            let prev = (*next.as_ptr()).front;
+            (*node.as_ptr()).front = prev;
+            (*node.as_ptr()).back = Some(next);
+            (*next.as_ptr()).front = Some(node);
+            match prev {
+                Some(prev) => (*prev.as_ptr()).back = Some(node),
+                None => self.front = Some(node),
+            }
+            self.len += 1;
+        }
+    }
 
-unsafe impl<'a, T: Send> Send for IterMut<'a, T> {}
-unsafe impl<'a, T: Sync> Sync for IterMut<'a, T> {}
+    // Wrap a chain of nodes that was already cut out of self into its own list
+    // Any handles pointing into the chain follow it to the new owner
+    unsafe fn split_chain(
+        &mut self,
+        front: Link<T>,
+        back: Link<T>,
+        len: usize,
+    ) -> LinkedList<T, A> {
+        let mut output = LinkedList {
+            front,
+            back,
+            len,
+            id: 0,
+            handles: HashMap::new(),
+            finger: Mutex::new(None),
+            alloc: self.alloc.clone(),
+            _boo: PhantomData,
+        };
+        if !self.handles.is_empty() {
+            // Someone is watching, walk the chain and hand over what's handled
+            let id = output.id();
+            let mut cur = output.front;
+            while let Some(node) = cur {
+                if let Some(owner) = self.handles.remove(&node.cast()) {
+                    owner.store(id, Relaxed);
+                    output.handles.insert(node.cast(), owner);
+                }
+                cur = unsafe { (*node.as_ptr()).back };
+            }
+        }
+        output
+    }
 
-// More or less an iterator for the list
-// Points between elements, and allows inserting before/after element
-// splicing
-// etc.
-pub struct CursorMut<'a, T> {
-    cur: Link<T>,
-    list: &'a mut LinkedList<T>,
-    index: Option<usize>,
-}
+    // Input's nodes are about to become ours, make their handles point at us
+    // Only has to visit the handled nodes, not the whole chain
+    fn adopt_handles(&mut self, input: &mut LinkedList<T, A>) {
+        if !input.handles.is_empty() {
+            let id = self.id();
+            for (node, owner) in input.handles.drain() {
+                owner.store(id, Relaxed);
+                self.handles.insert(node, owner);
+            }
+        }
+    }
 
-impl<T> LinkedList<T> {
-    pub fn cursor_mut(&mut self) -> CursorMut<T> {
-        CursorMut {
-            list: self,
-            cur: None,
-            index: None,
+    fn id(&mut self) -> u64 {
+        if self.id == 0 {
+            self.id = NEXT_LIST_ID.fetch_add(1, Relaxed);
         }
+        self.id
     }
 }
 
-impl<'a, T> CursorMut<'a, T> {
-    pub fn index(&self) -> Option<usize> {
-        self.index
+// Stable pointer to a single node, for O(1) access and removal from the middle
+// Stays valid while the node lives, no matter how the list around it changes
+// Also follows the node when it gets split/spliced into another list
+// Using it with the wrong list, or after the node is gone, is always detected
+// and every operation just returns None/false, no UB possible
+pub struct NodeHandle<T> {
+    node: NonNull<Node<T>>,
+    owner: Owner,
+}
+
+impl<T, A: NodeAllocator> LinkedList<T, A> {
+    pub fn push_front_handle(&mut self, elem: T) -> NodeHandle<T> {
+        self.push_front(elem);
+        unsafe { self.handle_to(self.front.unwrap()) }
     }
 
-    pub fn move_next(&mut self) {
-        if let Some(cur) = self.cur {
-            unsafe {
-                // We're on a real element, go to its next (back)
-                self.cur = (*cur.as_ptr()).back;
-                if self.cur.is_some() {
-                    *self.index.as_mut().unwrap() += 1;
-                } else {
-                    // We just walked to the ghost, no more index
-                    self.index = None;
-                }
+    pub fn push_back_handle(&mut self, elem: T) -> NodeHandle<T> {
+        self.push_back(elem);
+        unsafe { self.handle_to(self.back.unwrap()) }
+    }
+
+    // Cursor sitting on the handle's node
+    // Cursor needs an index, so this one walks to the front, O(index)
+    pub fn cursor_at(&mut self, handle: &NodeHandle<T>) -> Option<CursorMut<'_, T, A>> {
+        let node = handle.node_in(self)?;
+        let mut index = 0;
+        let mut cur = node;
+        unsafe {
+            while let Some(prev) = (*cur.as_ptr()).front {
+                index += 1;
+                cur = prev;
             }
-        } else if !self.list.is_empty() {
-            // We're at the ghost, and there is a real front, so move to it!
-            self.cur = self.list.front;
-            self.index = Some(0)
-        } else {
-            // We're at the ghost, but that's the only element... do nothing.
         }
+        Some(self.cursor_on(node, index))
     }
 
-    pub fn move_prev(&mut self) {
-        if let Some(cur) = self.cur {
-            unsafe {
-                // We're on a real element, go to its previous (front)
-                self.cur = (*cur.as_ptr()).front;
-                if self.cur.is_some() {
-                    *self.index.as_mut().unwrap() -= 1;
-                } else {
-                    // We just walked to the ghost, no more index
-                    self.index = None;
-                }
-            }
-        } else if !self.list.is_empty() {
-            // We're at the ghost, and there is a real back, so move to it!
-            self.cur = self.list.back;
-            self.index = Some(self.list.len - 1)
-        } else {
-            // We're at the ghost, but that's the only element... do nothing.
+    unsafe fn handle_to(&mut self, node: NonNull<Node<T>>) -> NodeHandle<T> {
+        let id = self.id();
+        let owner = self
+            .handles
+            .entry(node.cast())
+            .or_insert_with(|| Arc::new(AtomicU64::new(id)));
+        NodeHandle {
+            node,
+            owner: owner.clone(),
         }
     }
+}
 
-    pub fn current(&mut self) -> Option<&mut T> {
-        unsafe { self.cur.map(|node| &mut (*node.as_ptr()).elem) }
+impl<T> NodeHandle<T> {
+    // The node, if it is alive and lives in this list
+    // The owner id only matches when both are true, so the node is safe to touch
+    fn node_in<A: NodeAllocator>(&self, list: &LinkedList<T, A>) -> Option<NonNull<Node<T>>> {
+        let owner = self.owner.load(Relaxed);
+        (owner != 0 && owner == list.id).then_some(self.node)
     }
 
-    pub fn peek_next(&mut self) -> Option<&mut T> {
+    pub fn get<'a, A: NodeAllocator>(&self, list: &'a LinkedList<T, A>) -> Option<&'a T> {
+        self.node_in(list)
+            .map(|node| unsafe { &(*node.as_ptr()).elem })
+    }
+
+    pub fn get_mut<'a, A: NodeAllocator>(
+        &self,
+        list: &'a mut LinkedList<T, A>,
+    ) -> Option<&'a mut T> {
+        self.node_in(list)
+            .map(|node| unsafe { &mut (*node.as_ptr()).elem })
+    }
+
+    pub fn remove<A: NodeAllocator>(self, list: &mut LinkedList<T, A>) -> Option<T> {
+        let node = self.node_in(list)?;
+        list.forget_finger();
         unsafe {
-            let next = if let Some(cur) = self.cur {
-                // Normal case, try to follow the cur node's back pointer
-                (*cur.as_ptr()).back
-            } else {
-                // Ghost case, try to use the list's front pointer
-                self.list.front
-            };
+            list.unlink(node);
+            // Freeing it marks every other copy of the handle as dead
+            Some(list.free(node))
+        }
+    }
 
-            // Yield the element if the next node exists
-            next.map(|node| &mut (*node.as_ptr()).elem)
+    pub fn move_to_front<A: NodeAllocator>(&self, list: &mut LinkedList<T, A>) -> bool {
+        let Some(node) = self.node_in(list) else {
+            return false;
+        };
+        list.forget_finger();
+        unsafe {
+            list.unlink(node);
+            list.link_front(node);
         }
+        true
     }
 
-    pub fn peek_prev(&mut self) -> Option<&mut T> {
+    pub fn move_to_back<A: NodeAllocator>(&self, list: &mut LinkedList<T, A>) -> bool {
+        let Some(node) = self.node_in(list) else {
+            return false;
+        };
+        list.forget_finger();
         unsafe {
-            let prev = if let Some(cur) = self.cur {
-                // Normal case, try to follow the cur node's front pointer
-                (*cur.as_ptr()).front
-            } else {
-                // Ghost case, try to use the list's back pointer
-                self.list.back
-            };
+            list.unlink(node);
+            list.link_back(node);
+        }
+        true
+    }
+}
 
-            // Yield the element if the prev node exists
-            prev.map(|node| &mut (*node.as_ptr()).elem)
+// Copies of a handle all point at the same node
+impl<T> Clone for NodeHandle<T> {
+    fn clone(&self) -> Self {
+        NodeHandle {
+            node: self.node,
+            owner: self.owner.clone(),
         }
     }
+}
 
+impl<T> Debug for NodeHandle<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("NodeHandle")
+            .field("node", &self.node)
+            .field("owner", &self.owner.load(Relaxed))
+            .finish()
+    }
+}
 
+// That's a lot of code for iterators
 
-    pub fn split_before(&mut self) -> LinkedList<T> {
-        if let Some(cur) = self.cur {
-            // We are pointing at a real element, so the list is non-empty.
+impl<T, A: NodeAllocator> Drop for LinkedList<T, A> {
+    fn drop(&mut self) {
+        // We are about to stop existing
+        // So... no real need to keep track of our state and update it constantly
+        // Just iterate over it and free as we go
+        // Handles only need to hear that their nodes are gone
+        for owner in self.handles.values() {
+            owner.store(0, Relaxed);
+        }
+        while let Some(ptr) = self.front {
             unsafe {
-                // Current state
-                let old_len = self.list.len;
-                let old_idx = self.index.unwrap();
-                let prev = (*cur.as_ptr()).front;
-
-                // What self will become
-                let new_len = old_len - old_idx;
+                let node = self.dealloc_node(ptr);
+                self.front = node.back;
+            }
+        }
+    }
+}
+
+pub struct Iter<'a, T> {
+    front: Link<T>,
+    back: Link<T>,
+    len: usize,
+    _boo: PhantomData<&'a T>,
+}
+
+impl<T, A: NodeAllocator> LinkedList<T, A> {
+    pub fn iter(&self) -> Iter<'_, T> {
+        Iter {
+            front: self.front,
+            back: self.back,
+            len: self.len,
+            _boo: PhantomData,
+        }
+    }
+}
+
+impl<'a, T, A: NodeAllocator> IntoIterator for &'a LinkedList<T, A> {
+    type IntoIter = Iter<'a, T>;
+    type Item = &'a T;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+impl<'a, T> Iterator for Iter<'a, T> {
+    type Item = &'a T;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        // While self.front == self.back is a tempting condition to check here,
+        // it won't do the right for yielding the last element! That sort of
+        // thing only works for arrays because of "one-past-the-end" pointers.
+        if self.len > 0 {
+            // We could unwrap front, but this is safer and easier
+            self.front.map(|node| unsafe {
+                self.len -= 1;
+                self.front = (*node.as_ptr()).back;
+                &(*node.as_ptr()).elem
+            })
+        } else {
+            None
+        }
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.len, Some(self.len))
+    }
+}
+
+impl<'a, T> DoubleEndedIterator for Iter<'a, T> {
+    fn next_back(&mut self) -> Option<Self::Item> {
+        if self.len > 0 {
+            self.back.map(|node| unsafe {
+                self.len -= 1;
+                self.back = (*node.as_ptr()).front;
+                &(*node.as_ptr()).elem
+            })
+        } else {
+            None
+        }
+    }
+}
+
+impl<'a, T> ExactSizeIterator for Iter<'a, T> {
+    fn len(&self) -> usize {
+        self.len
+    }
+}
+
+pub struct IntoIter<T, A: NodeAllocator = Global> {
+    list: LinkedList<T, A>,
+}
+
+impl<T, A: NodeAllocator> IntoIterator for LinkedList<T, A> {
+    type IntoIter = IntoIter<T, A>;
+    type Item = T;
+
+    fn into_iter(self) -> Self::IntoIter {
+        IntoIter { list: self }
+    }
+}
+
+impl<T, A: NodeAllocator> Iterator for IntoIter<T, A> {
+    type Item = T;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.list.pop_front()
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.list.len, Some(self.list.len))
+    }
+}
+
+impl<T, A: NodeAllocator> DoubleEndedIterator for IntoIter<T, A> {
+    fn next_back(&mut self) -> Option<Self::Item> {
+        self.list.pop_back()
+    }
+}
+
+impl<T, A: NodeAllocator> ExactSizeIterator for IntoIter<T, A> {
+    fn len(&self) -> usize {
+        self.list.len
+    }
+}
+
+pub struct IterMut<'a, T> {
+    front: Link<T>,
+    back: Link<T>,
+    len: usize,
+    _boo: PhantomData<&'a mut T>,
+}
+
+impl<'a, T> Iterator for IterMut<'a, T> {
+    type Item = &'a mut T;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        // While self.front == self.back is a tempting condition to check here,
+        // it won't do the right for yielding the last element! That sort of
+        // thing only works for arrays because of "one-past-the-end" pointers.
+        if self.len > 0 {
+            // We could unwrap front, but this is safer and easier
+            self.front.map(|node| unsafe {
+                self.len -= 1;
+                self.front = (*node.as_ptr()).back;
+                &mut (*node.as_ptr()).elem
+            })
+        } else {
+            None
+        }
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.len, Some(self.len))
+    }
+}
+
+impl<'a, T> DoubleEndedIterator for IterMut<'a, T> {
+    fn next_back(&mut self) -> Option<Self::Item> {
+        if self.len > 0 {
+            self.back.map(|node| unsafe {
+                self.len -= 1;
+                self.back = (*node.as_ptr()).front;
+                &mut (*node.as_ptr()).elem
+            })
+        } else {
+            None
+        }
+    }
+}
+
+impl<'a, T> ExactSizeIterator for IterMut<'a, T> {
+    fn len(&self) -> usize {
+        self.len
+    }
+}
+
+// Removes a range of elements, one node at a time as we iterate
+// The list is a valid list at every step, with whatever wasn't drained yet still in it
+// So dropping it halfway, or even mem::forget-ing it, is fine
+pub struct Drain<'a, T, A: NodeAllocator = Global> {
+    list: &'a mut LinkedList<T, A>,
+    // Next nodes to drain from both ends, only meaningful while len > 0
+    front: Link<T>,
+    back: Link<T>,
+    len: usize,
+}
+
+impl<T, A: NodeAllocator> LinkedList<T, A> {
+    pub fn drain<R: RangeBounds<usize>>(&mut self, range: R) -> Drain<'_, T, A> {
+        self.forget_finger();
+        let (start, end) = range_bounds(range, self.len);
+        let (front, back) = unsafe { sub_chain(self.front, self.back, self.len, (start, end)) };
+        Drain {
+            list: self,
+            front,
+            back,
+            len: end - start,
+        }
+    }
+}
+
+impl<T, A: NodeAllocator> Iterator for Drain<'_, T, A> {
+    type Item = T;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.len == 0 {
+            return None;
+        }
+        self.front.map(|node| unsafe {
+            self.len -= 1;
+            self.front = (*node.as_ptr()).back;
+            self.list.unlink(node);
+            self.list.free(node)
+        })
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.len, Some(self.len))
+    }
+}
+
+impl<T, A: NodeAllocator> DoubleEndedIterator for Drain<'_, T, A> {
+    fn next_back(&mut self) -> Option<Self::Item> {
+        if self.len == 0 {
+            return None;
+        }
+        self.back.map(|node| unsafe {
+            self.len -= 1;
+            self.back = (*node.as_ptr()).front;
+            self.list.unlink(node);
+            self.list.free(node)
+        })
+    }
+}
+
+impl<T, A: NodeAllocator> ExactSizeIterator for Drain<'_, T, A> {
+    fn len(&self) -> usize {
+        self.len
+    }
+}
+
+impl<T, A: NodeAllocator> FusedIterator for Drain<'_, T, A> {}
+
+impl<T, A: NodeAllocator> Drop for Drain<'_, T, A> {
+    fn drop(&mut self) {
+        // Whatever is left in the range still has to go
+        // If an element panics in its Drop, the rest just stays in the list
+        for _ in self.by_ref() {}
+    }
+}
+
+// Removes (and yields) every element the predicate says yes to, one at a time
+// Dropping it stops right there, everything not visited yet stays in the list
+pub struct ExtractIf<'a, T, F: FnMut(&mut T) -> bool, A: NodeAllocator = Global> {
+    list: &'a mut LinkedList<T, A>,
+    // Next node to ask the predicate about
+    next: Link<T>,
+    // How many nodes we haven't looked at yet
+    remaining: usize,
+    pred: F,
+}
+
+impl<T, A: NodeAllocator> LinkedList<T, A> {
+    pub fn extract_if<F: FnMut(&mut T) -> bool>(&mut self, pred: F) -> ExtractIf<'_, T, F, A> {
+        self.forget_finger();
+        ExtractIf {
+            next: self.front,
+            remaining: self.len,
+            list: self,
+            pred,
+        }
+    }
+}
+
+impl<T, F: FnMut(&mut T) -> bool, A: NodeAllocator> Iterator for ExtractIf<'_, T, F, A> {
+    type Item = T;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        while let Some(node) = self.next {
+            unsafe {
+                // Step past the node first, so a panicking predicate just leaves it in the list
+                self.next = (*node.as_ptr()).back;
+                self.remaining -= 1;
+                if (self.pred)(&mut (*node.as_ptr()).elem) {
+                    self.list.unlink(node);
+                    return Some(self.list.free(node));
+                }
+            }
+        }
+        None
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (0, Some(self.remaining))
+    }
+}
+
+impl<T, F: FnMut(&mut T) -> bool, A: NodeAllocator> FusedIterator for ExtractIf<'_, T, F, A> {}
+
+// Borrowed view of a part of the list, bounded by its first and last node
+// Pretty much an Iter that doesn't get used up
+pub struct ListSlice<'a, T> {
+    front: Link<T>,
+    back: Link<T>,
+    len: usize,
+    _boo: PhantomData<&'a T>,
+}
+
+// Mutable view of a part of the list
+// Can relink the nodes inside of it (reverse, sort), but never the ones outside
+// Doesn't keep its own front and back, but the nodes right outside of it
+// reverse/sort relink the nodes inside a view, also through a range_mut of it,
+// and whatever ends it had stored would be stale after that
+// The nodes outside never move while the view lives, so the ends are always found fresh from them
+pub struct ListSliceMut<'a, T, A: NodeAllocator = Global> {
+    list: &'a mut LinkedList<T, A>,
+    // Node before our front, None if we start at the list's front
+    before: Link<T>,
+    // Node after our back, None if we end at the list's back
+    after: Link<T>,
+    len: usize,
+}
+
+impl<T, A: NodeAllocator> LinkedList<T, A> {
+    pub fn range<R: RangeBounds<usize>>(&self, range: R) -> ListSlice<'_, T> {
+        let bounds = range_bounds(range, self.len);
+        let (front, back) = unsafe { sub_chain(self.front, self.back, self.len, bounds) };
+        ListSlice {
+            front,
+            back,
+            len: bounds.1 - bounds.0,
+            _boo: PhantomData,
+        }
+    }
+
+    pub fn range_mut<R: RangeBounds<usize>>(&mut self, range: R) -> ListSliceMut<'_, T, A> {
+        self.forget_finger();
+        let bounds = range_bounds(range, self.len);
+        let (front, back) = unsafe { sub_chain(self.front, self.back, self.len, bounds) };
+        unsafe { ListSliceMut::new(self, front, back, bounds.1 - bounds.0) }
+    }
+}
+
+impl<'a, T> ListSlice<'a, T> {
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    pub fn iter(&self) -> Iter<'a, T> {
+        Iter {
+            front: self.front,
+            back: self.back,
+            len: self.len,
+            _boo: PhantomData,
+        }
+    }
+
+    pub fn first(&self) -> Option<&'a T> {
+        unsafe { self.front.map(|node| &(*node.as_ptr()).elem) }
+    }
+
+    pub fn last(&self) -> Option<&'a T> {
+        unsafe { self.back.map(|node| &(*node.as_ptr()).elem) }
+    }
+
+    // Slice of a slice, indices are relative to this one
+    pub fn range<R: RangeBounds<usize>>(&self, range: R) -> ListSlice<'a, T> {
+        let bounds = range_bounds(range, self.len);
+        let (front, back) = unsafe { sub_chain(self.front, self.back, self.len, bounds) };
+        ListSlice {
+            front,
+            back,
+            len: bounds.1 - bounds.0,
+            _boo: PhantomData,
+        }
+    }
+}
+
+impl<T> Clone for ListSlice<'_, T> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<T> Copy for ListSlice<'_, T> {}
+
+impl<'a, T> IntoIterator for ListSlice<'a, T> {
+    type IntoIter = Iter<'a, T>;
+    type Item = &'a T;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+impl<T: Debug> Debug for ListSlice<'_, T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_list().entries(self.iter()).finish()
+    }
+}
+
+impl<'a, T, A: NodeAllocator> ListSliceMut<'a, T, A> {
+    // front..=back has to be a chain of len nodes in list (both None if len is 0)
+    unsafe fn new(
+        list: &'a mut LinkedList<T, A>,
+        front: Link<T>,
+        back: Link<T>,
+        len: usize,
+    ) -> Self {
+        let (before, after) = unsafe {
+            (
+                front.and_then(|node| (*node.as_ptr()).front),
+                back.and_then(|node| (*node.as_ptr()).back),
+            )
+        };
+        ListSliceMut {
+            list,
+            before,
+            after,
+            len,
+        }
+    }
+
+    fn front(&self) -> Link<T> {
+        if self.len == 0 {
+            return None;
+        }
+        match self.before {
+            Some(before) => unsafe { (*before.as_ptr()).back },
+            None => self.list.front,
+        }
+    }
+
+    fn back(&self) -> Link<T> {
+        if self.len == 0 {
+            return None;
+        }
+        match self.after {
+            Some(after) => unsafe { (*after.as_ptr()).front },
+            None => self.list.back,
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    pub fn as_slice(&self) -> ListSlice<'_, T> {
+        ListSlice {
+            front: self.front(),
+            back: self.back(),
+            len: self.len,
+            _boo: PhantomData,
+        }
+    }
+
+    pub fn iter(&self) -> Iter<'_, T> {
+        self.as_slice().iter()
+    }
+
+    pub fn iter_mut(&mut self) -> IterMut<'_, T> {
+        IterMut {
+            front: self.front(),
+            back: self.back(),
+            len: self.len,
+            _boo: PhantomData,
+        }
+    }
+
+    pub fn first(&self) -> Option<&T> {
+        unsafe { self.front().map(|node| &(*node.as_ptr()).elem) }
+    }
+
+    pub fn first_mut(&mut self) -> Option<&mut T> {
+        unsafe { self.front().map(|node| &mut (*node.as_ptr()).elem) }
+    }
+
+    pub fn last(&self) -> Option<&T> {
+        unsafe { self.back().map(|node| &(*node.as_ptr()).elem) }
+    }
+
+    pub fn last_mut(&mut self) -> Option<&mut T> {
+        unsafe { self.back().map(|node| &mut (*node.as_ptr()).elem) }
+    }
+
+    pub fn range<R: RangeBounds<usize>>(&self, range: R) -> ListSlice<'_, T> {
+        self.as_slice().range(range)
+    }
+
+    // Mutable slice of a slice, indices are relative to this one
+    pub fn range_mut<R: RangeBounds<usize>>(&mut self, range: R) -> ListSliceMut<'_, T, A> {
+        let bounds = range_bounds(range, self.len);
+        let (front, back) = unsafe { sub_chain(self.front(), self.back(), self.len, bounds) };
+        unsafe { ListSliceMut::new(self.list, front, back, bounds.1 - bounds.0) }
+    }
+
+    // Flip the nodes of the view around, the rest of the list doesn't move
+    pub fn reverse(&mut self) {
+        self.detached(LinkedList::reverse);
+    }
+
+    pub fn sort(&mut self)
+    where
+        T: Ord,
+    {
+        self.sort_by(T::cmp);
+    }
+
+    pub fn sort_by_key<K: Ord, F: FnMut(&T) -> K>(&mut self, mut f: F) {
+        self.sort_by(|a, b| f(a).cmp(&f(b)));
+    }
+
+    // Same guarantees as LinkedList::sort_by, a panic leaves the view (and list) valid
+    pub fn sort_by<F: FnMut(&T, &T) -> Ordering>(&mut self, compare: F) {
+        self.detached(|chain| chain.sort_by(compare));
+    }
+
+    // Cut the view's nodes out into a temporary list, let f play with it, and put them back
+    // Putting back happens in Drop, so a panic in f can't leave a hole in the list
+    fn detached(&mut self, f: impl FnOnce(&mut LinkedList<T, A>)) {
+        let (Some(front), Some(back)) = (self.front(), self.back()) else {
+            return;
+        };
+        unsafe {
+            // Cut it loose from before and after, Reattach links it back to them
+            (*front.as_ptr()).front = None;
+            (*back.as_ptr()).back = None;
+            let mut guard = Reattach {
+                // Handles inside the chain still say they belong to the list
+                // That's fine, this list never frees or hands out anything
+                chain: LinkedList {
+                    front: Some(front),
+                    back: Some(back),
+                    len: self.len,
+                    id: 0,
+                    handles: HashMap::new(),
+                    finger: Mutex::new(None),
+                    alloc: self.list.alloc.clone(),
+                    _boo: PhantomData,
+                },
+                slice: self,
+            };
+            f(&mut guard.chain);
+        }
+    }
+}
+
+struct Reattach<'s, 'a, T, A: NodeAllocator> {
+    slice: &'s mut ListSliceMut<'a, T, A>,
+    chain: LinkedList<T, A>,
+}
+
+impl<T, A: NodeAllocator> Drop for Reattach<'_, '_, T, A> {
+    fn drop(&mut self) {
+        // Take everything, so the temporary list drops as an empty one
+        let front = self.chain.front.take().unwrap();
+        let back = self.chain.back.take().unwrap();
+        self.chain.len = 0;
+
+        // The new front and back hang off before and after again, which is all the view
+        // (and any view it was made from) needs to find them
+        let (before, after) = (self.slice.before, self.slice.after);
+        unsafe {
+            (*front.as_ptr()).front = before;
+            match before {
+                Some(before) => (*before.as_ptr()).back = Some(front),
+                None => self.slice.list.front = Some(front),
+            }
+            (*back.as_ptr()).back = after;
+            match after {
+                Some(after) => (*after.as_ptr()).front = Some(back),
+                None => self.slice.list.back = Some(back),
+            }
+        }
+    }
+}
+
+impl<T: Debug, A: NodeAllocator> Debug for ListSliceMut<'_, T, A> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_list().entries(self.iter()).finish()
+    }
+}
+
+impl<T, A: NodeAllocator> Index<usize> for LinkedList<T, A> {
+    type Output = T;
+
+    fn index(&self, at: usize) -> &T {
+        let len = self.len;
+        self.get(at).unwrap_or_else(|| {
+            panic!("index out of bounds: the len is {len} but the index is {at}")
+        })
+    }
+}
+
+impl<T, A: NodeAllocator> IndexMut<usize> for LinkedList<T, A> {
+    fn index_mut(&mut self, at: usize) -> &mut T {
+        let len = self.len;
+        self.get_mut(at).unwrap_or_else(|| {
+            panic!("index out of bounds: the len is {len} but the index is {at}")
+        })
+    }
+}
+
+impl<T, A: NodeAllocator + Default> Default for LinkedList<T, A> {
+    fn default() -> Self {
+        Self::new_in(A::default())
+    }
+}
+
+impl<T: Clone, A: NodeAllocator> Clone for LinkedList<T, A> {
+    fn clone(&self) -> Self {
+        // The copy lives in the same allocator as we do
+        let mut new_list = self.empty();
+        for item in self {
+            new_list.push_back(item.clone());
+        }
+        new_list
+    }
+}
+
+impl<T, A: NodeAllocator> Extend<T> for LinkedList<T, A> {
+    fn extend<I: IntoIterator<Item=T>>(&mut self, iter: I) {
+        for item in iter {
+            self.push_back(item);
+        }
+    }
+}
+
+impl<T, A: NodeAllocator + Default> FromIterator<T> for LinkedList<T, A> {
+    fn from_iter<I: IntoIterator<Item = T>>(iter: I) -> Self {
+        let mut list = Self::default();
+        list.extend(iter);
+        list
+    }
+}
+
+impl<T: Debug, A: NodeAllocator> Debug for LinkedList<T, A> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_list().entries(self).finish()
+    }
+}
+
+impl<T: PartialEq, A: NodeAllocator> PartialEq for LinkedList<T, A> {
+    fn eq(&self, other: &Self) -> bool {
+        self.len() == other.len() && self.iter().eq(other)
+    }
+}
+
+impl<T: Eq, A: NodeAllocator> Eq for LinkedList<T, A> {}
+
+impl<T: PartialOrd, A: NodeAllocator> PartialOrd for LinkedList<T, A> {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        self.iter().partial_cmp(other)
+    }
+}
+
+impl<T: Ord, A: NodeAllocator> Ord for LinkedList<T, A> {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.iter().cmp(other)
+    }
+}
+
+impl<T: Hash, A: NodeAllocator> Hash for LinkedList<T, A> {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.len().hash(state);
+        for item in self {
+            item.hash(state);
+        }
+    }
+}
+
+/*
+    Send -> allow sending to a different thread
+    Sync -> allow sharing with a different thread
+
+    Auto-derived, so normally no need to opt-in, you rather opt-out
+    But we have raw-pointers through NonNull, they outed out of it
+    so we need to opt back in
+*/
+unsafe impl<T: Send, A: NodeAllocator + Send> Send for LinkedList<T, A> {}
+unsafe impl<T: Sync, A: NodeAllocator + Sync> Sync for LinkedList<T, A> {}
+
+unsafe impl<'a, T: Send> Send for Iter<'a, T> {}
+unsafe impl<'a, T: Sync> Sync for Iter<'a, T> {}
+
+unsafe impl<'a, T: Send> Send for IterMut<'a, T> {}
+unsafe impl<'a, T: Sync> Sync for IterMut<'a, T> {}
+
+unsafe impl<'a, T: Send, A: NodeAllocator + Send> Send for Drain<'a, T, A> {}
+unsafe impl<'a, T: Sync, A: NodeAllocator + Sync> Sync for Drain<'a, T, A> {}
+
+unsafe impl<'a, T: Send, F: FnMut(&mut T) -> bool + Send, A: NodeAllocator + Send> Send
+    for ExtractIf<'a, T, F, A>
+{
+}
+unsafe impl<'a, T: Sync, F: FnMut(&mut T) -> bool + Sync, A: NodeAllocator + Sync> Sync
+    for ExtractIf<'a, T, F, A>
+{
+}
+
+unsafe impl<'a, T: Sync> Send for ListSlice<'a, T> {}
+unsafe impl<'a, T: Sync> Sync for ListSlice<'a, T> {}
+
+unsafe impl<'a, T: Send, A: NodeAllocator + Send> Send for ListSliceMut<'a, T, A> {}
+unsafe impl<'a, T: Sync, A: NodeAllocator + Sync> Sync for ListSliceMut<'a, T, A> {}
+
+// A handle never touches its node on its own, it needs the list to do anything
+// So it's just an id we can pass around freely
+unsafe impl<T> Send for NodeHandle<T> {}
+unsafe impl<T> Sync for NodeHandle<T> {}
+
+// More or less an iterator for the list
+// Points between elements, and allows inserting before/after element
+// splicing
+// etc.
+pub struct CursorMut<'a, T, A: NodeAllocator = Global> {
+    cur: Link<T>,
+    list: &'a mut LinkedList<T, A>,
+    index: Option<usize>,
+}
+
+impl<T, A: NodeAllocator> LinkedList<T, A> {
+    pub fn cursor_mut(&mut self) -> CursorMut<'_, T, A> {
+        // Cursor can move everything around, and the finger can't follow
+        self.forget_finger();
+        CursorMut {
+            list: self,
+            cur: None,
+            index: None,
+        }
+    }
+}
+
+impl<'a, T, A: NodeAllocator> CursorMut<'a, T, A> {
+    pub fn index(&self) -> Option<usize> {
+        self.index
+    }
+
+    pub fn move_next(&mut self) {
+        if let Some(cur) = self.cur {
+            unsafe {
+                // We're on a real element, go to its next (back)
+                self.cur = (*cur.as_ptr()).back;
+                if self.cur.is_some() {
+                    *self.index.as_mut().unwrap() += 1;
+                } else {
+                    // We just walked to the ghost, no more index
+                    self.index = None;
+                }
+            }
+        } else if !self.list.is_empty() {
+            // We're at the ghost, and there is a real front, so move to it!
+            self.cur = self.list.front;
+            self.index = Some(0)
+        } else {
+            // We're at the ghost, but that's the only element... do nothing.
+        }
+    }
+
+    pub fn move_prev(&mut self) {
+        if let Some(cur) = self.cur {
+            unsafe {
+                // We're on a real element, go to its previous (front)
+                self.cur = (*cur.as_ptr()).front;
+                if self.cur.is_some() {
+                    *self.index.as_mut().unwrap() -= 1;
+                } else {
+                    // We just walked to the ghost, no more index
+                    self.index = None;
+                }
+            }
+        } else if !self.list.is_empty() {
+            // We're at the ghost, and there is a real back, so move to it!
+            self.cur = self.list.back;
+            self.index = Some(self.list.len - 1)
+        } else {
+            // We're at the ghost, but that's the only element... do nothing.
+        }
+    }
+
+    pub fn current(&mut self) -> Option<&mut T> {
+        unsafe { self.cur.map(|node| &mut (*node.as_ptr()).elem) }
+    }
+
+    pub fn peek_next(&mut self) -> Option<&mut T> {
+        unsafe {
+            let next = if let Some(cur) = self.cur {
+                // Normal case, try to follow the cur node's back pointer
+                (*cur.as_ptr()).back
+            } else {
+                // Ghost case, try to use the list's front pointer
+                self.list.front
+            };
+
+            // Yield the element if the next node exists
+            next.map(|node| &mut (*node.as_ptr()).elem)
+        }
+    }
+
+    pub fn peek_prev(&mut self) -> Option<&mut T> {
+        unsafe {
+            let prev = if let Some(cur) = self.cur {
+                // Normal case, try to follow the cur node's front pointer
+                (*cur.as_ptr()).front
+            } else {
+                // Ghost case, try to use the list's back pointer
+                self.list.back
+            };
+
+            // Yield the element if the prev node exists
+            prev.map(|node| &mut (*node.as_ptr()).elem)
+        }
+    }
+
+    // Jump to the element at index, index == len puts us on the ghost
+    // We start walking from whichever is closest: front, back or where we are now
+    pub fn seek_to(&mut self, index: usize) {
+        let len = self.list.len;
+        assert!(
+            index <= len,
+            "seek_to index (is {index}) should be <= len (is {len})"
+        );
+
+        if index == len {
+            self.cur = None;
+            self.index = None;
+            return;
+        }
+
+        // Distance from the ghost is 1 + distance from front/back, we might as well start there
+        let from_front = index;
+        let from_back = len - 1 - index;
+        let from_here = self.index.map(|cur| cur.abs_diff(index));
+
+        if from_here.is_none_or(|here| here > from_front.min(from_back)) {
+            if from_front <= from_back {
+                self.cur = self.list.front;
+                self.index = Some(0);
+            } else {
+                self.cur = self.list.back;
+                self.index = Some(len - 1);
+            }
+        }
+
+        while self.index.unwrap() < index {
+            self.move_next();
+        }
+        while self.index.unwrap() > index {
+            self.move_prev();
+        }
+    }
+
+    pub fn insert_before(&mut self, elem: T) {
+        if let Some(cur) = self.cur {
+            unsafe {
+                let new = self.list.alloc_node(elem);
+                (*new.as_ptr()).front = (*cur.as_ptr()).front;
+                (*new.as_ptr()).back = Some(cur);
+
+                if let Some(prev) = (*cur.as_ptr()).front {
+                    (*prev.as_ptr()).back = Some(new);
+                } else {
+                    // We were at the front, the new node takes its place
+                    self.list.front = Some(new);
+                }
+                (*cur.as_ptr()).front = Some(new);
+
+                // Something appeared in front of us, so we're one further
+                *self.index.as_mut().unwrap() += 1;
+                self.list.len += 1;
+            }
+        } else {
+            // Before the ghost is the back of the list
+            self.list.push_back(elem);
+        }
+    }
+
+    pub fn insert_after(&mut self, elem: T) {
+        if let Some(cur) = self.cur {
+            unsafe {
+                let new = self.list.alloc_node(elem);
+                (*new.as_ptr()).front = Some(cur);
+                (*new.as_ptr()).back = (*cur.as_ptr()).back;
+
+                if let Some(next) = (*cur.as_ptr()).back {
+                    (*next.as_ptr()).front = Some(new);
+                } else {
+                    // We were at the back, the new node takes its place
+                    self.list.back = Some(new);
+                }
+                (*cur.as_ptr()).back = Some(new);
+
+                // Index doesn't change
+                self.list.len += 1;
+            }
+        } else {
+            // After the ghost is the front of the list
+            self.list.push_front(elem);
+        }
+    }
+
+    // Insert everything from the iterator before the cursor, in order
+    // Each new element lands right before cur, so it ends up after the previous one
+    pub fn splice_before_iter<I: IntoIterator<Item = T>>(&mut self, iter: I) {
+        for elem in iter {
+            self.insert_before(elem);
+        }
+    }
+
+    // Removes the current element, and moves the cursor to the next one
+    // Index stays the same, as the next element now lives there
+    // Does nothing on the ghost
+    pub fn remove_current(&mut self) -> Option<T> {
+        self.remove_current_as_list()
+            .map(|mut list| list.pop_front().unwrap())
+    }
+
+    // Same as remove_current, but hands back the node itself in a 1 element list
+    // No allocation or deallocation, the node just changes owners
+    pub fn remove_current_as_list(&mut self) -> Option<LinkedList<T, A>> {
+        let cur = self.cur?;
+        unsafe {
+            let next = (*cur.as_ptr()).back;
+            self.list.unlink(cur);
+
+            self.cur = next;
+            if next.is_none() {
+                // We removed the back, so we walked onto the ghost
+                self.index = None;
+            }
+
+            Some(self.list.split_chain(Some(cur), Some(cur), 1))
+        }
+    }
+
+    pub fn split_before(&mut self) -> LinkedList<T, A> {
+        if let Some(cur) = self.cur {
+            // We are pointing at a real element, so the list is non-empty.
+            unsafe {
+                // Current state
+                let old_len = self.list.len;
+                let old_idx = self.index.unwrap();
+                let prev = (*cur.as_ptr()).front;
+
+                // What self will become
+                let new_len = old_len - old_idx;
                 let new_front = self.cur;
                 let new_back = self.list.back;
                 let new_idx = Some(0);
 
-                // What the output will become
-                let output_len = old_len - new_len;
-                let output_front = self.list.front;
-                let output_back = prev;
+                // What the output will become
+                let output_len = old_len - new_len;
+                // If there's no prev, we're at the front and the output is empty
+                let output_front = prev.and(self.list.front);
+                let output_back = prev;
+
+                // Break the links between cur and prev
+                if let Some(prev) = prev {
+                    (*cur.as_ptr()).front = None;
+                    (*prev.as_ptr()).back = None;
+                }
+
+                // Produce the result:
+                self.list.len = new_len;
+                self.list.front = new_front;
+                self.list.back = new_back;
+                self.index = new_idx;
+
+                self.list.split_chain(output_front, output_back, output_len)
+            }
+        } else {
+            // We're at the ghost, just replace our list with an empty one.
+            // No other state needs to be changed.
+            self.list.take_all()
+        }
+    }
+
+    pub fn splice_before(&mut self, mut input: LinkedList<T, A>) {
+        self.list.assert_compatible(&input);
+        unsafe {
+            // We can either `take` the input's pointers or `mem::forget`
+            // it. Using `take` is more responsible in case we ever do custom
+            // allocators or something that also needs to be cleaned up!
+            if input.is_empty() {
+                // Input is empty, do nothing.
+            } else if let Some(cur) = self.cur {
+                // Both lists are non-empty
+                self.list.adopt_handles(&mut input);
+                let in_front = input.front.take().unwrap();
+                let in_back = input.back.take().unwrap();
+
+                if let Some(prev) = (*cur.as_ptr()).front {
+                    // General Case, no boundaries, just internal fixups
+                    (*prev.as_ptr()).back = Some(in_front);
+                    (*in_front.as_ptr()).front = Some(prev);
+                    (*cur.as_ptr()).front = Some(in_back);
+                    (*in_back.as_ptr()).back = Some(cur);
+                } else {
+                    // No prev, we're appending to the front
+                    (*cur.as_ptr()).front = Some(in_back);
+                    (*in_back.as_ptr()).back = Some(cur);
+                    self.list.front = Some(in_front);
+                }
+                // Index moves forward by input length
+                *self.index.as_mut().unwrap() += input.len;
+            } else if let Some(back) = self.list.back {
+                // We're on the ghost but non-empty, append to the back
+                self.list.adopt_handles(&mut input);
+                let in_front = input.front.take().unwrap();
+                let in_back = input.back.take().unwrap();
+
+                (*back.as_ptr()).back = Some(in_front);
+                (*in_front.as_ptr()).front = Some(back);
+                self.list.back = Some(in_back);
+            } else {
+                // We're empty, become the input, remain on the ghost
+                std::mem::swap(self.list, &mut input);
+            }
+
+            self.list.len += input.len;
+            // Not necessary but Polite To Do
+            input.len = 0;
+
+            // Input dropped here
+        }
+    }
+
+    pub fn split_after(&mut self) -> LinkedList<T, A> {
+        // We have this:
+        //
+        //     list.front -> A <-> B <-> C <-> D <- list.back
+        //                         ^
+        //                        cur
+        //
+        //
+        // And we want to produce this:
+        //
+        //     list.front -> A <-> B <- list.back
+        //                         ^
+        //                        cur
+        //
+        //
+        //    return.front -> C <-> D <- return.back
+        //
+        if let Some(cur) = self.cur {
+            // We are pointing at a real element, so the list is non-empty.
+            unsafe {
+                // Current state
+                let old_len = self.list.len;
+                let old_idx = self.index.unwrap();
+                let next = (*cur.as_ptr()).back;
+
+                // What self will become
+                let new_len = old_idx + 1;
+                let new_back = self.cur;
+                let new_front = self.list.front;
+                let new_idx = Some(old_idx);
+
+                // What the output will become
+                let output_len = old_len - new_len;
+                // If there's no next, we're at the back and the output is empty
+                let output_front = next;
+                let output_back = next.and(self.list.back);
+
+                // Break the links between cur and next
+                if let Some(next) = next {
+                    (*cur.as_ptr()).back = None;
+                    (*next.as_ptr()).front = None;
+                }
+
+                // Produce the result:
+                self.list.len = new_len;
+                self.list.front = new_front;
+                self.list.back = new_back;
+                self.index = new_idx;
+
+                self.list.split_chain(output_front, output_back, output_len)
+            }
+        } else {
+            // We're at the ghost, just replace our list with an empty one.
+            // No other state needs to be changed.
+            self.list.take_all()
+        }
+    }
+
+    pub fn splice_after(&mut self, mut input: LinkedList<T, A>) {
+        // We have this:
+        //
+        // input.front -> 1 <-> 2 <- input.back
+        //
+        // list.front -> A <-> B <-> C <- list.back
+        //                     ^
+        //                    cur
+        //
+        //
+        // Becoming this:
+        //
+        // list.front -> A <-> B <-> 1 <-> 2 <-> C <- list.back
+        //                     ^
+        //                    cur
+        //
+        self.list.assert_compatible(&input);
+        unsafe {
+            // We can either `take` the input's pointers or `mem::forget`
+            // it. Using `take` is more responsible in case we ever do custom
+            // allocators or something that also needs to be cleaned up!
+            if input.is_empty() {
+                // Input is empty, do nothing.
+            } else if let Some(cur) = self.cur {
+                // Both lists are non-empty
+                self.list.adopt_handles(&mut input);
+                let in_front = input.front.take().unwrap();
+                let in_back = input.back.take().unwrap();
+
+                if let Some(next) = (*cur.as_ptr()).back {
+                    // General Case, no boundaries, just internal fixups
+                    (*next.as_ptr()).front = Some(in_back);
+                    (*in_back.as_ptr()).back = Some(next);
+                    (*cur.as_ptr()).back = Some(in_front);
+                    (*in_front.as_ptr()).front = Some(cur);
+                } else {
+                    // No next, we're appending to the back
+                    (*cur.as_ptr()).back = Some(in_front);
+                    (*in_front.as_ptr()).front = Some(cur);
+                    self.list.back = Some(in_back);
+                }
+                // Index doesn't change
+            } else if let Some(front) = self.list.front {
+                // We're on the ghost but non-empty, append to the front
+                self.list.adopt_handles(&mut input);
+                let in_front = input.front.take().unwrap();
+                let in_back = input.back.take().unwrap();
+
+                (*front.as_ptr()).front = Some(in_back);
+                (*in_back.as_ptr()).back = Some(front);
+                self.list.front = Some(in_front);
+            } else {
+                // We're empty, become the input, remain on the ghost
+                std::mem::swap(self.list, &mut input);
+            }
+
+            self.list.len += input.len;
+            // Not necessary but Polite To Do
+            input.len = 0;
+
+            // Input dropped here
+        }
+    }
+
+    // Look at the list through read-only glasses, at the same position
+    // Reborrows self, so the CursorMut is frozen while this lives
+    pub fn as_cursor(&self) -> Cursor<'_, T, A> {
+        Cursor {
+            cur: self.cur,
+            list: self.list,
+            index: self.index,
+        }
+    }
+}
+
+// Read-only version of CursorMut
+// Only holds a shared reference to the list, so we can have as many of them as we want
+// Same ghost element semantics, the ghost sits between back and front
+// But everything we hand out lives for 'a, not for the borrow of the cursor
+pub struct Cursor<'a, T, A: NodeAllocator = Global> {
+    cur: Link<T>,
+    list: &'a LinkedList<T, A>,
+    index: Option<usize>,
+}
+
+impl<T, A: NodeAllocator> LinkedList<T, A> {
+    // Cursor pointing at the front element, or at the ghost if the list is empty
+    pub fn cursor_front(&self) -> Cursor<'_, T, A> {
+        Cursor {
+            cur: self.front,
+            list: self,
+            index: self.front.map(|_| 0),
+        }
+    }
+
+    // Cursor pointing at the back element, or at the ghost if the list is empty
+    pub fn cursor_back(&self) -> Cursor<'_, T, A> {
+        Cursor {
+            cur: self.back,
+            list: self,
+            index: self.back.map(|_| self.len - 1),
+        }
+    }
+}
+
+// #[derive(Clone)] would require T: Clone, but we only copy pointers around
+impl<T, A: NodeAllocator> Clone for Cursor<'_, T, A> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<T, A: NodeAllocator> Copy for Cursor<'_, T, A> {}
+
+impl<'a, T, A: NodeAllocator> Cursor<'a, T, A> {
+    pub fn index(&self) -> Option<usize> {
+        self.index
+    }
+
+    pub fn move_next(&mut self) {
+        if let Some(cur) = self.cur {
+            unsafe {
+                // We're on a real element, go to its next (back)
+                self.cur = (*cur.as_ptr()).back;
+                if self.cur.is_some() {
+                    *self.index.as_mut().unwrap() += 1;
+                } else {
+                    // We just walked to the ghost, no more index
+                    self.index = None;
+                }
+            }
+        } else if !self.list.is_empty() {
+            // We're at the ghost, and there is a real front, so move to it!
+            self.cur = self.list.front;
+            self.index = Some(0)
+        } else {
+            // We're at the ghost, but that's the only element... do nothing.
+        }
+    }
+
+    pub fn move_prev(&mut self) {
+        if let Some(cur) = self.cur {
+            unsafe {
+                // We're on a real element, go to its previous (front)
+                self.cur = (*cur.as_ptr()).front;
+                if self.cur.is_some() {
+                    *self.index.as_mut().unwrap() -= 1;
+                } else {
+                    // We just walked to the ghost, no more index
+                    self.index = None;
+                }
+            }
+        } else if !self.list.is_empty() {
+            // We're at the ghost, and there is a real back, so move to it!
+            self.cur = self.list.back;
+            self.index = Some(self.list.len - 1)
+        } else {
+            // We're at the ghost, but that's the only element... do nothing.
+        }
+    }
+
+    // No &mut anywhere, so the references can outlive the cursor itself
+    pub fn current(&self) -> Option<&'a T> {
+        unsafe { self.cur.map(|node| &(*node.as_ptr()).elem) }
+    }
+
+    pub fn peek_next(&self) -> Option<&'a T> {
+        unsafe {
+            let next = if let Some(cur) = self.cur {
+                // Normal case, try to follow the cur node's back pointer
+                (*cur.as_ptr()).back
+            } else {
+                // Ghost case, try to use the list's front pointer
+                self.list.front
+            };
+
+            next.map(|node| &(*node.as_ptr()).elem)
+        }
+    }
+
+    pub fn peek_prev(&self) -> Option<&'a T> {
+        unsafe {
+            let prev = if let Some(cur) = self.cur {
+                // Normal case, try to follow the cur node's front pointer
+                (*cur.as_ptr()).front
+            } else {
+                // Ghost case, try to use the list's back pointer
+                self.list.back
+            };
+
+            prev.map(|node| &(*node.as_ptr()).elem)
+        }
+    }
+}
+
+// Cursor is basically a &LinkedList<T, A>, so it is Send/Sync whenever that is
+unsafe impl<T: Sync, A: NodeAllocator + Sync> Send for Cursor<'_, T, A> {}
+unsafe impl<T: Sync, A: NodeAllocator + Sync> Sync for Cursor<'_, T, A> {}
+
+// Sorting
+// Natural merge sort, we never allocate, only relink the nodes we already have
+// 1. Chop the list into runs that are already sorted (reversing strictly descending ones)
+// 2. Merge runs like adding 1 to a binary counter, slot i holds a merge of ~2^i runs
+// 3. Merge whatever is left in the slots
+// While sorting, `back` is the only pointer we maintain, chains are singly linked
+// `front` pointers get rebuilt at the very end
+impl<T, A: NodeAllocator> LinkedList<T, A> {
+    pub fn sort(&mut self)
+    where
+        T: Ord,
+    {
+        self.sort_by(T::cmp);
+    }
+
+    pub fn sort_by_key<K: Ord, F: FnMut(&T) -> K>(&mut self, mut f: F) {
+        self.sort_by(|a, b| f(a).cmp(&f(b)));
+    }
+
+    // Merge sort is the best we can do on a linked list anyway,
+    // and a stable sort is a perfectly fine unstable sort
+    pub fn sort_unstable_by<F: FnMut(&T, &T) -> Ordering>(&mut self, compare: F) {
+        self.sort_by(compare);
+    }
+
+    // If compare panics, the list is still a valid list with all of its elements
+    // Their order is unspecified though
+    pub fn sort_by<F: FnMut(&T, &T) -> Ordering>(&mut self, mut compare: F) {
+        self.forget_finger();
+        if self.len < 2 {
+            return;
+        }
+
+        let mut is_less = |a: &T, b: &T| compare(a, b) == Ordering::Less;
+        let rest = self.front.take();
+        self.back = None;
+        let mut state = SortState {
+            list: self,
+            rest,
+            slots: [None; usize::BITS as usize],
+            carry: None,
+            left: None,
+            right: None,
+            out: None,
+            out_tail: None,
+        };
+        unsafe { state.sort(&mut is_less) };
+        // state gets dropped here and relinks the list
+    }
+}
+
+// Every node being sorted lives in exactly one of these chains at all times
+// So if the comparator panics, Drop can just glue them back together
+struct SortState<'a, T, A: NodeAllocator> {
+    list: &'a mut LinkedList<T, A>,
+    // Not chopped into runs yet
+    rest: Link<T>,
+    // slots[i] holds a sorted run, higher slots hold earlier elements
+    slots: [Link<T>; usize::BITS as usize],
+    // Run that's looking for a slot to land in
+    carry: Link<T>,
+    // What the current merge is working on
+    left: Link<T>,
+    right: Link<T>,
+    out: Link<T>,
+    out_tail: Link<T>,
+}
+
+impl<T, A: NodeAllocator> SortState<'_, T, A> {
+    unsafe fn sort<F: FnMut(&T, &T) -> bool>(&mut self, is_less: &mut F) {
+        unsafe {
+            while self.rest.is_some() {
+                self.next_run(is_less);
+                for slot in 0..self.slots.len() {
+                    if self.slots[slot].is_none() {
+                        self.slots[slot] = self.carry.take();
+                        break;
+                    }
+                    // Slot holds earlier elements, so it goes on the left for stability
+                    self.left = self.slots[slot].take();
+                    self.right = self.carry.take();
+                    self.carry = self.merge(is_less);
+                }
+            }
+
+            // Collapse the slots, lower slots hold the later elements
+            for slot in 0..self.slots.len() {
+                if self.slots[slot].is_some() {
+                    self.left = self.slots[slot].take();
+                    self.right = self.carry.take();
+                    self.carry = self.merge(is_less);
+                }
+            }
+        }
+    }
+
+    // Cut the longest sorted run off the front of rest, and put it in carry
+    unsafe fn next_run<F: FnMut(&T, &T) -> bool>(&mut self, is_less: &mut F) {
+        unsafe {
+            let head = self.rest.unwrap();
+            let Some(second) = (*head.as_ptr()).back else {
+                self.carry = self.rest.take();
+                return;
+            };
+
+            if is_less(&(*second.as_ptr()).elem, &(*head.as_ptr()).elem) {
+                // Strictly descending, it's stable to flip it around
+                // Move nodes one by one from rest into carry, so both always hold their part
+                self.rest = Some(second);
+                (*head.as_ptr()).back = None;
+                self.carry = Some(head);
+                while let (Some(next), Some(run)) = (self.rest, self.carry) {
+                    if !is_less(&(*next.as_ptr()).elem, &(*run.as_ptr()).elem) {
+                        break;
+                    }
+                    self.rest = (*next.as_ptr()).back;
+                    (*next.as_ptr()).back = Some(run);
+                    self.carry = Some(next);
+                }
+            } else {
+                // Non-descending, just find where it stops
+                // rest keeps the whole chain until we cut it, so nothing can get lost
+                let mut tail = second;
+                while let Some(next) = (*tail.as_ptr()).back {
+                    if is_less(&(*next.as_ptr()).elem, &(*tail.as_ptr()).elem) {
+                        break;
+                    }
+                    tail = next;
+                }
+                self.carry = self.rest.take();
+                self.rest = (*tail.as_ptr()).back.take();
+            }
+        }
+    }
+
+    // Merge left and right into out, and hand it back
+    // Ties go to left, which keeps the sort stable
+    unsafe fn merge<F: FnMut(&T, &T) -> bool>(&mut self, is_less: &mut F) -> Link<T> {
+        unsafe {
+            while let (Some(l), Some(r)) = (self.left, self.right) {
+                // Only thing that can panic, nothing moved yet at this point
+                let take = if is_less(&(*r.as_ptr()).elem, &(*l.as_ptr()).elem) {
+                    self.right = (*r.as_ptr()).back;
+                    r
+                } else {
+                    self.left = (*l.as_ptr()).back;
+                    l
+                };
+                (*take.as_ptr()).back = None;
+                self.push_out(Some(take));
+            }
+
+            // One of them ran out, the other one is already sorted, just glue it on
+            let remainder = self.left.take().or(self.right.take());
+            self.push_out(remainder);
+            self.out_tail = None;
+            self.out.take()
+        }
+    }
+
+    unsafe fn push_out(&mut self, chain: Link<T>) {
+        unsafe {
+            match self.out_tail {
+                Some(tail) => (*tail.as_ptr()).back = chain,
+                None => self.out = chain,
+            }
+            if chain.is_some() {
+                self.out_tail = chain;
+            }
+        }
+    }
+}
+
+impl<T, A: NodeAllocator> Drop for SortState<'_, T, A> {
+    fn drop(&mut self) {
+        // When everything went fine, all of it sits in carry
+        // If we're unwinding, it's scattered all over, glue all the chains together
+        let mut chains = [
+            self.carry.take(),
+            self.out.take(),
+            self.left.take(),
+            self.right.take(),
+            self.rest.take(),
+        ]
+        .into_iter()
+        .chain(self.slots.iter_mut().map(Option::take));
+
+        unsafe {
+            let mut prev: Link<T> = None;
+            let mut next = chains.next().flatten();
+            loop {
+                while let Some(node) = next {
+                    // Rebuild the front pointers, and link to whatever was before
+                    (*node.as_ptr()).front = prev;
+                    match prev {
+                        Some(prev) => (*prev.as_ptr()).back = Some(node),
+                        None => self.list.front = Some(node),
+                    }
+                    prev = Some(node);
+                    next = (*node.as_ptr()).back;
+                }
+                match chains.next() {
+                    Some(chain) => next = chain,
+                    None => break,
+                }
+            }
+            self.list.back = prev;
+        }
+    }
+}
+
+#[cfg(test)]
+#[allow(
+    clippy::manual_next_back,
+    clippy::zero_divided_by_zero,
+    clippy::neg_cmp_op_on_partial_ord,
+    clippy::useless_vec
+)]
+mod test {
+    use super::*;
+
+    fn generate_test() -> LinkedList<i32> {
+        list_from(&[0, 1, 2, 3, 4, 5, 6])
+    }
+
+    fn list_from<T: Clone>(v: &[T]) -> LinkedList<T> {
+        v.iter().map(|x| (*x).clone()).collect()
+    }
+
+    #[test]
+    fn test_basic_front() {
+        let mut list = LinkedList::new();
+
+        // Try to break an empty list
+        assert_eq!(list.len(), 0);
+        assert_eq!(list.pop_front(), None);
+        assert_eq!(list.len(), 0);
+
+        // Try to break a one item list
+        list.push_front(10);
+        assert_eq!(list.len(), 1);
+        assert_eq!(list.pop_front(), Some(10));
+        assert_eq!(list.len(), 0);
+        assert_eq!(list.pop_front(), None);
+        assert_eq!(list.len(), 0);
+
+        // Mess around
+        list.push_front(10);
+        assert_eq!(list.len(), 1);
+        list.push_front(20);
+        assert_eq!(list.len(), 2);
+        list.push_front(30);
+        assert_eq!(list.len(), 3);
+        assert_eq!(list.pop_front(), Some(30));
+        assert_eq!(list.len(), 2);
+        list.push_front(40);
+        assert_eq!(list.len(), 3);
+        assert_eq!(list.pop_front(), Some(40));
+        assert_eq!(list.len(), 2);
+        assert_eq!(list.pop_front(), Some(20));
+        assert_eq!(list.len(), 1);
+        assert_eq!(list.pop_front(), Some(10));
+        assert_eq!(list.len(), 0);
+        assert_eq!(list.pop_front(), None);
+        assert_eq!(list.len(), 0);
+        assert_eq!(list.pop_front(), None);
+        assert_eq!(list.len(), 0);
+    }
+
+    #[test]
+    fn test_basic() {
+        let mut m = LinkedList::new();
+        assert_eq!(m.pop_front(), None);
+        assert_eq!(m.pop_back(), None);
+        assert_eq!(m.pop_front(), None);
+        m.push_front(1);
+        assert_eq!(m.pop_front(), Some(1));
+        m.push_back(2);
+        m.push_back(3);
+        assert_eq!(m.len(), 2);
+        assert_eq!(m.pop_front(), Some(2));
+        assert_eq!(m.pop_front(), Some(3));
+        assert_eq!(m.len(), 0);
+        assert_eq!(m.pop_front(), None);
+        m.push_back(1);
+        m.push_back(3);
+        m.push_back(5);
+        m.push_back(7);
+        assert_eq!(m.pop_front(), Some(1));
+
+        let mut n = LinkedList::new();
+        n.push_front(2);
+        n.push_front(3);
+        {
+            assert_eq!(n.front().unwrap(), &3);
+            let x = n.front_mut().unwrap();
+            assert_eq!(*x, 3);
+            *x = 0;
+        }
+        {
+            assert_eq!(n.back().unwrap(), &2);
+            let y = n.back_mut().unwrap();
+            assert_eq!(*y, 2);
+            *y = 1;
+        }
+        assert_eq!(n.pop_front(), Some(0));
+        assert_eq!(n.pop_front(), Some(1));
+    }
+
+    #[test]
+    fn test_iterator() {
+        let m = generate_test();
+        for (i, elt) in m.iter().enumerate() {
+            assert_eq!(i as i32, *elt);
+        }
+        let mut n = LinkedList::new();
+        assert_eq!(n.iter().next(), None);
+        n.push_front(4);
+        let mut it = n.iter();
+        assert_eq!(it.size_hint(), (1, Some(1)));
+        assert_eq!(it.next().unwrap(), &4);
+        assert_eq!(it.size_hint(), (0, Some(0)));
+        assert_eq!(it.next(), None);
+    }
+
+    #[test]
+    fn test_iterator_double_end() {
+        let mut n = LinkedList::new();
+        assert_eq!(n.iter().next(), None);
+        n.push_front(4);
+        n.push_front(5);
+        n.push_front(6);
+        let mut it = n.iter();
+        assert_eq!(it.size_hint(), (3, Some(3)));
+        assert_eq!(it.next().unwrap(), &6);
+        assert_eq!(it.size_hint(), (2, Some(2)));
+        assert_eq!(it.next_back().unwrap(), &4);
+        assert_eq!(it.size_hint(), (1, Some(1)));
+        assert_eq!(it.next_back().unwrap(), &5);
+        assert_eq!(it.next_back(), None);
+        assert_eq!(it.next(), None);
+    }
+
+    #[test]
+    fn test_rev_iter() {
+        let m = generate_test();
+        for (i, elt) in m.iter().rev().enumerate() {
+            assert_eq!(6 - i as i32, *elt);
+        }
+        let mut n = LinkedList::new();
+        assert_eq!(n.iter().rev().next(), None);
+        n.push_front(4);
+        let mut it = n.iter().rev();
+        assert_eq!(it.size_hint(), (1, Some(1)));
+        assert_eq!(it.next().unwrap(), &4);
+        assert_eq!(it.size_hint(), (0, Some(0)));
+        assert_eq!(it.next(), None);
+    }
+
+    #[test]
+    fn test_mut_iter() {
+        let mut m = generate_test();
+        let mut len = m.len();
+        for (i, elt) in m.iter_mut().enumerate() {
+            assert_eq!(i as i32, *elt);
+            len -= 1;
+        }
+        assert_eq!(len, 0);
+        let mut n = LinkedList::new();
+        assert!(n.iter_mut().next().is_none());
+        n.push_front(4);
+        n.push_back(5);
+        let mut it = n.iter_mut();
+        assert_eq!(it.size_hint(), (2, Some(2)));
+        assert!(it.next().is_some());
+        assert!(it.next().is_some());
+        assert_eq!(it.size_hint(), (0, Some(0)));
+        assert!(it.next().is_none());
+    }
+
+    #[test]
+    fn test_iterator_mut_double_end() {
+        let mut n = LinkedList::new();
+        assert!(n.iter_mut().next_back().is_none());
+        n.push_front(4);
+        n.push_front(5);
+        n.push_front(6);
+        let mut it = n.iter_mut();
+        assert_eq!(it.size_hint(), (3, Some(3)));
+        assert_eq!(*it.next().unwrap(), 6);
+        assert_eq!(it.size_hint(), (2, Some(2)));
+        assert_eq!(*it.next_back().unwrap(), 4);
+        assert_eq!(it.size_hint(), (1, Some(1)));
+        assert_eq!(*it.next_back().unwrap(), 5);
+        assert!(it.next_back().is_none());
+        assert!(it.next().is_none());
+    }
+
+    #[test]
+    fn test_eq() {
+        let mut n: LinkedList<u8> = list_from(&[]);
+        let mut m = list_from(&[]);
+        assert!(n == m);
+        n.push_front(1);
+        assert!(n != m);
+        m.push_back(1);
+        assert!(n == m);
+
+        let n = list_from(&[2, 3, 4]);
+        let m = list_from(&[1, 2, 3]);
+        assert!(n != m);
+    }
+
+    #[test]
+    fn test_ord() {
+        let n = list_from(&[]);
+        let m = list_from(&[1, 2, 3]);
+        assert!(n < m);
+        assert!(m > n);
+        assert!(n <= n);
+        assert!(n >= n);
+    }
+
+    #[test]
+    fn test_ord_nan() {
+        let nan = 0.0f64 / 0.0;
+        let n = list_from(&[nan]);
+        let m = list_from(&[nan]);
+        assert!(!(n < m));
+        assert!(!(n > m));
+        assert!(!(n <= m));
+        assert!(!(n >= m));
+
+        let n = list_from(&[nan]);
+        let one = list_from(&[1.0f64]);
+        assert!(!(n < one));
+        assert!(!(n > one));
+        assert!(!(n <= one));
+        assert!(!(n >= one));
+
+        let u = list_from(&[1.0f64, 2.0, nan]);
+        let v = list_from(&[1.0f64, 2.0, 3.0]);
+        assert!(!(u < v));
+        assert!(!(u > v));
+        assert!(!(u <= v));
+        assert!(!(u >= v));
+
+        let s = list_from(&[1.0f64, 2.0, 4.0, 2.0]);
+        let t = list_from(&[1.0f64, 2.0, 3.0, 2.0]);
+        assert!(!(s < t));
+        assert!(s > one);
+        assert!(!(s <= one));
+        assert!(s >= one);
+    }
+
+    #[test]
+    fn test_debug() {
+        let list: LinkedList<i32> = (0..10).collect();
+        assert_eq!(format!("{:?}", list), "[0, 1, 2, 3, 4, 5, 6, 7, 8, 9]");
+
+        let list: LinkedList<&str> = vec!["just", "one", "test", "more"]
+            .iter()
+            .copied()
+            .collect();
+        assert_eq!(format!("{:?}", list), r#"["just", "one", "test", "more"]"#);
+    }
+
+    #[test]
+    // The finger and the handle owners are interior mutability, but they never take part in Hash/Eq
+    #[allow(clippy::mutable_key_type)]
+    fn test_hashmap() {
+        // Check that HashMap works with this as a key
+
+        let list1: LinkedList<i32> = (0..10).collect();
+        let list2: LinkedList<i32> = (1..11).collect();
+        let mut map = std::collections::HashMap::new();
+
+        assert_eq!(map.insert(list1.clone(), "list1"), None);
+        assert_eq!(map.insert(list2.clone(), "list2"), None);
+
+        assert_eq!(map.len(), 2);
+
+        assert_eq!(map.get(&list1), Some(&"list1"));
+        assert_eq!(map.get(&list2), Some(&"list2"));
+
+        assert_eq!(map.remove(&list1), Some("list1"));
+        assert_eq!(map.remove(&list2), Some("list2"));
+
+        assert!(map.is_empty());
+    }
+
+    // Spooky test to check for trait implementation
+    #[allow(dead_code)]
+    fn assert_properties() {
+        fn is_send<T: Send>() {}
+        fn is_sync<T: Sync>() {}
+
+        is_send::<LinkedList<i32>>();
+        is_sync::<LinkedList<i32>>();
+
+        is_send::<IntoIter<i32>>();
+        is_sync::<IntoIter<i32>>();
+
+        is_send::<Iter<i32>>();
+        is_sync::<Iter<i32>>();
+
+        is_send::<IterMut<i32>>();
+        is_sync::<IterMut<i32>>();
+
+        is_send::<Cursor<i32>>();
+        is_sync::<Cursor<i32>>();
+
+        is_send::<Drain<i32>>();
+        is_sync::<Drain<i32>>();
+
+        is_send::<ListSlice<i32>>();
+        is_sync::<ListSlice<i32>>();
+
+        is_send::<ListSliceMut<i32>>();
+        is_sync::<ListSliceMut<i32>>();
+
+        is_send::<NodeHandle<i32>>();
+        is_sync::<NodeHandle<i32>>();
+
+        fn linked_list_covariant<'a, T>(x: LinkedList<&'static T>) -> LinkedList<&'a T> {
+            x
+        }
+        fn iter_covariant<'i, 'a, T>(x: Iter<'i, &'static T>) -> Iter<'i, &'a T> {
+            x
+        }
+        fn list_slice_covariant<'i, 'a, T>(x: ListSlice<'i, &'static T>) -> ListSlice<'i, &'a T> {
+            x
+        }
+        fn into_iter_covariant<'a, T>(x: IntoIter<&'static T>) -> IntoIter<&'a T> {
+            x
+        }
+    }
+
+    #[test]
+    fn test_cursor_move_peek() {
+        let mut m: LinkedList<u32> = LinkedList::new();
+        m.extend([1, 2, 3, 4, 5, 6]);
+        let mut cursor = m.cursor_mut();
+        cursor.move_next();
+        assert_eq!(cursor.current(), Some(&mut 1));
+        assert_eq!(cursor.peek_next(), Some(&mut 2));
+        assert_eq!(cursor.peek_prev(), None);
+        assert_eq!(cursor.index(), Some(0));
+        cursor.move_prev();
+        assert_eq!(cursor.current(), None);
+        assert_eq!(cursor.peek_next(), Some(&mut 1));
+        assert_eq!(cursor.peek_prev(), Some(&mut 6));
+        assert_eq!(cursor.index(), None);
+        cursor.move_next();
+        cursor.move_next();
+        assert_eq!(cursor.current(), Some(&mut 2));
+        assert_eq!(cursor.peek_next(), Some(&mut 3));
+        assert_eq!(cursor.peek_prev(), Some(&mut 1));
+        assert_eq!(cursor.index(), Some(1));
+
+        let mut cursor = m.cursor_mut();
+        cursor.move_prev();
+        assert_eq!(cursor.current(), Some(&mut 6));
+        assert_eq!(cursor.peek_next(), None);
+        assert_eq!(cursor.peek_prev(), Some(&mut 5));
+        assert_eq!(cursor.index(), Some(5));
+        cursor.move_next();
+        assert_eq!(cursor.current(), None);
+        assert_eq!(cursor.peek_next(), Some(&mut 1));
+        assert_eq!(cursor.peek_prev(), Some(&mut 6));
+        assert_eq!(cursor.index(), None);
+        cursor.move_prev();
+        cursor.move_prev();
+        assert_eq!(cursor.current(), Some(&mut 5));
+        assert_eq!(cursor.peek_next(), Some(&mut 6));
+        assert_eq!(cursor.peek_prev(), Some(&mut 4));
+        assert_eq!(cursor.index(), Some(4));
+    }
+
+    #[test]
+    fn test_cursor_mut_insert() {
+        let mut m: LinkedList<u32> = LinkedList::new();
+        m.extend([1, 2, 3, 4, 5, 6]);
+        let mut cursor = m.cursor_mut();
+        cursor.move_next();
+        cursor.splice_before(Some(7).into_iter().collect());
+        cursor.splice_after(Some(8).into_iter().collect());
+        // check_links(&m);
+        assert_eq!(m.iter().cloned().collect::<Vec<_>>(), &[7, 1, 8, 2, 3, 4, 5, 6]);
+        let mut cursor = m.cursor_mut();
+        cursor.move_next();
+        cursor.move_prev();
+        cursor.splice_before(Some(9).into_iter().collect());
+        cursor.splice_after(Some(10).into_iter().collect());
+        check_links(&m);
+        assert_eq!(m.iter().cloned().collect::<Vec<_>>(), &[10, 7, 1, 8, 2, 3, 4, 5, 6, 9]);
+
+        let mut cursor = m.cursor_mut();
+        cursor.move_next();
+        cursor.move_prev();
+        assert_eq!(cursor.remove_current(), None);
+        cursor.move_next();
+        cursor.move_next();
+        assert_eq!(cursor.remove_current(), Some(7));
+        cursor.move_prev();
+        cursor.move_prev();
+        cursor.move_prev();
+        assert_eq!(cursor.remove_current(), Some(9));
+        cursor.move_next();
+        assert_eq!(cursor.remove_current(), Some(10));
+        check_links(&m);
+        assert_eq!(
+            m.iter().cloned().collect::<Vec<_>>(),
+            &[1, 8, 2, 3, 4, 5, 6]
+        );
+
+        let mut m: LinkedList<u32> = LinkedList::new();
+        m.extend([1, 8, 2, 3, 4, 5, 6]);
+        let mut cursor = m.cursor_mut();
+        cursor.move_next();
+        let mut p: LinkedList<u32> = LinkedList::new();
+        p.extend([100, 101, 102, 103]);
+        let mut q: LinkedList<u32> = LinkedList::new();
+        q.extend([200, 201, 202, 203]);
+        cursor.splice_after(p);
+        cursor.splice_before(q);
+        check_links(&m);
+        assert_eq!(
+            m.iter().cloned().collect::<Vec<_>>(),
+            &[200, 201, 202, 203, 1, 100, 101, 102, 103, 8, 2, 3, 4, 5, 6]
+        );
+        let mut cursor = m.cursor_mut();
+        cursor.move_next();
+        cursor.move_prev();
+        let tmp = cursor.split_before();
+        assert_eq!(m.into_iter().collect::<Vec<_>>(), &[]);
+        m = tmp;
+        let mut cursor = m.cursor_mut();
+        cursor.move_next();
+        cursor.move_next();
+        cursor.move_next();
+        cursor.move_next();
+        cursor.move_next();
+        cursor.move_next();
+        cursor.move_next();
+        let tmp = cursor.split_after();
+        assert_eq!(
+            tmp.into_iter().collect::<Vec<_>>(),
+            &[102, 103, 8, 2, 3, 4, 5, 6]
+        );
+        check_links(&m);
+        assert_eq!(
+            m.iter().cloned().collect::<Vec<_>>(),
+            &[200, 201, 202, 203, 1, 100, 101]
+        );
+    }
+
+    #[test]
+    fn test_cursor_ref() {
+        let m: LinkedList<u32> = [1, 2, 3, 4, 5, 6].into_iter().collect();
+
+        let mut front = m.cursor_front();
+        let mut back = m.cursor_back();
+        assert_eq!(front.current(), Some(&1));
+        assert_eq!(front.index(), Some(0));
+        assert_eq!(back.current(), Some(&6));
+        assert_eq!(back.index(), Some(5));
+
+        // Both cursors live at once, and walk independently
+        front.move_next();
+        back.move_prev();
+        assert_eq!(front.current(), Some(&2));
+        assert_eq!(front.peek_prev(), Some(&1));
+        assert_eq!(front.peek_next(), Some(&3));
+        assert_eq!(back.current(), Some(&5));
+        assert_eq!(back.index(), Some(4));
+
+        // Walk onto the ghost and around it
+        let mut cursor = m.cursor_front();
+        cursor.move_prev();
+        assert_eq!(cursor.current(), None);
+        assert_eq!(cursor.index(), None);
+        assert_eq!(cursor.peek_next(), Some(&1));
+        assert_eq!(cursor.peek_prev(), Some(&6));
+        cursor.move_prev();
+        assert_eq!(cursor.current(), Some(&6));
+        assert_eq!(cursor.index(), Some(5));
+        cursor.move_next();
+        cursor.move_next();
+        assert_eq!(cursor.current(), Some(&1));
+        assert_eq!(cursor.index(), Some(0));
 
-                // Break the links between cur and prev
-                if let Some(prev) = prev {
-                    (*cur.as_ptr()).front = None;
-                    (*prev.as_ptr()).back = None;
-                }
+        // References outlive the cursor that produced them
+        let elem = {
+            let copy = cursor;
+            copy.peek_next()
+        };
+        assert_eq!(elem, Some(&2));
+        assert_eq!(cursor.current(), Some(&1));
 
-                // Produce the result:
-                self.list.len = new_len;
-                self.list.front = new_front;
-                self.list.back = new_back;
-                self.index = new_idx;
+        let empty: LinkedList<u32> = LinkedList::new();
+        let mut cursor = empty.cursor_front();
+        assert_eq!(cursor.current(), None);
+        assert_eq!(cursor.index(), None);
+        cursor.move_next();
+        assert_eq!(cursor.current(), None);
+        assert_eq!(empty.cursor_back().peek_prev(), None);
+    }
 
-                LinkedList {
-                    front: output_front,
-                    back: output_back,
-                    len: output_len,
-                    _boo: PhantomData,
-                }
-            }
-        } else {
-            // We're at the ghost, just replace our list with an empty one.
-            // No other state needs to be changed.
-            std::mem::replace(self.list, LinkedList::new())
+    #[test]
+    fn test_cursor_mut_as_cursor() {
+        let mut m: LinkedList<u32> = [1, 2, 3].into_iter().collect();
+        let mut cursor = m.cursor_mut();
+        cursor.move_next();
+        cursor.move_next();
+        {
+            let view = cursor.as_cursor();
+            let mut other = view;
+            assert_eq!(view.current(), Some(&2));
+            assert_eq!(view.index(), Some(1));
+            other.move_next();
+            assert_eq!(other.current(), Some(&3));
+            assert_eq!(view.current(), Some(&2));
         }
+        *cursor.current().unwrap() = 20;
+        assert_eq!(cursor.as_cursor().current(), Some(&20));
     }
 
-    pub fn splice_before(&mut self, mut input: LinkedList<T>) {
-        unsafe {
-            // We can either `take` the input's pointers or `mem::forget`
-            // it. Using `take` is more responsible in case we ever do custom
-            // allocators or something that also needs to be cleaned up!
-            if input.is_empty() {
-                // Input is empty, do nothing.
-            } else if let Some(cur) = self.cur {
-                // Both lists are non-empty
-                let in_front = input.front.take().unwrap();
-                let in_back = input.back.take().unwrap();
+    #[test]
+    fn test_cursor_mut_insert_remove() {
+        let mut m: LinkedList<u32> = LinkedList::new();
+        let mut cursor = m.cursor_mut();
+        // Empty list, both inserts go next to the ghost
+        cursor.insert_before(2);
+        cursor.insert_after(1);
+        assert_eq!(cursor.index(), None);
+        assert_eq!(cursor.peek_next(), Some(&mut 1));
+        assert_eq!(cursor.peek_prev(), Some(&mut 2));
+        check_links(&m);
+        assert_eq!(m.iter().cloned().collect::<Vec<_>>(), &[1, 2]);
 
-                if let Some(prev) = (*cur.as_ptr()).front {
-                    // General Case, no boundaries, just internal fixups
-                    (*prev.as_ptr()).back = Some(in_front);
-                    (*in_front.as_ptr()).front = Some(prev);
-                    (*cur.as_ptr()).front = Some(in_back);
-                    (*in_back.as_ptr()).back = Some(cur);
-                } else {
-                    // No prev, we're appending to the front
-                    (*cur.as_ptr()).front = Some(in_back);
-                    (*in_back.as_ptr()).back = Some(cur);
-                    self.list.front = Some(in_front);
-                }
-                // Index moves forward by input length
-                *self.index.as_mut().unwrap() += input.len;
-            } else if let Some(back) = self.list.back {
-                // We're on the ghost but non-empty, append to the back
-                let in_front = input.front.take().unwrap();
-                let in_back = input.back.take().unwrap();
+        let mut cursor = m.cursor_mut();
+        cursor.move_next();
+        cursor.insert_before(0);
+        assert_eq!(cursor.index(), Some(1));
+        assert_eq!(cursor.current(), Some(&mut 1));
+        cursor.insert_after(10);
+        assert_eq!(cursor.index(), Some(1));
+        assert_eq!(cursor.peek_next(), Some(&mut 10));
+        cursor.move_prev();
+        cursor.move_prev();
+        cursor.splice_before_iter([7, 8, 9]);
+        assert_eq!(cursor.index(), None);
+        check_links(&m);
+        assert_eq!(
+            m.iter().cloned().collect::<Vec<_>>(),
+            &[0, 1, 10, 2, 7, 8, 9]
+        );
 
-                (*back.as_ptr()).back = Some(in_front);
-                (*in_front.as_ptr()).front = Some(back);
-                self.list.back = Some(in_back);
+        let mut cursor = m.cursor_mut();
+        cursor.move_next();
+        cursor.move_next();
+        cursor.splice_before_iter(3..6);
+        assert_eq!(cursor.index(), Some(4));
+        assert_eq!(cursor.current(), Some(&mut 1));
+        assert_eq!(cursor.remove_current(), Some(1));
+        assert_eq!(cursor.index(), Some(4));
+        assert_eq!(cursor.current(), Some(&mut 10));
+        check_links(&m);
+        assert_eq!(
+            m.iter().cloned().collect::<Vec<_>>(),
+            &[0, 3, 4, 5, 10, 2, 7, 8, 9]
+        );
+
+        let mut cursor = m.cursor_mut();
+        cursor.move_prev();
+        let single = cursor.remove_current_as_list().unwrap();
+        assert_eq!(single.len(), 1);
+        assert_eq!(single.iter().cloned().collect::<Vec<_>>(), &[9]);
+        // Removing the back walks us onto the ghost
+        assert_eq!(cursor.index(), None);
+        assert_eq!(cursor.current(), None);
+        assert!(cursor.remove_current_as_list().is_none());
+        cursor.move_next();
+        assert_eq!(cursor.remove_current(), Some(0));
+        assert_eq!(cursor.index(), Some(0));
+        assert_eq!(cursor.current(), Some(&mut 3));
+        check_links(&m);
+        assert_eq!(m.len(), 7);
+        assert_eq!(m.front(), Some(&3));
+        assert_eq!(m.back(), Some(&8));
+
+        let mut cursor = m.cursor_mut();
+        cursor.move_next();
+        while cursor.remove_current().is_some() {}
+        assert_eq!(cursor.index(), None);
+        assert!(m.is_empty());
+        assert_eq!(m.front(), None);
+        assert_eq!(m.back(), None);
+    }
+
+    #[test]
+    fn test_cursor_mut_seek_to() {
+        let mut m: LinkedList<u32> = (0..10).collect();
+        let mut cursor = m.cursor_mut();
+        for i in [3, 9, 0, 5, 4, 8, 10, 2, 10, 7] {
+            cursor.seek_to(i);
+            if i == 10 {
+                assert_eq!(cursor.index(), None);
+                assert_eq!(cursor.current(), None);
             } else {
-                // We're empty, become the input, remain on the ghost
-                std::mem::swap(self.list, &mut input);
+                assert_eq!(cursor.index(), Some(i));
+                assert_eq!(cursor.current(), Some(&mut (i as u32)));
             }
+        }
 
-            self.list.len += input.len;
-            // Not necessary but Polite To Do
-            input.len = 0;
+        cursor.seek_to(4);
+        cursor.insert_before(100);
+        assert_eq!(cursor.index(), Some(5));
+        cursor.seek_to(4);
+        assert_eq!(cursor.current(), Some(&mut 100));
+    }
 
-            // Input dropped here
+    #[test]
+    #[should_panic]
+    fn test_cursor_mut_seek_out_of_bounds() {
+        let mut m: LinkedList<u32> = (0..3).collect();
+        m.cursor_mut().seek_to(4);
+    }
+
+    #[test]
+    fn test_cursor_split_at_ends() {
+        // Splitting at the very front/back must hand out a truly empty list
+        let mut m: LinkedList<u32> = (0..4).collect();
+        let mut cursor = m.cursor_mut();
+        cursor.move_next();
+        let before = cursor.split_before();
+        assert!(before.is_empty());
+        assert_eq!(before.front(), None);
+        drop(before);
+        cursor.move_prev();
+        cursor.move_prev();
+        assert_eq!(cursor.current(), Some(&mut 3));
+        let mut after = cursor.split_after();
+        assert!(after.is_empty());
+        assert_eq!(after.pop_back(), None);
+        drop(after);
+        check_links(&m);
+        assert_eq!(m.iter().cloned().collect::<Vec<_>>(), &[0, 1, 2, 3]);
+    }
+
+    #[test]
+    fn test_nested_slice_relink() {
+        // A child view relinking the parent's end nodes used to leave the parent with stale ends
+        let mut m: LinkedList<u32> = (0..4).collect();
+        let mut parent = m.range_mut(1..3);
+        {
+            let mut child = parent.range_mut(0..2);
+            child.reverse();
+        }
+        assert_eq!(parent.first(), Some(&2));
+        assert_eq!(parent.last(), Some(&1));
+        parent.reverse();
+        assert_eq!(parent.iter().copied().collect::<Vec<_>>(), &[1, 2]);
+        check_links(&m);
+        assert_eq!(m.iter().copied().collect::<Vec<_>>(), &[0, 1, 2, 3]);
+
+        // Same at the list's ends, and with sort
+        let mut m: LinkedList<u32> = [5, 4, 3, 2, 1, 0].into_iter().collect();
+        let mut parent = m.range_mut(..);
+        parent.range_mut(..3).sort();
+        parent.range_mut(3..).sort();
+        assert_eq!(parent.first(), Some(&3));
+        assert_eq!(parent.last(), Some(&2));
+        {
+            let mut middle = parent.range_mut(1..5);
+            middle.range_mut(1..).reverse();
+            assert_eq!(middle.iter().copied().collect::<Vec<_>>(), &[4, 1, 0, 5]);
         }
+        parent.sort();
+        for (i, elem) in parent.iter_mut().enumerate() {
+            assert_eq!(*elem, i as u32);
+        }
+        check_links(&m);
+        assert_eq!(m.iter().copied().collect::<Vec<_>>(), &[0, 1, 2, 3, 4, 5]);
     }
 
-    pub fn split_after(&mut self) -> LinkedList<T> {
-        // We have this:
-        //
-        //     list.front -> A <-> B <-> C <-> D <- list.back
-        //                         ^
-        //                        cur
-        //
-        //
-        // And we want to produce this:
-        //
-        //     list.front -> A <-> B <- list.back
-        //                         ^
-        //                        cur
-        //
-        //
-        //    return.front -> C <-> D <- return.back
-        //
-        if let Some(cur) = self.cur {
-            // We are pointing at a real element, so the list is non-empty.
-            unsafe {
-                // Current state
-                let old_len = self.list.len;
-                let old_idx = self.index.unwrap();
-                let next = (*cur.as_ptr()).back;
+    #[test]
+    fn test_node_size() {
+        // Handles live in the list, nodes are just the two links and the element
+        assert_eq!(
+            std::mem::size_of::<Node<u64>>(),
+            2 * std::mem::size_of::<usize>() + 8
+        );
+    }
 
-                // What self will become
-                let new_len = old_idx + 1;
-                let new_back = self.cur;
-                let new_front = self.list.front;
-                let new_idx = Some(old_idx);
+    #[test]
+    fn test_handles() {
+        let mut m: LinkedList<u32> = LinkedList::new();
+        let one = m.push_back_handle(1);
+        let two = m.push_back_handle(2);
+        m.push_back(3);
+        let zero = m.push_front_handle(0);
+        assert_eq!(m.iter().cloned().collect::<Vec<_>>(), &[0, 1, 2, 3]);
 
-                // What the output will become
-                let output_len = old_len - new_len;
-                let output_front = next;
-                let output_back = self.list.back;
+        assert_eq!(one.get(&m), Some(&1));
+        *two.get_mut(&mut m).unwrap() = 20;
+        assert_eq!(two.get(&m), Some(&20));
 
-                // Break the links between cur and next
-                if let Some(next) = next {
-                    (*cur.as_ptr()).back = None;
-                    (*next.as_ptr()).front = None;
-                }
+        assert!(zero.move_to_back(&mut m));
+        assert!(two.move_to_front(&mut m));
+        check_links(&m);
+        assert_eq!(m.iter().cloned().collect::<Vec<_>>(), &[20, 1, 3, 0]);
 
-                // Produce the result:
-                self.list.len = new_len;
-                self.list.front = new_front;
-                self.list.back = new_back;
-                self.index = new_idx;
+        let mut cursor = m.cursor_at(&one).unwrap();
+        assert_eq!(cursor.index(), Some(1));
+        assert_eq!(cursor.current(), Some(&mut 1));
+        cursor.move_next();
+        assert_eq!(cursor.current(), Some(&mut 3));
 
-                LinkedList {
-                    front: output_front,
-                    back: output_back,
-                    len: output_len,
-                    _boo: PhantomData,
-                }
-            }
-        } else {
-            // We're at the ghost, just replace our list with an empty one.
-            // No other state needs to be changed.
-            std::mem::replace(self.list, LinkedList::new())
-        }
+        let copy = one.clone();
+        assert_eq!(one.remove(&mut m), Some(1));
+        check_links(&m);
+        assert_eq!(m.iter().cloned().collect::<Vec<_>>(), &[20, 3, 0]);
+
+        // The node is gone, every copy of the handle knows it
+        assert_eq!(copy.get(&m), None);
+        assert!(!copy.move_to_front(&mut m));
+        assert!(m.cursor_at(&copy).is_none());
+        assert_eq!(copy.remove(&mut m), None);
+
+        assert_eq!(m.pop_front(), Some(20));
+        assert_eq!(two.get(&m), None);
+        assert_eq!(m.len(), 2);
     }
 
-    pub fn splice_after(&mut self, mut input: LinkedList<T>) {
-        // We have this:
-        //
-        // input.front -> 1 <-> 2 <- input.back
-        //
-        // list.front -> A <-> B <-> C <- list.back
-        //                     ^
-        //                    cur
-        //
-        //
-        // Becoming this:
-        //
-        // list.front -> A <-> B <-> 1 <-> 2 <-> C <- list.back
-        //                     ^
-        //                    cur
-        //
-        unsafe {
-            // We can either `take` the input's pointers or `mem::forget`
-            // it. Using `take` is more responsible in case we ever do custom
-            // allocators or something that also needs to be cleaned up!
-            if input.is_empty() {
-                // Input is empty, do nothing.
-            } else if let Some(cur) = self.cur {
-                // Both lists are non-empty
-                let in_front = input.front.take().unwrap();
-                let in_back = input.back.take().unwrap();
+    #[test]
+    fn test_handles_wrong_list() {
+        let mut m: LinkedList<u32> = LinkedList::new();
+        let mut n: LinkedList<u32> = LinkedList::new();
+        let a = m.push_back_handle(1);
+        let b = n.push_back_handle(1);
+
+        assert_eq!(a.get(&n), None);
+        assert_eq!(b.get(&m), None);
+        assert!(!a.move_to_back(&mut n));
+        assert!(n.cursor_at(&a).is_none());
+        assert_eq!(a.clone().remove(&mut n), None);
+        assert_eq!(n.len(), 1);
+
+        // A list that never handed out handles doesn't match anything either
+        let mut clone = m.clone();
+        assert_eq!(a.get(&clone), None);
+        assert_eq!(a.get_mut(&mut clone), None);
+
+        drop(m);
+        assert_eq!(a.get(&n), None);
+    }
 
-                if let Some(next) = (*cur.as_ptr()).back {
-                    // General Case, no boundaries, just internal fixups
-                    (*next.as_ptr()).front = Some(in_back);
-                    (*in_back.as_ptr()).back = Some(next);
-                    (*cur.as_ptr()).back = Some(in_front);
-                    (*in_front.as_ptr()).front = Some(cur);
-                } else {
-                    // No next, we're appending to the back
-                    (*cur.as_ptr()).back = Some(in_front);
-                    (*in_front.as_ptr()).front = Some(cur);
-                    self.list.back = Some(in_back);
-                }
-                // Index doesn't change
-            } else if let Some(front) = self.list.front {
-                // We're on the ghost but non-empty, append to the front
-                let in_front = input.front.take().unwrap();
-                let in_back = input.back.take().unwrap();
+    #[test]
+    fn test_handles_follow_nodes() {
+        let mut m: LinkedList<u32> = LinkedList::new();
+        let handles: Vec<_> = (0..6).map(|i| m.push_back_handle(i)).collect();
+
+        // Split the front half off, its handles move with it
+        let mut cursor = m.cursor_mut();
+        cursor.seek_to(3);
+        let mut front = cursor.split_before();
+        assert_eq!(handles[1].get(&m), None);
+        assert_eq!(handles[1].get(&front), Some(&1));
+        assert_eq!(handles[4].get(&front), None);
+        assert_eq!(handles[4].get(&m), Some(&4));
+        assert!(handles[5].move_to_front(&mut m));
+        assert_eq!(m.iter().cloned().collect::<Vec<_>>(), &[5, 3, 4]);
+
+        // Removing a single node as a list takes its handle along too
+        let mut cursor = m.cursor_mut();
+        cursor.move_next();
+        let mut single = cursor.remove_current_as_list().unwrap();
+        assert_eq!(handles[5].get(&m), None);
+        assert_eq!(handles[5].get_mut(&mut single), Some(&mut 5));
+
+        // And splicing brings them back
+        let mut cursor = front.cursor_mut();
+        cursor.splice_before(m);
+        cursor.splice_after(single);
+        check_links(&front);
+        assert_eq!(
+            front.iter().cloned().collect::<Vec<_>>(),
+            &[5, 0, 1, 2, 3, 4]
+        );
+        for (i, handle) in handles.iter().enumerate() {
+            assert_eq!(handle.get(&front), Some(&(i as u32)));
+        }
+        assert_eq!(handles[3].clone().remove(&mut front), Some(3));
+        assert_eq!(front.iter().cloned().collect::<Vec<_>>(), &[5, 0, 1, 2, 4]);
 
-                (*front.as_ptr()).front = Some(in_back);
-                (*in_back.as_ptr()).back = Some(front);
-                self.list.front = Some(in_front);
-            } else {
-                // We're empty, become the input, remain on the ghost
-                std::mem::swap(self.list, &mut input);
-            }
+        // Dropping the list marks everything dead
+        drop(front);
+        assert!(handles.iter().all(|h| h.owner.load(Relaxed) == 0));
+    }
 
-            self.list.len += input.len;
-            // Not necessary but Polite To Do
-            input.len = 0;
+    fn to_vec<T: Clone, A: NodeAllocator>(list: &LinkedList<T, A>) -> Vec<T> {
+        list.iter().cloned().collect()
+    }
 
-            // Input dropped here
+    #[test]
+    fn test_append() {
+        let mut m: LinkedList<u32> = (0..3).collect();
+        let mut n: LinkedList<u32> = (3..6).collect();
+        m.append(&mut n);
+        check_links(&m);
+        assert_eq!(to_vec(&m), &[0, 1, 2, 3, 4, 5]);
+        assert_eq!(m.len(), 6);
+        assert!(n.is_empty());
+        assert_eq!(n.front(), None);
+
+        // Both ways of being empty
+        m.append(&mut n);
+        assert_eq!(m.len(), 6);
+        n.append(&mut m);
+        assert_eq!(to_vec(&n), &[0, 1, 2, 3, 4, 5]);
+        assert!(m.is_empty());
+        n.push_back(6);
+        assert_eq!(n.back(), Some(&6));
+
+        // Handles come along
+        let mut other = LinkedList::new();
+        let handle = other.push_back_handle(7);
+        n.append(&mut other);
+        assert_eq!(handle.get(&n), Some(&7));
+        assert_eq!(handle.get(&other), None);
+    }
+
+    #[test]
+    fn test_split_off() {
+        for at in 0..=6 {
+            let mut m: LinkedList<u32> = (0..6).collect();
+            let tail = m.split_off(at);
+            check_links(&m);
+            check_links(&tail);
+            assert_eq!(to_vec(&m), (0..at as u32).collect::<Vec<_>>());
+            assert_eq!(to_vec(&tail), (at as u32..6).collect::<Vec<_>>());
+            assert_eq!(m.len(), at);
+            assert_eq!(tail.len(), 6 - at);
         }
+
+        let mut m: LinkedList<u32> = LinkedList::new();
+        let handles: Vec<_> = (0..4).map(|i| m.push_back_handle(i)).collect();
+        let tail = m.split_off(1);
+        assert_eq!(handles[0].get(&m), Some(&0));
+        assert_eq!(handles[2].get(&tail), Some(&2));
+        assert_eq!(handles[2].get(&m), None);
     }
-}
 
+    #[test]
+    #[should_panic]
+    fn test_split_off_out_of_bounds() {
+        let mut m: LinkedList<u32> = (0..3).collect();
+        m.split_off(4);
+    }
 
-#[cfg(test)]
-mod test {
-    use std::io::Cursor;
+    #[test]
+    fn test_insert_remove() {
+        let mut m: LinkedList<u32> = LinkedList::new();
+        m.insert(0, 1);
+        m.insert(0, 0);
+        m.insert(2, 3);
+        m.insert(2, 2);
+        check_links(&m);
+        assert_eq!(to_vec(&m), &[0, 1, 2, 3]);
 
-    use super::*;
+        let mut v: Vec<u32> = to_vec(&m);
+        for (at, elem) in [(1, 10), (4, 11), (5, 12), (3, 13)] {
+            m.insert(at, elem);
+            v.insert(at, elem);
+        }
+        check_links(&m);
+        assert_eq!(to_vec(&m), v);
 
-    fn generate_test() -> LinkedList<i32> {
-        list_from(&[0, 1, 2, 3, 4, 5, 6])
+        for at in [0, 5, 2, 3, 0] {
+            assert_eq!(m.remove(at), v.remove(at));
+            check_links(&m);
+            assert_eq!(to_vec(&m), v);
+        }
+        assert_eq!(m.len(), v.len());
     }
 
-    fn list_from<T: Clone>(v: &[T]) -> LinkedList<T> {
-        v.iter().map(|x| (*x).clone()).collect()
+    #[test]
+    #[should_panic]
+    fn test_remove_out_of_bounds() {
+        let mut m: LinkedList<u32> = (0..3).collect();
+        m.remove(3);
     }
 
     #[test]
-    fn test_basic_front() {
-        let mut list = LinkedList::new();
+    fn test_truncate() {
+        let mut m: LinkedList<u32> = (0..6).collect();
+        m.truncate(10);
+        assert_eq!(m.len(), 6);
+        m.truncate(4);
+        check_links(&m);
+        assert_eq!(to_vec(&m), &[0, 1, 2, 3]);
+        m.truncate(0);
+        assert!(m.is_empty());
+        assert_eq!(m.back(), None);
+    }
 
-        // Try to break an empty list
-        assert_eq!(list.len(), 0);
-        assert_eq!(list.pop_front(), None);
-        assert_eq!(list.len(), 0);
+    #[test]
+    fn test_retain() {
+        let mut m: LinkedList<u32> = (0..10).collect();
+        m.retain(|x| x % 3 != 0);
+        check_links(&m);
+        assert_eq!(to_vec(&m), &[1, 2, 4, 5, 7, 8]);
 
-        // Try to break a one item list
-        list.push_front(10);
-        assert_eq!(list.len(), 1);
-        assert_eq!(list.pop_front(), Some(10));
-        assert_eq!(list.len(), 0);
-        assert_eq!(list.pop_front(), None);
-        assert_eq!(list.len(), 0);
+        m.retain_mut(|x| {
+            *x *= 10;
+            *x > 40
+        });
+        check_links(&m);
+        assert_eq!(to_vec(&m), &[50, 70, 80]);
 
-        // Mess around
-        list.push_front(10);
-        assert_eq!(list.len(), 1);
-        list.push_front(20);
-        assert_eq!(list.len(), 2);
-        list.push_front(30);
-        assert_eq!(list.len(), 3);
-        assert_eq!(list.pop_front(), Some(30));
-        assert_eq!(list.len(), 2);
-        list.push_front(40);
-        assert_eq!(list.len(), 3);
-        assert_eq!(list.pop_front(), Some(40));
-        assert_eq!(list.len(), 2);
-        assert_eq!(list.pop_front(), Some(20));
-        assert_eq!(list.len(), 1);
-        assert_eq!(list.pop_front(), Some(10));
-        assert_eq!(list.len(), 0);
-        assert_eq!(list.pop_front(), None);
-        assert_eq!(list.len(), 0);
-        assert_eq!(list.pop_front(), None);
-        assert_eq!(list.len(), 0);
+        m.retain(|_| false);
+        assert!(m.is_empty());
+        assert_eq!(m.front(), None);
     }
 
     #[test]
-    fn test_basic() {
-        let mut m = LinkedList::new();
-        assert_eq!(m.pop_front(), None);
-        assert_eq!(m.pop_back(), None);
-        assert_eq!(m.pop_front(), None);
-        m.push_front(1);
-        assert_eq!(m.pop_front(), Some(1));
-        m.push_back(2);
-        m.push_back(3);
-        assert_eq!(m.len(), 2);
-        assert_eq!(m.pop_front(), Some(2));
-        assert_eq!(m.pop_front(), Some(3));
-        assert_eq!(m.len(), 0);
-        assert_eq!(m.pop_front(), None);
-        m.push_back(1);
-        m.push_back(3);
-        m.push_back(5);
-        m.push_back(7);
-        assert_eq!(m.pop_front(), Some(1));
+    fn test_dedup() {
+        let mut m: LinkedList<u32> = [1, 1, 2, 3, 3, 3, 1, 4, 4].into_iter().collect();
+        m.dedup();
+        check_links(&m);
+        assert_eq!(to_vec(&m), &[1, 2, 3, 1, 4]);
 
-        let mut n = LinkedList::new();
-        n.push_front(2);
-        n.push_front(3);
-        {
-            assert_eq!(n.front().unwrap(), &3);
-            let x = n.front_mut().unwrap();
-            assert_eq!(*x, 3);
-            *x = 0;
+        let mut m: LinkedList<u32> = [10, 11, 20, 21, 22, 30, 12].into_iter().collect();
+        m.dedup_by_key(|x| *x / 10);
+        check_links(&m);
+        assert_eq!(to_vec(&m), &[10, 20, 30, 12]);
+
+        // Compares against the last kept element, not the last visited one
+        let mut m: LinkedList<u32> = [1, 2, 3, 4, 5, 6].into_iter().collect();
+        m.dedup_by(|cur, prev| *cur - *prev < 2);
+        assert_eq!(to_vec(&m), &[1, 3, 5]);
+
+        let mut empty: LinkedList<u32> = LinkedList::new();
+        empty.dedup();
+        assert!(empty.is_empty());
+    }
+
+    #[test]
+    fn test_contains() {
+        let m: LinkedList<u32> = (0..5).collect();
+        assert!(m.contains(&0));
+        assert!(m.contains(&4));
+        assert!(!m.contains(&5));
+    }
+
+    #[test]
+    fn test_swap() {
+        let mut m: LinkedList<u32> = LinkedList::new();
+        let handles: Vec<_> = (0..6).map(|i| m.push_back_handle(i)).collect();
+        let mut v: Vec<u32> = to_vec(&m);
+        for (a, b) in [(0, 5), (1, 2), (3, 2), (4, 4), (0, 1), (5, 4), (2, 4)] {
+            m.swap(a, b);
+            v.swap(a, b);
+            check_links(&m);
+            assert_eq!(to_vec(&m), v);
         }
-        {
-            assert_eq!(n.back().unwrap(), &2);
-            let y = n.back_mut().unwrap();
-            assert_eq!(*y, 2);
-            *y = 1;
+        assert_eq!(m.front(), v.first());
+        assert_eq!(m.back(), v.last());
+        // Nodes moved, not values
+        for (i, handle) in handles.iter().enumerate() {
+            assert_eq!(handle.get(&m), Some(&(i as u32)));
         }
-        assert_eq!(n.pop_front(), Some(0));
-        assert_eq!(n.pop_front(), Some(1));
     }
 
     #[test]
-    fn test_iterator() {
-        let m = generate_test();
-        for (i, elt) in m.iter().enumerate() {
-            assert_eq!(i as i32, *elt);
+    fn test_drain() {
+        let mut m: LinkedList<u32> = (0..10).collect();
+        let drained: Vec<_> = m.drain(2..5).collect();
+        assert_eq!(drained, &[2, 3, 4]);
+        check_links(&m);
+        assert_eq!(to_vec(&m), &[0, 1, 5, 6, 7, 8, 9]);
+
+        let mut drain = m.drain(1..=5);
+        assert_eq!(drain.len(), 5);
+        assert_eq!(drain.next(), Some(1));
+        assert_eq!(drain.next_back(), Some(8));
+        assert_eq!(drain.next_back(), Some(7));
+        assert_eq!(drain.size_hint(), (2, Some(2)));
+        assert_eq!(drain.next(), Some(5));
+        assert_eq!(drain.next(), Some(6));
+        assert_eq!(drain.next(), None);
+        assert_eq!(drain.next_back(), None);
+        drop(drain);
+        check_links(&m);
+        assert_eq!(to_vec(&m), &[0, 9]);
+
+        assert_eq!(m.drain(1..1).count(), 0);
+        assert_eq!(m.drain(..).collect::<Vec<_>>(), &[0, 9]);
+        assert!(m.is_empty());
+        assert_eq!(m.front(), None);
+        assert_eq!(m.drain(..).next(), None);
+    }
+
+    #[test]
+    fn test_drain_partial() {
+        // Dropping halfway removes the rest of the range
+        let mut m: LinkedList<u32> = (0..10).collect();
+        let mut drain = m.drain(3..8);
+        assert_eq!(drain.next(), Some(3));
+        assert_eq!(drain.next_back(), Some(7));
+        drop(drain);
+        check_links(&m);
+        assert_eq!(to_vec(&m), &[0, 1, 2, 8, 9]);
+
+        // Forgetting it leaves whatever wasn't drained yet in the list
+        let mut drain = m.drain(1..4);
+        assert_eq!(drain.next(), Some(1));
+        std::mem::forget(drain);
+        check_links(&m);
+        assert_eq!(m.len(), 4);
+        assert_eq!(to_vec(&m), &[0, 2, 8, 9]);
+
+        // Handles of drained nodes die with them
+        let mut m: LinkedList<u32> = LinkedList::new();
+        let handles: Vec<_> = (0..4).map(|i| m.push_back_handle(i)).collect();
+        m.drain(1..3);
+        assert_eq!(handles[0].get(&m), Some(&0));
+        assert_eq!(handles[1].get(&m), None);
+        assert_eq!(handles[2].get(&m), None);
+        assert_eq!(handles[3].clone().remove(&mut m), Some(3));
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_drain_out_of_bounds() {
+        let mut m: LinkedList<u32> = (0..3).collect();
+        m.drain(1..4);
+    }
+
+    #[test]
+    fn test_extract_if() {
+        let mut m: LinkedList<u32> = (0..10).collect();
+        let evens: Vec<_> = m.extract_if(|x| *x % 2 == 0).collect();
+        assert_eq!(evens, &[0, 2, 4, 6, 8]);
+        check_links(&m);
+        assert_eq!(to_vec(&m), &[1, 3, 5, 7, 9]);
+
+        // Predicate can change what it keeps
+        let big: Vec<_> = m
+            .extract_if(|x| {
+                *x *= 10;
+                *x > 50
+            })
+            .collect();
+        assert_eq!(big, &[70, 90]);
+        assert_eq!(to_vec(&m), &[10, 30, 50]);
+
+        // Stopping early leaves the rest alone, even the ones that would match
+        // There's no Drop, so forgetting it is no different
+        let mut m: LinkedList<u32> = (0..10).collect();
+        {
+            let mut iter = m.extract_if(|x| *x % 3 == 0);
+            assert_eq!(iter.size_hint(), (0, Some(10)));
+            assert_eq!(iter.next(), Some(0));
+            assert_eq!(iter.next(), Some(3));
+            assert_eq!(iter.size_hint(), (0, Some(6)));
         }
-        let mut n = LinkedList::new();
-        assert_eq!(n.iter().next(), None);
-        n.push_front(4);
-        let mut it = n.iter();
-        assert_eq!(it.size_hint(), (1, Some(1)));
-        assert_eq!(it.next().unwrap(), &4);
-        assert_eq!(it.size_hint(), (0, Some(0)));
-        assert_eq!(it.next(), None);
+        check_links(&m);
+        assert_eq!(to_vec(&m), &[1, 2, 4, 5, 6, 7, 8, 9]);
     }
 
     #[test]
-    fn test_iterator_double_end() {
-        let mut n = LinkedList::new();
-        assert_eq!(n.iter().next(), None);
-        n.push_front(4);
-        n.push_front(5);
-        n.push_front(6);
-        let mut it = n.iter();
-        assert_eq!(it.size_hint(), (3, Some(3)));
-        assert_eq!(it.next().unwrap(), &6);
-        assert_eq!(it.size_hint(), (2, Some(2)));
-        assert_eq!(it.next_back().unwrap(), &4);
-        assert_eq!(it.size_hint(), (1, Some(1)));
-        assert_eq!(it.next_back().unwrap(), &5);
-        assert_eq!(it.next_back(), None);
-        assert_eq!(it.next(), None);
+    fn test_extract_if_panic() {
+        let mut m: LinkedList<u32> = (0..6).collect();
+        let result = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
+            m.extract_if(|x| {
+                assert!(*x != 3);
+                *x % 2 == 0
+            })
+            .for_each(drop);
+        }));
+        assert!(result.is_err());
+        check_links(&m);
+        assert_eq!(to_vec(&m), &[1, 3, 4, 5]);
     }
 
     #[test]
-    fn test_rev_iter() {
-        let m = generate_test();
-        for (i, elt) in m.iter().rev().enumerate() {
-            assert_eq!(6 - i as i32, *elt);
+    fn test_range() {
+        let m: LinkedList<u32> = (0..10).collect();
+        let slice = m.range(2..7);
+        assert_eq!(slice.len(), 5);
+        assert_eq!(slice.first(), Some(&2));
+        assert_eq!(slice.last(), Some(&6));
+        assert_eq!(slice.iter().copied().collect::<Vec<_>>(), &[2, 3, 4, 5, 6]);
+        assert_eq!(
+            slice.iter().rev().copied().collect::<Vec<_>>(),
+            &[6, 5, 4, 3, 2]
+        );
+        assert_eq!(format!("{:?}", slice), "[2, 3, 4, 5, 6]");
+
+        // Sub-slices are relative to the slice
+        let sub = slice.range(1..=2);
+        assert_eq!(sub.into_iter().copied().collect::<Vec<_>>(), &[3, 4]);
+        let empty = slice.range(3..3);
+        assert!(empty.is_empty());
+        assert_eq!(empty.first(), None);
+        assert_eq!(empty.iter().next(), None);
+
+        // Many views at once are fine
+        let all = m.range(..);
+        let tail = m.range(8..);
+        assert_eq!(all.len(), 10);
+        assert_eq!(tail.iter().copied().collect::<Vec<_>>(), &[8, 9]);
+        assert_eq!(slice.first(), Some(&2));
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_range_out_of_bounds() {
+        let m: LinkedList<u32> = (0..10).collect();
+        m.range(5..11);
+    }
+
+    #[test]
+    fn test_range_mut() {
+        let mut m: LinkedList<u32> = (0..10).collect();
+        let mut slice = m.range_mut(3..6);
+        *slice.first_mut().unwrap() = 30;
+        *slice.last_mut().unwrap() = 50;
+        for elem in slice.range_mut(1..2).iter_mut() {
+            *elem = 40;
         }
-        let mut n = LinkedList::new();
-        assert_eq!(n.iter().rev().next(), None);
-        n.push_front(4);
-        let mut it = n.iter().rev();
-        assert_eq!(it.size_hint(), (1, Some(1)));
-        assert_eq!(it.next().unwrap(), &4);
-        assert_eq!(it.size_hint(), (0, Some(0)));
-        assert_eq!(it.next(), None);
+        assert_eq!(slice.len(), 3);
+        assert_eq!(
+            slice.range(..2).iter().copied().collect::<Vec<_>>(),
+            &[30, 40]
+        );
+        assert_eq!(to_vec(&m), &[0, 1, 2, 30, 40, 50, 6, 7, 8, 9]);
     }
 
     #[test]
-    fn test_mut_iter() {
-        let mut m = generate_test();
-        let mut len = m.len();
-        for (i, elt) in m.iter_mut().enumerate() {
-            assert_eq!(i as i32, *elt);
-            len -= 1;
+    fn test_range_mut_reverse() {
+        let mut m: LinkedList<u32> = LinkedList::new();
+        let handles: Vec<_> = (0..8).map(|i| m.push_back_handle(i)).collect();
+
+        let mut slice = m.range_mut(2..6);
+        slice.reverse();
+        assert_eq!(slice.first(), Some(&5));
+        assert_eq!(slice.last(), Some(&2));
+        assert_eq!(format!("{:?}", slice), "[5, 4, 3, 2]");
+        slice.range_mut(1..3).reverse();
+        assert_eq!(format!("{:?}", slice), "[5, 3, 4, 2]");
+        check_links(&m);
+        assert_eq!(to_vec(&m), &[0, 1, 5, 3, 4, 2, 6, 7]);
+
+        // Touching both ends of the list
+        m.range_mut(..).reverse();
+        check_links(&m);
+        assert_eq!(to_vec(&m), &[7, 6, 2, 4, 3, 5, 1, 0]);
+        assert_eq!(m.front(), Some(&7));
+        assert_eq!(m.back(), Some(&0));
+        m.range_mut(..1).reverse();
+        m.range_mut(3..3).reverse();
+        assert_eq!(to_vec(&m), &[7, 6, 2, 4, 3, 5, 1, 0]);
+
+        for (i, handle) in handles.iter().enumerate() {
+            assert_eq!(handle.get(&m), Some(&(i as u32)));
         }
-        assert_eq!(len, 0);
-        let mut n = LinkedList::new();
-        assert!(n.iter_mut().next().is_none());
-        n.push_front(4);
-        n.push_back(5);
-        let mut it = n.iter_mut();
-        assert_eq!(it.size_hint(), (2, Some(2)));
-        assert!(it.next().is_some());
-        assert!(it.next().is_some());
-        assert_eq!(it.size_hint(), (0, Some(0)));
-        assert!(it.next().is_none());
     }
 
     #[test]
-    fn test_iterator_mut_double_end() {
-        let mut n = LinkedList::new();
-        assert!(n.iter_mut().next_back().is_none());
-        n.push_front(4);
-        n.push_front(5);
-        n.push_front(6);
-        let mut it = n.iter_mut();
-        assert_eq!(it.size_hint(), (3, Some(3)));
-        assert_eq!(*it.next().unwrap(), 6);
-        assert_eq!(it.size_hint(), (2, Some(2)));
-        assert_eq!(*it.next_back().unwrap(), 4);
-        assert_eq!(it.size_hint(), (1, Some(1)));
-        assert_eq!(*it.next_back().unwrap(), 5);
-        assert!(it.next_back().is_none());
-        assert!(it.next().is_none());
+    fn test_range_mut_sort() {
+        let mut m: LinkedList<u32> = [9, 8, 7, 6, 5, 4, 3, 2, 1, 0].into_iter().collect();
+        let mut slice = m.range_mut(2..8);
+        slice.sort();
+        assert_eq!(slice.first(), Some(&2));
+        assert_eq!(slice.last(), Some(&7));
+        check_links(&m);
+        assert_eq!(to_vec(&m), &[9, 8, 2, 3, 4, 5, 6, 7, 1, 0]);
+
+        m.range_mut(7..).sort_by_key(|x| *x);
+        check_links(&m);
+        assert_eq!(to_vec(&m), &[9, 8, 2, 3, 4, 5, 6, 0, 1, 7]);
+        assert_eq!(m.back(), Some(&7));
+
+        m.range_mut(..3).sort();
+        check_links(&m);
+        assert_eq!(to_vec(&m), &[2, 8, 9, 3, 4, 5, 6, 0, 1, 7]);
+        assert_eq!(m.front(), Some(&2));
+
+        // Panicking comparator can't tear a hole in the list
+        let result = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
+            m.range_mut(2..8).sort_by(|_, _| panic!("no"));
+        }));
+        assert!(result.is_err());
+        check_links(&m);
+        assert_eq!(m.len(), 10);
+        assert_eq!(to_vec(&m), &[2, 8, 9, 3, 4, 5, 6, 0, 1, 7]);
+    }
+
+    #[test]
+    fn test_rotate() {
+        for n in 0..=7 {
+            let mut m: LinkedList<u32> = (0..7).collect();
+            let mut v: std::collections::VecDeque<u32> = (0..7).collect();
+            m.rotate_left(n);
+            v.rotate_left(n);
+            check_links(&m);
+            assert_eq!(to_vec(&m), v.iter().copied().collect::<Vec<_>>());
+            assert_eq!(m.front(), v.front());
+            assert_eq!(m.back(), v.back());
+
+            m.rotate_right(n);
+            assert_eq!(to_vec(&m), (0..7).collect::<Vec<_>>());
+        }
+
+        let mut m: LinkedList<u32> = LinkedList::new();
+        m.rotate_left(0);
+        m.rotate_right(0);
+        assert!(m.is_empty());
+
+        // Round-robin, same nodes going around
+        let mut m: LinkedList<u32> = LinkedList::new();
+        let first = m.push_back_handle(0);
+        m.extend(1..3);
+        for _ in 0..4 {
+            m.rotate_left(1);
+        }
+        check_links(&m);
+        assert_eq!(to_vec(&m), &[1, 2, 0]);
+        assert_eq!(first.get(&m), Some(&0));
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_rotate_out_of_bounds() {
+        let mut m: LinkedList<u32> = (0..3).collect();
+        m.rotate_right(4);
     }
 
     #[test]
-    fn test_eq() {
-        let mut n: LinkedList<u8> = list_from(&[]);
-        let mut m = list_from(&[]);
-        assert!(n == m);
-        n.push_front(1);
-        assert!(n != m);
-        m.push_back(1);
-        assert!(n == m);
+    fn test_reverse() {
+        let mut m: LinkedList<u32> = (0..6).collect();
+        let back = m.push_back_handle(6);
+        m.reverse();
+        check_links(&m);
+        assert_eq!(to_vec(&m), &[6, 5, 4, 3, 2, 1, 0]);
+        assert_eq!(m.front(), Some(&6));
+        assert_eq!(m.back(), Some(&0));
+        assert_eq!(back.get(&m), Some(&6));
+        assert_eq!(m.pop_front(), Some(6));
+        m.push_back(10);
+        check_links(&m);
+        assert_eq!(to_vec(&m), &[5, 4, 3, 2, 1, 0, 10]);
+
+        let mut one: LinkedList<u32> = (0..1).collect();
+        one.reverse();
+        assert_eq!(to_vec(&one), &[0]);
+        let mut empty: LinkedList<u32> = LinkedList::new();
+        empty.reverse();
+        assert!(empty.is_empty());
+    }
 
-        let n = list_from(&[2, 3, 4]);
-        let m = list_from(&[1, 2, 3]);
-        assert!(n != m);
+    // Every index, in an order that makes the finger jump around
+    fn check_get(list: &LinkedList<u32>) {
+        let expected = to_vec(list);
+        let len = expected.len();
+        let order = (0..len)
+            .chain((0..len).rev())
+            .chain((0..len).map(|i| i * 7 % len.max(1)));
+        for i in order {
+            assert_eq!(list.get(i), Some(&expected[i]));
+            assert_eq!(list[i], expected[i]);
+            assert_eq!(finger_index(list), Some(i));
+        }
+        assert_eq!(list.get(len), None);
     }
 
-    #[test]
-    fn test_ord() {
-        let n = list_from(&[]);
-        let m = list_from(&[1, 2, 3]);
-        assert!(n < m);
-        assert!(m > n);
-        assert!(n <= n);
-        assert!(n >= n);
+    fn finger_index<T>(list: &LinkedList<T>) -> Option<usize> {
+        list.finger.lock().unwrap().map(|(index, _)| index)
     }
 
     #[test]
-    fn test_ord_nan() {
-        let nan = 0.0f64 / 0.0;
-        let n = list_from(&[nan]);
-        let m = list_from(&[nan]);
-        assert!(!(n < m));
-        assert!(!(n > m));
-        assert!(!(n <= m));
-        assert!(!(n >= m));
+    fn test_get() {
+        let mut m: LinkedList<u32> = (0..100).collect();
+        check_get(&m);
+        assert_eq!(m.get(100), None);
+        assert_eq!(m.get_mut(100), None);
+
+        *m.get_mut(50).unwrap() = 500;
+        m[51] = 510;
+        m[52] += 1;
+        assert_eq!(m.get(50), Some(&500));
+        assert_eq!(m[51], 510);
+        assert_eq!(m[52], 53);
+        assert_eq!(finger_index(&m), Some(52));
+
+        let empty: LinkedList<u32> = LinkedList::new();
+        assert_eq!(empty.get(0), None);
+    }
 
-        let n = list_from(&[nan]);
-        let one = list_from(&[1.0f64]);
-        assert!(!(n < one));
-        assert!(!(n > one));
-        assert!(!(n <= one));
-        assert!(!(n >= one));
+    #[test]
+    #[should_panic(expected = "index out of bounds")]
+    fn test_index_out_of_bounds() {
+        let m: LinkedList<u32> = (0..3).collect();
+        let _ = m[3];
+    }
 
-        let u = list_from(&[1.0f64, 2.0, nan]);
-        let v = list_from(&[1.0f64, 2.0, 3.0]);
-        assert!(!(u < v));
-        assert!(!(u > v));
-        assert!(!(u <= v));
-        assert!(!(u >= v));
+    #[test]
+    fn test_finger_shared_walk() {
+        // Only &LinkedList here, get and Index move the finger on their own
+        let m: LinkedList<u32> = (0..1000).collect();
+        let list = &m;
+        for i in 0..1000 {
+            assert_eq!(list[i], i as u32);
+            assert_eq!(finger_index(list), Some(i));
+        }
+        for i in (0..1000).rev().step_by(3) {
+            assert_eq!(list.get(i), Some(&(i as u32)));
+            assert_eq!(finger_index(list), Some(i));
+        }
 
-        let s = list_from(&[1.0f64, 2.0, 4.0, 2.0]);
-        let t = list_from(&[1.0f64, 2.0, 3.0, 2.0]);
-        assert!(!(s < t));
-        assert!(s > one);
-        assert!(!(s <= one));
-        assert!(s >= one);
+        // Threads fighting over the finger just walk from the ends, and still find the right nodes
+        std::thread::scope(|s| {
+            for t in 0..4 {
+                s.spawn(move || {
+                    for i in (t..1000).step_by(4) {
+                        assert_eq!(list[i], i as u32);
+                    }
+                });
+            }
+        });
+        check_get(&m);
     }
 
     #[test]
-    fn test_debug() {
-        let list: LinkedList<i32> = (0..10).collect();
-        assert_eq!(format!("{:?}", list), "[0, 1, 2, 3, 4, 5, 6, 7, 8, 9]");
-
-        let list: LinkedList<&str> = vec!["just", "one", "test", "more"]
-            .iter()
-            .copied()
-            .collect();
-        assert_eq!(format!("{:?}", list), r#"["just", "one", "test", "more"]"#);
+    fn test_finger_follows_ends() {
+        let mut m: LinkedList<u32> = (0..10).collect();
+        m.get(5);
+        m.push_front(100);
+        assert_eq!(finger_index(&m), Some(6));
+        m.push_back(200);
+        assert_eq!(finger_index(&m), Some(6));
+        assert_eq!(m.pop_front(), Some(100));
+        assert_eq!(finger_index(&m), Some(5));
+        check_get(&m);
+
+        // Popping the node the finger sits on forgets it
+        m.get(0);
+        m.pop_front();
+        assert_eq!(finger_index(&m), None);
+        m.get(m.len() - 1);
+        m.pop_back();
+        assert_eq!(finger_index(&m), None);
+        check_get(&m);
+        m.truncate(3);
+        check_get(&m);
     }
 
     #[test]
-    fn test_hashmap() {
-        // Check that HashMap works with this as a key
+    fn test_finger_invalidation() {
+        let mut m: LinkedList<u32> = (0..20).collect();
+        let handle = m.push_back_handle(20);
+        let ops: Vec<fn(&mut LinkedList<u32>)> = vec![
+            |m| m.insert(3, 100),
+            |m| {
+                m.remove(4);
+            },
+            |m| m.swap(1, 15),
+            |m| m.rotate_left(7),
+            |m| m.reverse(),
+            |m| m.sort(),
+            |m| m.range_mut(2..12).reverse(),
+            |m| {
+                m.drain(5..8);
+            },
+            |m| {
+                m.extract_if(|x| *x % 6 == 1).for_each(drop);
+            },
+            |m| m.retain(|x| *x != 7),
+            |m| {
+                let mut cursor = m.cursor_mut();
+                cursor.seek_to(3);
+                let front = cursor.split_before();
+                cursor.seek_to(2);
+                cursor.splice_after(front);
+            },
+            |m| {
+                let mut cursor = m.cursor_mut();
+                cursor.seek_to(4);
+                let back = cursor.split_after();
+                cursor.seek_to(1);
+                cursor.splice_before(back);
+                cursor.insert_after(300);
+                cursor.remove_current();
+            },
+            |m| {
+                let mut tail = m.split_off(6);
+                tail.get(2);
+                m.get(3);
+                tail.append(m);
+                std::mem::swap(m, &mut tail);
+            },
+            |m| m.push_front(400),
+            |m| {
+                m.pop_front();
+            },
+        ];
+        for op in ops {
+            // Leave the finger somewhere in the middle, then shake things up
+            let middle = m.len() / 2;
+            m.get(middle);
+            op(&mut m);
+            check_links(&m);
+            check_get(&m);
+        }
 
-        let list1: LinkedList<i32> = (0..10).collect();
-        let list2: LinkedList<i32> = (1..11).collect();
-        let mut map = std::collections::HashMap::new();
+        m.get(m.len() / 2);
+        assert!(handle.move_to_front(&mut m));
+        check_get(&m);
+        m.get(m.len() / 2);
+        handle.remove(&mut m);
+        check_get(&m);
+    }
 
-        assert_eq!(map.insert(list1.clone(), "list1"), None);
-        assert_eq!(map.insert(list2.clone(), "list2"), None);
+    // Tiny LCG so we don't need a rand dependency
+    fn pseudo_random(seed: &mut u64) -> u64 {
+        *seed = seed
+            .wrapping_mul(6364136223846793005)
+            .wrapping_add(1442695040888963407);
+        *seed >> 33
+    }
 
-        assert_eq!(map.len(), 2);
+    #[test]
+    fn test_sort() {
+        let mut seed = 42;
+        for len in [0, 1, 2, 3, 10, 100, 1000] {
+            for modulo in [2, 10, 1000] {
+                let values: Vec<u64> = (0..len)
+                    .map(|_| pseudo_random(&mut seed) % modulo)
+                    .collect();
+                let mut m: LinkedList<u64> = values.iter().copied().collect();
+                let mut expected = values.clone();
+                expected.sort();
+                m.sort();
+                check_links(&m);
+                assert_eq!(m.len(), len);
+                assert_eq!(m.iter().copied().collect::<Vec<_>>(), expected);
+
+                m.sort_unstable_by(|a, b| b.cmp(a));
+                expected.reverse();
+                assert_eq!(m.iter().copied().collect::<Vec<_>>(), expected);
+            }
+        }
 
-        assert_eq!(map.get(&list1), Some(&"list1"));
-        assert_eq!(map.get(&list2), Some(&"list2"));
+        // Already sorted runs, both ways
+        let mut m: LinkedList<u32> = (0..50).chain((0..50).rev()).chain(25..75).collect();
+        let mut expected: Vec<_> = m.iter().copied().collect();
+        expected.sort();
+        m.sort();
+        check_links(&m);
+        assert_eq!(m.iter().copied().collect::<Vec<_>>(), expected);
+        assert_eq!(m.front(), Some(&0));
+        assert_eq!(m.back(), Some(&74));
+    }
 
-        assert_eq!(map.remove(&list1), Some("list1"));
-        assert_eq!(map.remove(&list2), Some("list2"));
+    #[test]
+    fn test_sort_stable() {
+        let mut seed = 7;
+        // Sort by key only, the index tells us if equal keys kept their order
+        let values: Vec<(u64, usize)> = (0..500)
+            .map(|i| (pseudo_random(&mut seed) % 10, i))
+            .collect();
+        let mut m: LinkedList<_> = values.iter().copied().collect();
+        let mut expected = values.clone();
+        expected.sort_by_key(|&(key, _)| key);
+        m.sort_by_key(|&(key, _)| key);
+        check_links(&m);
+        assert_eq!(m.iter().copied().collect::<Vec<_>>(), expected);
 
-        assert!(map.is_empty());
+        // Descending runs get reversed, equal elements must not be part of them
+        let mut m: LinkedList<(u32, u32)> = [(3, 0), (2, 1), (2, 2), (1, 3), (1, 4), (0, 5)]
+            .into_iter()
+            .collect();
+        m.sort_by_key(|&(key, _)| key);
+        assert_eq!(
+            m.iter().copied().collect::<Vec<_>>(),
+            &[(0, 5), (1, 3), (1, 4), (2, 1), (2, 2), (3, 0)]
+        );
     }
 
-    // Spooky test to check for trait implementation
-    #[allow(dead_code)]
-    fn assert_properties() {
-        fn is_send<T: Send>() {}
-        fn is_sync<T: Sync>() {}
-
-        is_send::<LinkedList<i32>>();
-        is_sync::<LinkedList<i32>>();
+    #[test]
+    fn test_sort_keeps_nodes() {
+        let mut m: LinkedList<u32> = LinkedList::new();
+        let handles: Vec<_> = [5, 3, 9, 1]
+            .into_iter()
+            .map(|x| m.push_back_handle(x))
+            .collect();
+        m.sort();
+        assert_eq!(m.iter().copied().collect::<Vec<_>>(), &[1, 3, 5, 9]);
+        for (handle, expected) in handles.iter().zip([5, 3, 9, 1]) {
+            assert_eq!(handle.get(&m), Some(&expected));
+        }
+    }
 
-        is_send::<IntoIter<i32>>();
-        is_sync::<IntoIter<i32>>();
+    #[test]
+    fn test_sort_panic() {
+        let mut seed = 3;
+        let values: Vec<u64> = (0..200).map(|_| pseudo_random(&mut seed) % 50).collect();
+
+        // Descending input panics halfway through reversing the first run
+        let descending = (0..100).rev().chain(0..100).collect::<Vec<u64>>();
+        for (values, panic_at) in [
+            (&values, 0),
+            (&values, 1),
+            (&values, 5),
+            (&values, 50),
+            (&values, 199),
+            (&values, 500),
+            (&descending, 30),
+        ] {
+            let mut expected = values.clone();
+            expected.sort();
+            let mut m: LinkedList<u64> = values.iter().copied().collect();
+            let mut comparisons = 0;
+            let result = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
+                m.sort_by(|a, b| {
+                    comparisons += 1;
+                    if comparisons > panic_at {
+                        panic!("comparator gave up");
+                    }
+                    a.cmp(b)
+                })
+            }));
+            assert!(result.is_err());
+
+            // Still a valid list, with exactly the same elements
+            check_links(&m);
+            assert_eq!(m.len(), values.len());
+            assert_eq!(m.iter().count(), values.len());
+            let mut after: Vec<_> = m.iter().copied().collect();
+            after.sort();
+            assert_eq!(after, expected);
+            assert!(m.pop_back().is_some());
+        }
+    }
 
-        is_send::<Iter<i32>>();
-        is_sync::<Iter<i32>>();
+    // Counts the nodes it has handed out, clones share the count
+    // Two separately created ones are two different heaps that can't be mixed
+    #[derive(Clone)]
+    struct CountingAlloc {
+        live: Rc<Cell<usize>>,
+    }
 
-        is_send::<IterMut<i32>>();
-        is_sync::<IterMut<i32>>();
+    impl CountingAlloc {
+        fn new() -> Self {
+            CountingAlloc {
+                live: Rc::new(Cell::new(0)),
+            }
+        }
 
-        is_send::<Cursor<i32>>();
-        is_sync::<Cursor<i32>>();
+        fn live(&self) -> usize {
+            self.live.get()
+        }
+    }
 
-        fn linked_list_covariant<'a, T>(x: LinkedList<&'static T>) -> LinkedList<&'a T> {
-            x
+    unsafe impl NodeAllocator for CountingAlloc {
+        fn allocate(&self, layout: Layout) -> Result<NonNull<u8>, AllocError> {
+            let ptr = Global.allocate(layout)?;
+            self.live.set(self.live.get() + 1);
+            Ok(ptr)
         }
-        fn iter_covariant<'i, 'a, T>(x: Iter<'i, &'static T>) -> Iter<'i, &'a T> {
-            x
+
+        unsafe fn deallocate(&self, ptr: NonNull<u8>, layout: Layout) {
+            // Freeing something we never allocated underflows and panics
+            self.live.set(self.live.get() - 1);
+            unsafe { Global.deallocate(ptr, layout) }
         }
-        fn into_iter_covariant<'a, T>(x: IntoIter<&'static T>) -> IntoIter<&'a T> {
-            x
+
+        fn is_compatible_with(&self, other: &Self) -> bool {
+            Rc::ptr_eq(&self.live, &other.live)
         }
     }
 
     #[test]
-    fn test_cursor_move_peek() {
-        let mut m: LinkedList<u32> = LinkedList::new();
-        m.extend([1, 2, 3, 4, 5, 6]);
-        let mut cursor = m.cursor_mut();
-        cursor.move_next();
-        assert_eq!(cursor.current(), Some(&mut 1));
-        assert_eq!(cursor.peek_next(), Some(&mut 2));
-        assert_eq!(cursor.peek_prev(), None);
-        assert_eq!(cursor.index(), Some(0));
-        cursor.move_prev();
-        assert_eq!(cursor.current(), None);
-        assert_eq!(cursor.peek_next(), Some(&mut 1));
-        assert_eq!(cursor.peek_prev(), Some(&mut 6));
-        assert_eq!(cursor.index(), None);
-        cursor.move_next();
-        cursor.move_next();
-        assert_eq!(cursor.current(), Some(&mut 2));
-        assert_eq!(cursor.peek_next(), Some(&mut 3));
-        assert_eq!(cursor.peek_prev(), Some(&mut 1));
-        assert_eq!(cursor.index(), Some(1));
+    fn test_allocator() {
+        let alloc = CountingAlloc::new();
+        let mut m = LinkedList::new_in(alloc.clone());
+        m.extend(0..6);
+        m.push_front(-1);
+        assert_eq!(alloc.live(), 7);
+        assert_eq!(m.pop_back(), Some(5));
+        assert_eq!(alloc.live(), 6);
+
+        // Split off lists keep using the same allocator
+        let tail = m.split_off(3);
+        assert!(tail.allocator().is_compatible_with(&alloc));
+        let copy = tail.clone();
+        assert_eq!(alloc.live(), 9);
+        drop(copy);
+        assert_eq!(alloc.live(), 6);
 
         let mut cursor = m.cursor_mut();
-        cursor.move_prev();
-        assert_eq!(cursor.current(), Some(&mut 6));
-        assert_eq!(cursor.peek_next(), None);
-        assert_eq!(cursor.peek_prev(), Some(&mut 5));
-        assert_eq!(cursor.index(), Some(5));
         cursor.move_next();
-        assert_eq!(cursor.current(), None);
-        assert_eq!(cursor.peek_next(), Some(&mut 1));
-        assert_eq!(cursor.peek_prev(), Some(&mut 6));
-        assert_eq!(cursor.index(), None);
-        cursor.move_prev();
-        cursor.move_prev();
-        assert_eq!(cursor.current(), Some(&mut 5));
-        assert_eq!(cursor.peek_next(), Some(&mut 6));
-        assert_eq!(cursor.peek_prev(), Some(&mut 4));
-        assert_eq!(cursor.index(), Some(4));
+        cursor.insert_after(10);
+        cursor.splice_after(tail);
+        let mut rest = cursor.split_after();
+        assert_eq!(alloc.live(), 7);
+        assert_eq!(to_vec(&rest), &[2, 3, 4, 10, 0, 1]);
+        assert_eq!(rest.remove(3), 10);
+        assert_eq!(alloc.live(), 6);
+
+        // Empty lists on the same allocator can be spliced too
+        m.append(&mut LinkedList::new_in(alloc.clone()));
+        m.append(&mut rest);
+        check_links(&m);
+        assert_eq!(to_vec(&m), &[-1, 2, 3, 4, 0, 1]);
+        m.drain(1..3);
+        m.retain(|&x| x != 0);
+        assert_eq!(alloc.live(), 3);
+
+        drop(m);
+        drop(rest);
+        assert_eq!(alloc.live(), 0);
     }
 
     #[test]
-    fn test_cursor_mut_insert() {
-        let mut m: LinkedList<u32> = LinkedList::new();
-        m.extend([1, 2, 3, 4, 5, 6]);
-        let mut cursor = m.cursor_mut();
-        cursor.move_next();
-        cursor.splice_before(Some(7).into_iter().collect());
-        cursor.splice_after(Some(8).into_iter().collect());
-        // check_links(&m);
-        assert_eq!(m.iter().cloned().collect::<Vec<_>>(), &[7, 1, 8, 2, 3, 4, 5, 6]);
-        let mut cursor = m.cursor_mut();
-        cursor.move_next();
-        cursor.move_prev();
-        cursor.splice_before(Some(9).into_iter().collect());
-        cursor.splice_after(Some(10).into_iter().collect());
-        check_links(&m);
-        assert_eq!(m.iter().cloned().collect::<Vec<_>>(), &[10, 7, 1, 8, 2, 3, 4, 5, 6, 9]);
+    fn test_allocator_incompatible() {
+        let (a, b) = (CountingAlloc::new(), CountingAlloc::new());
+        let mut m = LinkedList::new_in(a.clone());
+        m.extend([1, 2]);
+        let mut n = LinkedList::new_in(b.clone());
+        n.push_back(3);
+
+        // Each one gets rejected before anything moves
+        let result = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| m.append(&mut n)));
+        assert!(result.is_err());
+        let result = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
+            m.cursor_mut().splice_before(n.clone())
+        }));
+        assert!(result.is_err());
+        let result = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
+            m.cursor_mut().splice_after(LinkedList::new_in(b.clone()))
+        }));
+        assert!(result.is_err());
+
+        assert_eq!(to_vec(&m), &[1, 2]);
+        assert_eq!(to_vec(&n), &[3]);
+        assert_eq!((a.live(), b.live()), (2, 1));
+        drop(m);
+        drop(n);
+        assert_eq!((a.live(), b.live()), (0, 0));
+    }
 
-        /*let mut cursor = m.cursor_mut();
-        cursor.move_next();
-        cursor.move_prev();
-        assert_eq!(cursor.remove_current(), None);
-        cursor.move_next();
-        cursor.move_next();
-        assert_eq!(cursor.remove_current(), Some(7));
-        cursor.move_prev();
-        cursor.move_prev();
-        cursor.move_prev();
-        assert_eq!(cursor.remove_current(), Some(9));
-        cursor.move_next();
-        assert_eq!(cursor.remove_current(), Some(10));
-        check_links(&m);
-        assert_eq!(m.iter().cloned().collect::<Vec<_>>(), &[1, 8, 2, 3, 4, 5, 6]);
-        */
+    #[test]
+    fn test_arena() {
+        let arena = ListArena::with_chunk_size(256);
+        let mut m = arena.list();
+        let mut n = arena.list();
+        for i in 0..100 {
+            m.push_back(i);
+            n.push_front(i);
+        }
+        assert!(arena.chunk_count() > 1);
+        let bytes = arena.allocated_bytes();
 
-        let mut m: LinkedList<u32> = LinkedList::new();
-        m.extend([1, 8, 2, 3, 4, 5, 6]);
-        let mut cursor = m.cursor_mut();
-        cursor.move_next();
-        let mut p: LinkedList<u32> = LinkedList::new();
-        p.extend([100, 101, 102, 103]);
-        let mut q: LinkedList<u32> = LinkedList::new();
-        q.extend([200, 201, 202, 203]);
-        cursor.splice_after(p);
-        cursor.splice_before(q);
+        // Same arena, so moving nodes between the lists is fine
+        m.append(&mut n);
+        let tail = m.split_off(150);
         check_links(&m);
+        check_links(&tail);
+        assert_eq!(to_vec(&tail), (0..50).rev().collect::<Vec<_>>());
+        assert_eq!(m.len(), 150);
+
+        // Popped nodes aren't handed out again, but nothing is freed or allocated either
+        m.clear();
+        assert_eq!(arena.allocated_bytes(), bytes);
+        m.push_back(7);
+        assert_eq!(m.front(), Some(&7));
+
+        // Nodes that don't fit in a chunk get one of their own
+        let small = ListArena::with_chunk_size(8);
+        let mut big = small.list();
+        big.extend([1u64, 2, 3]);
+        assert_eq!(small.chunk_count(), 3);
+        assert_eq!(to_vec(&big), &[1, 2, 3]);
+    }
+
+    #[test]
+    fn test_arena_drop() {
+        let counter = Rc::new(());
+        let arena = ListArena::new();
+        {
+            let mut m = arena.list();
+            m.extend(std::iter::repeat_n(counter.clone(), 10));
+            drop(m.pop_front());
+            assert_eq!(Rc::strong_count(&counter), 10);
+        }
+        // Element destructors still ran
+        assert_eq!(Rc::strong_count(&counter), 1);
+
+        // Copy elements have nothing to drop, the nodes just wait for the arena
+        let mut m = arena.list();
+        m.extend(0..1000);
+        m.forget();
+
+        // Handles have to be told their node is gone, so those lists still get walked
+        let mut m = arena.list();
+        let handle = m.push_back_handle(1);
+        m.forget();
+        let other = arena.list();
+        assert_eq!(handle.get(&other), None);
+    }
+
+    #[test]
+    fn test_arena_incompatible() {
+        let (a, b) = (ListArena::new(), ListArena::new());
+        let mut m = a.list();
+        m.push_back(1);
+        let mut n = b.list();
+        n.push_back(2);
+
+        let result = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| m.append(&mut n)));
+        assert!(result.is_err());
+        assert_eq!(to_vec(&m), &[1]);
+        assert_eq!(to_vec(&n), &[2]);
+    }
+
+    #[test]
+    fn test_node_pool() {
+        let pool = NodePool::new(4);
+        let mut m = pool.list();
+        let mut n = pool.list();
+        m.extend(0..10);
         assert_eq!(
-            m.iter().cloned().collect::<Vec<_>>(),
-            &[200, 201, 202, 203, 1, 100, 101, 102, 103, 8, 2, 3, 4, 5, 6]
+            pool.stats(),
+            PoolStats {
+                hits: 0,
+                misses: 10,
+                retained: 0
+            }
         );
-        let mut cursor = m.cursor_mut();
-        cursor.move_next();
-        cursor.move_prev();
-        let tmp = cursor.split_before();
-        assert_eq!(m.into_iter().collect::<Vec<_>>(), &[]);
-        m = tmp;
-        let mut cursor = m.cursor_mut();
-        cursor.move_next();
-        cursor.move_next();
-        cursor.move_next();
-        cursor.move_next();
-        cursor.move_next();
-        cursor.move_next();
-        cursor.move_next();
-        let tmp = cursor.split_after();
+
+        // Only cap nodes are kept, the rest goes back to the global allocator
+        m.truncate(3);
+        assert_eq!(pool.stats().retained, 4);
+
+        // Other lists on the pool get the recycled nodes
+        n.extend(0..6);
         assert_eq!(
-            tmp.into_iter().collect::<Vec<_>>(),
-            &[102, 103, 8, 2, 3, 4, 5, 6]
+            pool.stats(),
+            PoolStats {
+                hits: 4,
+                misses: 12,
+                retained: 0
+            }
         );
-        check_links(&m);
+
+        // Steady churn doesn't miss anymore
+        for i in 0..100 {
+            n.push_back(i);
+            n.pop_front();
+        }
+        assert_eq!(pool.stats().misses, 13);
+        m.append(&mut n);
+        assert_eq!(m.len(), 9);
+
+        // Lists keep the pool alive after we let go of it
+        let stats = pool.stats();
+        drop(pool);
+        m.push_front(-1);
+        assert_eq!(m.allocator().stats().hits, stats.hits + 1);
+        assert_eq!(m.allocator().cap(), 4);
+        m.clear();
+        assert_eq!(m.allocator().stats().retained, 4);
+    }
+
+    #[test]
+    fn test_try_push() {
+        let budget = Rc::new(Cell::new(3));
+        let mut m = LinkedList::new_in(FailingAlloc {
+            budget: budget.clone(),
+        });
+        assert_eq!(m.try_push_back(1), Ok(()));
+        assert_eq!(m.try_push_front(0), Ok(()));
+        assert_eq!(m[1], 1);
+
+        // Runs out halfway, none of it makes it in
+        let err = m.try_extend([2, 3, 4]).unwrap_err();
+        assert_eq!(err.layout(), Layout::new::<Node<i32>>());
         assert_eq!(
-            m.iter().cloned().collect::<Vec<_>>(),
-            &[200, 201, 202, 203, 1, 100, 101]
+            err.to_string(),
+            format!("memory allocation of {} bytes failed", err.layout().size())
         );
+
+        // Failed pushes leave everything as it was
+        assert_eq!(m.try_push_front(-1), Err(err));
+        assert_eq!(m.try_push_back(5), Err(err));
+        check_links(&m);
+        assert_eq!(to_vec(&m), &[0, 1]);
+        assert_eq!(m.len(), 2);
+        assert_eq!(m[1], 1);
+
+        budget.set(3);
+        assert_eq!(m.try_extend([2, 3, 4]), Ok(()));
+        assert_eq!(to_vec(&m), &[0, 1, 2, 3, 4]);
+
+        // Element of a failed push is dropped, not leaked
+        let counter = Rc::new(());
+        let mut n = LinkedList::new_in(FailingAlloc {
+            budget: Rc::new(Cell::new(0)),
+        });
+        assert!(n.try_push_back(counter.clone()).is_err());
+        assert!(n.is_empty());
+        assert_eq!(Rc::strong_count(&counter), 1);
     }
 
-    fn check_links<T: Eq + Debug>(list: &LinkedList<T>) {
+    fn check_links<T: Eq + Debug, A: NodeAllocator>(list: &LinkedList<T, A>) {
         let from_front: Vec<_> = list.iter().collect();
         let from_back: Vec<_> = list.iter().rev().collect();
         let re_reved: Vec<_> = from_back.into_iter().rev().collect();
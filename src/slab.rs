@@ -0,0 +1,233 @@
+// Just the storage under slab_deque, safe like the rest of it
+#![forbid(unsafe_code)]
+
+use std::mem;
+
+/*
+    A Vec of slots that never move, each either holding a node or free
+    Freed slots go on a free list of their own and get reused by the next insert
+
+    Every slot also has a generation, in a Vec next to the slots, moved on whenever the slot is freed
+    So a generation read while the slot held one element never matches it again once that element is gone
+    (well, until it wraps around after 4 billion reuses of the same slot)
+*/
+
+pub type Index = u32;
+
+// Our None, u32::MAX never is a valid slot
+pub const NIL: Index = u32::MAX;
+
+pub type Generation = u32;
+
+// The links are whatever the owner wants them to be
+pub struct Node<T> {
+    pub elem: T,
+    pub front: Index,
+    pub back: Index,
+}
+
+pub enum Slot<T> {
+    Occupied(Node<T>),
+    Vacant { next_free: Index },
+}
+
+pub struct Slab<T> {
+    slots: Vec<Slot<T>>,
+    generations: Vec<Generation>,
+    free: Index,
+}
+
+impl<T> Slab<T> {
+    pub fn new() -> Self {
+        Slab {
+            slots: Vec::new(),
+            generations: Vec::new(),
+            free: NIL,
+        }
+    }
+
+    pub fn with_capacity(capacity: usize) -> Self {
+        Slab {
+            slots: Vec::with_capacity(capacity),
+            generations: Vec::with_capacity(capacity),
+            free: NIL,
+        }
+    }
+
+    pub fn capacity(&self) -> usize {
+        self.slots.capacity()
+    }
+
+    // Occupied and free ones, the slab never shrinks
+    #[cfg(test)]
+    pub fn slot_count(&self) -> usize {
+        self.slots.len()
+    }
+
+    // Some only while the slot is occupied
+    pub fn generation(&self, index: Index) -> Option<Generation> {
+        self.get(index)?;
+        Some(self.generations[index as usize])
+    }
+
+    pub fn get(&self, index: Index) -> Option<&Node<T>> {
+        match self.slots.get(index as usize)? {
+            Slot::Occupied(node) => Some(node),
+            Slot::Vacant { .. } => None,
+        }
+    }
+
+    pub fn get_mut(&mut self, index: Index) -> Option<&mut Node<T>> {
+        match self.slots.get_mut(index as usize)? {
+            Slot::Occupied(node) => Some(node),
+            Slot::Vacant { .. } => None,
+        }
+    }
+
+    // Put elem in a free slot, or a new one at the end, links set to NIL
+    pub fn insert(&mut self, elem: T) -> Index {
+        let slot = Slot::Occupied(Node {
+            elem,
+            front: NIL,
+            back: NIL,
+        });
+        if self.free != NIL {
+            let index = self.free;
+            match mem::replace(&mut self.slots[index as usize], slot) {
+                Slot::Vacant { next_free } => self.free = next_free,
+                Slot::Occupied(_) => unreachable!("occupied slot on the free list"),
+            }
+            index
+        } else {
+            assert!(
+                self.slots.len() < NIL as usize,
+                "Slab can't hold more than u32::MAX - 1 elements"
+            );
+            self.slots.push(slot);
+            self.generations.push(0);
+            (self.slots.len() - 1) as Index
+        }
+    }
+
+    pub fn remove(&mut self, index: Index) -> Option<T> {
+        self.get(index)?;
+        let slot = mem::replace(
+            &mut self.slots[index as usize],
+            Slot::Vacant {
+                next_free: self.free,
+            },
+        );
+        self.free = index;
+        let generation = &mut self.generations[index as usize];
+        *generation = generation.wrapping_add(1);
+        match slot {
+            Slot::Occupied(node) => Some(node.elem),
+            Slot::Vacant { .. } => unreachable!(),
+        }
+    }
+
+    // Many &mut at once, in the order of indices, all of them up front
+    // split_at_mut only hands out disjoint borrows left to right, so go through the slots in index order
+    // and put each one back in its place after
+    // Panics if an index is free or shows up twice, that would be two &mut to one element
+    pub fn get_disjoint_mut(&mut self, indices: &[Index]) -> Vec<&mut T> {
+        let mut by_index: Vec<(Index, usize)> = indices.iter().copied().zip(0..).collect();
+        by_index.sort_unstable();
+
+        let mut elems: Vec<Option<&mut T>> = Vec::with_capacity(indices.len());
+        elems.resize_with(indices.len(), || None);
+        let mut rest = &mut self.slots[..];
+        let mut offset = 0;
+        for (index, position) in by_index {
+            let at = (index as usize)
+                .checked_sub(offset)
+                .unwrap_or_else(|| panic!("index {index} shows up twice"));
+            let Some((slot, tail)) = mem::take(&mut rest)
+                .get_mut(at..)
+                .and_then(<[_]>::split_first_mut)
+            else {
+                panic!("index {index} is out of bounds");
+            };
+            match slot {
+                Slot::Occupied(node) => elems[position] = Some(&mut node.elem),
+                Slot::Vacant { .. } => panic!("index {index} is free"),
+            }
+            rest = tail;
+            offset = index as usize + 1;
+        }
+        // Same size as the Options, so this reuses their Vec
+        elems
+            .into_iter()
+            .map(|elem| elem.expect("every position is taken"))
+            .collect()
+    }
+}
+
+impl<T> Default for Slab<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_insert_remove() {
+        let mut slab = Slab::new();
+        let a = slab.insert(String::from("a"));
+        let b = slab.insert(String::from("b"));
+        assert_eq!(slab.generation(a), Some(0));
+        slab.get_mut(b).unwrap().front = a;
+        assert_eq!(slab.get(b).map(|node| node.front), Some(a));
+        assert_eq!(slab.get(b).map(|node| &node.elem[..]), Some("b"));
+
+        assert_eq!(slab.remove(a), Some(String::from("a")));
+        assert_eq!(slab.remove(a), None);
+        assert!(slab.get(a).is_none());
+        assert_eq!(slab.generation(a), None);
+        assert_eq!(slab.generation(7), None);
+
+        // Free slots get reused, with the next generation
+        let c = slab.insert(String::from("c"));
+        assert_eq!(c, a);
+        assert_eq!(slab.generation(c), Some(1));
+        assert_eq!(slab.get(c).map(|node| node.front), Some(NIL));
+        assert_eq!(slab.slot_count(), 2);
+    }
+
+    #[test]
+    fn test_generation_wraps() {
+        let mut slab = Slab::new();
+        let index = slab.insert(1);
+        slab.generations[index as usize] = Generation::MAX;
+        assert_eq!(slab.remove(index), Some(1));
+        slab.insert(2);
+        assert_eq!(slab.generation(index), Some(0));
+    }
+
+    #[test]
+    fn test_disjoint_mut() {
+        let mut slab = Slab::new();
+        for i in 0..5 {
+            slab.insert(i);
+        }
+        slab.remove(1);
+        let elems = slab.get_disjoint_mut(&[4, 0, 2]);
+        assert_eq!(elems, [&mut 4, &mut 0, &mut 2]);
+        for elem in elems {
+            *elem *= 10;
+        }
+        assert_eq!(slab.get(4).map(|node| node.elem), Some(40));
+        assert_eq!(slab.get(2).map(|node| node.elem), Some(20));
+        assert_eq!(slab.get(3).map(|node| node.elem), Some(3));
+
+        for indices in [&[0, 2, 0][..], &[3, 1], &[9]] {
+            let result = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
+                slab.get_disjoint_mut(indices).len()
+            }));
+            assert!(result.is_err());
+        }
+    }
+}
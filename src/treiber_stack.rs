@@ -0,0 +1,465 @@
+use std::{
+    cell::UnsafeCell,
+    mem::ManuallyDrop,
+    ptr,
+    sync::atomic::{
+        AtomicBool, AtomicPtr,
+        Ordering::{Acquire, Relaxed, Release, SeqCst},
+    },
+};
+
+/*
+    ok_single_linked_list, but many threads can push and pop at once, without locks
+    Same Node, elem and next, the head is an AtomicPtr instead of an Option<Box>
+    push: point the new node at head, swap it in with compare_exchange, if somebody beat us to it, try again
+    pop: read head, swap in head.next, same deal
+
+    The hard part is freeing the nodes
+    A thread that read head might still be about to look at head.next when another one pops it and frees it
+    And if the memory got reused for a new node, the compare_exchange could even succeed when it shouldn't (ABA)
+
+    Hazard pointers, before touching a node a thread publishes "I'm looking at this one", then checks it's still the head
+    Popped nodes aren't freed right away, they're retired, and only freed once no hazard points at them
+    A node can't be freed, so it can't be reused while anyone looks at it, so no ABA either
+
+    peek_with only works for T: Copy
+    A pop moves elem out while a peeker may still be reading it, and doesn't wait for anyone to finish
+    For a Copy type moving out is just reading the bytes, nothing gets dropped or written,
+    so both reads are fine, and the hazard keeps the memory around until the peeker is done
+    Anything else would need pop to wait for the peekers, and then the stack wouldn't be lock-free anymore
+
+    Everything on head and the hazards is SeqCst, hazard pointers need the store to the hazard and the re-read of head
+    to be ordered against the popper's compare_exchange and its scan of the hazards, SeqCst is the simple way to get that
+*/
+
+struct Node<T> {
+    // Moved out by pop, the node itself is freed later, without dropping it again
+    elem: ManuallyDrop<T>,
+    // Set before the node is pushed, never changes after, so poppers can read it without any sync
+    next: *mut Node<T>,
+}
+
+// One per thread in the middle of an operation, reused after, never freed until the stack is
+struct Slot<T> {
+    active: AtomicBool,
+    // Node a pop is about to read next from, or peek_with is reading elem from
+    hazard: AtomicPtr<Node<T>>,
+    // Popped, waiting to be freed, only touched by whoever has the slot active
+    retired: UnsafeCell<Vec<*mut Node<T>>>,
+    // Slots are only ever added at the front, so this never changes after
+    next: *mut Slot<T>,
+}
+
+// Scanning the hazards costs a walk over all the slots, so only do it every so often
+const RETIRE_THRESHOLD: usize = 64;
+
+pub struct TreiberStack<T> {
+    head: AtomicPtr<Node<T>>,
+    slots: AtomicPtr<Slot<T>>,
+}
+
+impl<T> TreiberStack<T> {
+    pub fn new() -> Self {
+        TreiberStack {
+            head: AtomicPtr::new(ptr::null_mut()),
+            slots: AtomicPtr::new(ptr::null_mut()),
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.head.load(SeqCst).is_null()
+    }
+
+    pub fn push(&self, elem: T) {
+        let node = Box::into_raw(Box::new(Node {
+            elem: ManuallyDrop::new(elem),
+            next: ptr::null_mut(),
+        }));
+        // Never looks inside head, so no hazard needed
+        let mut head = self.head.load(Relaxed);
+        loop {
+            // Nobody else can see the node yet
+            unsafe { (*node).next = head };
+            match self.head.compare_exchange_weak(head, node, SeqCst, Relaxed) {
+                Ok(_) => return,
+                Err(current) => head = current,
+            }
+        }
+    }
+
+    pub fn pop(&self) -> Option<T> {
+        let slot = self.slot();
+        loop {
+            let head = self.protect(&slot.slot.hazard)?;
+            // SAFETY: protected by our hazard, can't be freed until we clear it
+            let next = unsafe { (*head).next };
+            if self
+                .head
+                .compare_exchange(head, next, SeqCst, SeqCst)
+                .is_ok()
+            {
+                slot.slot.hazard.store(ptr::null_mut(), SeqCst);
+                // SAFETY: it's out of the stack and we're the only one who got it
+                // Other poppers may still read next, and peekers elem, so only read, never write
+                let elem = unsafe { ManuallyDrop::into_inner(ptr::read(&(*head).elem)) };
+                slot.retire(head);
+                return Some(elem);
+            }
+        }
+    }
+
+    // Look at the top without taking it off
+    // Never holds up a pop, the element may well be popped while f is still looking at it
+    pub fn peek_with<R>(&self, f: impl FnOnce(&T) -> R) -> Option<R>
+    where
+        T: Copy,
+    {
+        let slot = self.slot();
+        let head = self.protect(&slot.slot.hazard)?;
+        // SAFETY: protected, so not freed until slot clears the hazard when dropped, even if f panics
+        // A pop only ever reads elem, T: Copy, so nobody writes to it meanwhile
+        Some(f(unsafe { &(*head).elem }))
+    }
+
+    // Publish head in hazard and check it's still head, so it's safe to touch until hazard is cleared
+    // None if the stack is empty
+    fn protect(&self, hazard: &AtomicPtr<Node<T>>) -> Option<*mut Node<T>> {
+        let mut head = self.head.load(SeqCst);
+        loop {
+            if head.is_null() {
+                hazard.store(ptr::null_mut(), SeqCst);
+                return None;
+            }
+            hazard.store(head, SeqCst);
+            // Still head after the hazard went up, so anyone popping it from now on will see the hazard
+            let current = self.head.load(SeqCst);
+            if current == head {
+                return Some(head);
+            }
+            head = current;
+        }
+    }
+
+    fn iter_slots(&self) -> impl Iterator<Item = &Slot<T>> {
+        let mut cur = self.slots.load(Acquire);
+        std::iter::from_fn(move || {
+            // SAFETY: slots are only freed in Drop, which has us exclusively
+            let slot = unsafe { cur.as_ref()? };
+            cur = slot.next;
+            Some(slot)
+        })
+    }
+
+    // A free slot, or a new one if all of them are in use
+    fn slot(&self) -> SlotGuard<'_, T> {
+        for slot in self.iter_slots() {
+            if slot
+                .active
+                .compare_exchange(false, true, Acquire, Relaxed)
+                .is_ok()
+            {
+                return SlotGuard { stack: self, slot };
+            }
+        }
+        let slot = Box::into_raw(Box::new(Slot {
+            active: AtomicBool::new(true),
+            hazard: AtomicPtr::new(ptr::null_mut()),
+            retired: UnsafeCell::new(Vec::new()),
+            next: ptr::null_mut(),
+        }));
+        let mut head = self.slots.load(Relaxed);
+        loop {
+            // Not shared yet
+            unsafe { (*slot).next = head };
+            match self
+                .slots
+                .compare_exchange_weak(head, slot, Release, Relaxed)
+            {
+                Ok(_) => break,
+                Err(current) => head = current,
+            }
+        }
+        SlotGuard {
+            stack: self,
+            // SAFETY: lives until the stack is dropped, and we borrow the stack
+            slot: unsafe { &*slot },
+        }
+    }
+}
+
+// Holds the slot active, clears its hazards and gives it back when done
+struct SlotGuard<'a, T> {
+    stack: &'a TreiberStack<T>,
+    slot: &'a Slot<T>,
+}
+
+impl<T> SlotGuard<'_, T> {
+    fn retire(&self, node: *mut Node<T>) {
+        // SAFETY: we have the slot active, nobody else touches retired meanwhile
+        let retired = unsafe { &mut *self.slot.retired.get() };
+        retired.push(node);
+        if retired.len() < RETIRE_THRESHOLD {
+            return;
+        }
+        let hazards: Vec<_> = self
+            .stack
+            .iter_slots()
+            .map(|slot| slot.hazard.load(SeqCst))
+            .filter(|hazard| !hazard.is_null())
+            .collect();
+        retired.retain(|&node| {
+            if hazards.contains(&node) {
+                return true;
+            }
+            // SAFETY: out of the stack, and nobody has a hazard on it, so nobody can get to it anymore
+            // elem was already moved out, ManuallyDrop keeps it from being dropped twice
+            drop(unsafe { Box::from_raw(node) });
+            false
+        });
+    }
+}
+
+impl<T> Drop for SlotGuard<'_, T> {
+    fn drop(&mut self) {
+        self.slot.hazard.store(ptr::null_mut(), SeqCst);
+        self.slot.active.store(false, Release);
+    }
+}
+
+impl<T> Default for TreiberStack<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T> Drop for TreiberStack<T> {
+    fn drop(&mut self) {
+        // &mut self, no other thread is in here, everything is ours to free
+        let mut cur = *self.head.get_mut();
+        while !cur.is_null() {
+            let mut node = unsafe { Box::from_raw(cur) };
+            cur = node.next;
+            // Still in the stack, so elem wasn't popped, drop it
+            unsafe { ManuallyDrop::drop(&mut node.elem) };
+        }
+        let mut cur = *self.slots.get_mut();
+        while !cur.is_null() {
+            let slot = unsafe { Box::from_raw(cur) };
+            cur = slot.next;
+            for node in slot.retired.into_inner() {
+                // Popped, elem is gone already, just the node
+                drop(unsafe { Box::from_raw(node) });
+            }
+        }
+    }
+}
+
+// Raw pointers make it neither by default
+// Popping moves a T to whichever thread popped it, so T: Send
+// peek_with hands out &T to any thread, so sharing the stack needs T: Sync too
+unsafe impl<T: Send> Send for TreiberStack<T> {}
+unsafe impl<T: Send + Sync> Sync for TreiberStack<T> {}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use std::sync::atomic::AtomicUsize;
+    use std::thread;
+
+    // Counts how many times it was dropped
+    struct Counted<'a>(&'a AtomicUsize);
+
+    impl Drop for Counted<'_> {
+        fn drop(&mut self) {
+            self.0.fetch_add(1, Relaxed);
+        }
+    }
+
+    fn retired<T>(stack: &TreiberStack<T>) -> usize {
+        stack
+            .iter_slots()
+            .map(|slot| unsafe { (*slot.retired.get()).len() })
+            .sum()
+    }
+
+    #[test]
+    fn basics() {
+        let stack = TreiberStack::new();
+        assert!(stack.is_empty());
+        assert_eq!(stack.pop(), None);
+        assert_eq!(stack.peek_with(|elem: &i32| *elem), None);
+
+        stack.push(1);
+        stack.push(2);
+        stack.push(3);
+        assert_eq!(stack.peek_with(|elem| *elem), Some(3));
+        assert_eq!(stack.pop(), Some(3));
+        assert_eq!(stack.pop(), Some(2));
+
+        stack.push(4);
+        assert_eq!(stack.pop(), Some(4));
+        assert_eq!(stack.pop(), Some(1));
+        assert_eq!(stack.pop(), None);
+        assert!(stack.is_empty());
+    }
+
+    #[test]
+    fn drops() {
+        let drops = AtomicUsize::new(0);
+        let stack = TreiberStack::new();
+        for _ in 0..200 {
+            stack.push(Counted(&drops));
+        }
+        for _ in 0..150 {
+            drop(stack.pop());
+        }
+        assert_eq!(drops.load(Relaxed), 150);
+        // Nodes get freed as we go, not all kept until the end
+        assert!(retired(&stack) < RETIRE_THRESHOLD);
+
+        // The rest are dropped with the stack, each exactly once
+        drop(stack);
+        assert_eq!(drops.load(Relaxed), 200);
+    }
+
+    #[test]
+    fn peek_panic() {
+        let stack = TreiberStack::new();
+        stack.push(1);
+        let result = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
+            stack.peek_with(|_| panic!("boom"));
+        }));
+        assert!(result.is_err());
+        // The hazard went away with the panic, otherwise the node could never be freed
+        assert!(
+            stack
+                .iter_slots()
+                .all(|slot| slot.hazard.load(SeqCst).is_null())
+        );
+        assert_eq!(stack.pop(), Some(1));
+    }
+
+    #[test]
+    fn pop_during_peek() {
+        // The popper gets its element while the peek of that same element is still going on
+        // If pop waited for peekers, this would never finish
+        let stack = TreiberStack::new();
+        stack.push(1);
+        stack.push(2);
+        let popped = std::sync::mpsc::channel();
+        thread::scope(|s| {
+            let (stack, (tx, rx)) = (&stack, popped);
+            let peeker = s.spawn(move || {
+                stack.peek_with(|elem| {
+                    let popped = rx.recv().unwrap();
+                    // Still readable, the hazard keeps the node around
+                    (*elem, popped)
+                })
+            });
+            // Wait until the peek has its hazard up on the node
+            while stack
+                .iter_slots()
+                .all(|slot| slot.hazard.load(SeqCst).is_null())
+            {
+                thread::yield_now();
+            }
+            tx.send(stack.pop()).unwrap();
+            assert_eq!(peeker.join().unwrap(), Some((2, Some(2))));
+        });
+
+        // And from inside f on the same thread too
+        let peeked = stack.peek_with(|&elem| (elem, stack.pop()));
+        assert_eq!(peeked, Some((1, Some(1))));
+        assert!(stack.is_empty());
+    }
+
+    #[test]
+    fn stress() {
+        // Every thread pushes its own numbers and pops whatever it gets
+        // Nothing lost, nothing popped twice
+        const THREADS: usize = 8;
+        const PER_THREAD: usize = 5000;
+        let stack = TreiberStack::new();
+        let mut popped: Vec<usize> = thread::scope(|s| {
+            let handles: Vec<_> = (0..THREADS)
+                .map(|t| {
+                    let stack = &stack;
+                    s.spawn(move || {
+                        let mut got = Vec::new();
+                        for i in 0..PER_THREAD {
+                            stack.push(Box::new(t * PER_THREAD + i));
+                            if i % 3 != 0 {
+                                got.extend(stack.pop().map(|elem| *elem));
+                            }
+                        }
+                        got
+                    })
+                })
+                .collect();
+            handles
+                .into_iter()
+                .flat_map(|h| h.join().unwrap())
+                .collect()
+        });
+        while let Some(elem) = stack.pop() {
+            popped.push(*elem);
+        }
+        popped.sort();
+        assert_eq!(popped, (0..THREADS * PER_THREAD).collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn stress_peek() {
+        // Peekers read elements that poppers are busy taking off and freeing
+        // If a node got freed while somebody was still reading it, this reads freed memory
+        const TOTAL: usize = 20_000;
+        let stack = TreiberStack::new();
+        let drops = AtomicUsize::new(0);
+        let done = AtomicBool::new(false);
+        thread::scope(|s| {
+            for _ in 0..2 {
+                s.spawn(|| {
+                    while !done.load(Relaxed) {
+                        stack.peek_with(|elem: &[usize; 4]| {
+                            assert_eq!(elem.len(), 4);
+                            assert!(elem.iter().all(|&x| x == elem[0]));
+                        });
+                    }
+                });
+            }
+            let workers: Vec<_> = (0..4)
+                .map(|t| {
+                    let (stack, drops) = (&stack, &drops);
+                    s.spawn(move || {
+                        for i in 0..TOTAL / 4 {
+                            stack.push([t * TOTAL + i; 4]);
+                            if stack.pop().is_some() {
+                                drops.fetch_add(1, Relaxed);
+                            }
+                        }
+                    })
+                })
+                .collect();
+            for worker in workers {
+                worker.join().unwrap();
+            }
+            done.store(true, Relaxed);
+        });
+        while stack.pop().is_some() {
+            drops.fetch_add(1, Relaxed);
+        }
+        assert_eq!(drops.load(Relaxed), TOTAL);
+    }
+
+    #[allow(dead_code)]
+    fn assert_properties() {
+        fn is_send<T: Send>() {}
+        fn is_sync<T: Sync>() {}
+
+        is_send::<TreiberStack<i32>>();
+        is_sync::<TreiberStack<i32>>();
+        is_send::<TreiberStack<std::cell::Cell<i32>>>();
+        // Two threads peeking the same Cell would race
+        // is_sync::<TreiberStack<std::cell::Cell<i32>>>();
+    }
+}
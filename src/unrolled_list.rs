@@ -0,0 +1,1273 @@
+use std::{
+    cmp::Ordering,
+    fmt::{self, Debug},
+    hash::{Hash, Hasher},
+    iter::FusedIterator,
+    marker::PhantomData,
+    mem::MaybeUninit,
+    ptr::{self, NonNull},
+};
+
+/*
+    Unrolled linked list
+    Same thing as the production deque, except every node holds up to N elements instead of one
+    Walking it is mostly walking small arrays, so a lot less pointer chasing and cache misses
+
+    Invariants:
+    - no node is ever empty
+    - a node's elements are packed at the start of its array, elems[..len] are initialized
+    Inserting into a full node splits it in half first
+    Removing from a node that ends up less than half full merges it with a neighbour, if they fit together
+*/
+
+pub struct UnrolledList<T, const N: usize> {
+    front: Link<T, N>,
+    back: Link<T, N>,
+    // Elements, not nodes
+    len: usize,
+    _boo: PhantomData<T>,
+}
+
+type Link<T, const N: usize> = Option<NonNull<Node<T, N>>>;
+
+// Where an element lives, its node and the offset inside of it
+type Pos<T, const N: usize> = (NonNull<Node<T, N>>, usize);
+
+struct Node<T, const N: usize> {
+    front: Link<T, N>,
+    back: Link<T, N>,
+    len: usize,
+    // MaybeUninit never drops anything, we drop elems[..len] by hand
+    elems: [MaybeUninit<T>; N],
+}
+
+// Fresh empty node, not linked to anything
+fn new_node<T, const N: usize>() -> NonNull<Node<T, N>> {
+    unsafe {
+        NonNull::new_unchecked(Box::into_raw(Box::new(Node {
+            front: None,
+            back: None,
+            len: 0,
+            elems: [const { MaybeUninit::uninit() }; N],
+        })))
+    }
+}
+
+// The node has to be empty already (or its elements moved out), this only frees the memory
+unsafe fn free_node<T, const N: usize>(node: NonNull<Node<T, N>>) {
+    unsafe { drop(Box::from_raw(node.as_ptr())) };
+}
+
+// Pointer to the i-th slot of a node
+// Never makes a reference to the whole node, iterators may be holding references into it
+unsafe fn slot<T, const N: usize>(node: NonNull<Node<T, N>>, i: usize) -> *mut T {
+    unsafe { (&raw mut (*node.as_ptr()).elems).cast::<T>().add(i) }
+}
+
+// Put elem at pos, everything from pos moves one slot to the back
+// Node can't be full
+unsafe fn insert_in<T, const N: usize>(node: NonNull<Node<T, N>>, pos: usize, elem: T) {
+    unsafe {
+        let len = (*node.as_ptr()).len;
+        debug_assert!(len < N && pos <= len);
+        ptr::copy(slot(node, pos), slot(node, pos + 1), len - pos);
+        slot(node, pos).write(elem);
+        (*node.as_ptr()).len += 1;
+    }
+}
+
+// Take the element at pos out, everything after it moves one slot to the front
+unsafe fn remove_in<T, const N: usize>(node: NonNull<Node<T, N>>, pos: usize) -> T {
+    unsafe {
+        let len = (*node.as_ptr()).len;
+        debug_assert!(pos < len);
+        let elem = slot(node, pos).read();
+        ptr::copy(slot(node, pos + 1), slot(node, pos), len - pos - 1);
+        (*node.as_ptr()).len -= 1;
+        elem
+    }
+}
+
+// Position of the element after/before pos, possibly in the neighbouring node
+unsafe fn next_pos<T, const N: usize>((node, off): Pos<T, N>) -> Option<Pos<T, N>> {
+    unsafe {
+        if off + 1 < (*node.as_ptr()).len {
+            Some((node, off + 1))
+        } else {
+            (*node.as_ptr()).back.map(|next| (next, 0))
+        }
+    }
+}
+
+unsafe fn prev_pos<T, const N: usize>((node, off): Pos<T, N>) -> Option<Pos<T, N>> {
+    unsafe {
+        if off > 0 {
+            Some((node, off - 1))
+        } else {
+            (*node.as_ptr())
+                .front
+                .map(|prev| (prev, (*prev.as_ptr()).len - 1))
+        }
+    }
+}
+
+impl<T, const N: usize> UnrolledList<T, N> {
+    pub fn new() -> Self {
+        // A node of 1 can't be split in half
+        const {
+            assert!(
+                N >= 2,
+                "UnrolledList needs room for at least 2 elements per node"
+            )
+        };
+        UnrolledList {
+            front: None,
+            back: None,
+            len: 0,
+            _boo: PhantomData,
+        }
+    }
+
+    pub fn push_front(&mut self, elem: T) {
+        unsafe {
+            let node = match self.front {
+                Some(front) if (*front.as_ptr()).len < N => front,
+                // Front node is full, or there is none, start a new one
+                _ => {
+                    let new = new_node();
+                    self.link_node_after(None, new);
+                    new
+                }
+            };
+            insert_in(node, 0, elem);
+            self.len += 1;
+        }
+    }
+
+    pub fn push_back(&mut self, elem: T) {
+        unsafe {
+            let node = match self.back {
+                Some(back) if (*back.as_ptr()).len < N => back,
+                // No splitting here, so pushing a lot keeps every node packed
+                _ => {
+                    let new = new_node();
+                    self.link_node_after(self.back, new);
+                    new
+                }
+            };
+            insert_in(node, (*node.as_ptr()).len, elem);
+            self.len += 1;
+        }
+    }
+
+    pub fn pop_front(&mut self) -> Option<T> {
+        self.front
+            .map(|front| unsafe { self.remove_at(front, 0).0 })
+    }
+
+    pub fn pop_back(&mut self) -> Option<T> {
+        self.back.map(|back| unsafe {
+            let last = (*back.as_ptr()).len - 1;
+            self.remove_at(back, last).0
+        })
+    }
+
+    pub fn front(&self) -> Option<&T> {
+        unsafe { self.front.map(|node| &*slot(node, 0)) }
+    }
+
+    pub fn front_mut(&mut self) -> Option<&mut T> {
+        unsafe { self.front.map(|node| &mut *slot(node, 0)) }
+    }
+
+    pub fn back(&self) -> Option<&T> {
+        unsafe { self.back.map(|node| &*slot(node, (*node.as_ptr()).len - 1)) }
+    }
+
+    pub fn back_mut(&mut self) -> Option<&mut T> {
+        unsafe {
+            self.back
+                .map(|node| &mut *slot(node, (*node.as_ptr()).len - 1))
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    pub fn clear(&mut self) {
+        *self = Self::new();
+    }
+
+    pub fn iter(&self) -> Iter<'_, T, N> {
+        unsafe {
+            Iter {
+                front: self.front.map(|node| (node, 0)),
+                back: self.last_pos(),
+                len: self.len,
+                _boo: PhantomData,
+            }
+        }
+    }
+
+    pub fn iter_mut(&mut self) -> IterMut<'_, T, N> {
+        unsafe {
+            IterMut {
+                front: self.front.map(|node| (node, 0)),
+                back: self.last_pos(),
+                len: self.len,
+                _boo: PhantomData,
+            }
+        }
+    }
+
+    // Moves all of other's nodes to our back, O(1)
+    pub fn append(&mut self, other: &mut Self) {
+        unsafe { self.splice_after_node(self.back, other) };
+    }
+
+    // We keep [0, at), the output gets [at, len)
+    pub fn split_off(&mut self, at: usize) -> Self {
+        let len = self.len;
+        assert!(
+            at <= len,
+            "cannot split off at a nonexistent index (is {at}, len {len})"
+        );
+
+        if at == 0 {
+            return std::mem::take(self);
+        }
+        if at == len {
+            return Self::new();
+        }
+
+        unsafe {
+            // Element at has to start a node, cut its node in two if it's in the middle
+            let (node, off) = self.locate(at);
+            let node = if off > 0 {
+                self.split_node(node, off)
+            } else {
+                node
+            };
+            // at > 0, so there is a node before this one
+            let prev = (*node.as_ptr()).front.unwrap();
+            self.split_after_node(prev, at)
+        }
+    }
+}
+
+// Plumbing, all of it trusts the caller that the nodes belong to self
+impl<T, const N: usize> UnrolledList<T, N> {
+    unsafe fn last_pos(&self) -> Option<Pos<T, N>> {
+        unsafe { self.back.map(|node| (node, (*node.as_ptr()).len - 1)) }
+    }
+
+    // Node and offset of the at-th element, walks from whichever end is closer
+    // Skips over whole nodes, so it's O(at / N) instead of O(at)
+    unsafe fn locate(&self, at: usize) -> Pos<T, N> {
+        debug_assert!(at < self.len);
+        unsafe {
+            if at < self.len / 2 {
+                let mut at = at;
+                let mut node = self.front.unwrap();
+                while at >= (*node.as_ptr()).len {
+                    at -= (*node.as_ptr()).len;
+                    node = (*node.as_ptr()).back.unwrap();
+                }
+                (node, at)
+            } else {
+                let mut from_back = self.len - 1 - at;
+                let mut node = self.back.unwrap();
+                while from_back >= (*node.as_ptr()).len {
+                    from_back -= (*node.as_ptr()).len;
+                    node = (*node.as_ptr()).front.unwrap();
+                }
+                (node, (*node.as_ptr()).len - 1 - from_back)
+            }
+        }
+    }
+
+    // Put a dangling node right after prev, or at the front if there's no prev
+    unsafe fn link_node_after(&mut self, prev: Link<T, N>, node: NonNull<Node<T, N>>) {
+        unsafe {
+            let next = match prev {
+                Some(prev) => (*prev.as_ptr()).back,
+                None => self.front,
+            };
+            (*node.as_ptr()).front = prev;
+            (*node.as_ptr()).back = next;
+            match prev {
+                Some(prev) => (*prev.as_ptr()).back = Some(node),
+                None => self.front = Some(node),
+            }
+            match next {
+                Some(next) => (*next.as_ptr()).front = Some(node),
+                None => self.back = Some(node),
+            }
+        }
+    }
+
+    // Cut the node out, its elements are the caller's problem
+    unsafe fn unlink_node(&mut self, node: NonNull<Node<T, N>>) {
+        unsafe {
+            let prev = (*node.as_ptr()).front.take();
+            let next = (*node.as_ptr()).back.take();
+            match prev {
+                Some(prev) => (*prev.as_ptr()).back = next,
+                None => self.front = next,
+            }
+            match next {
+                Some(next) => (*next.as_ptr()).front = prev,
+                None => self.back = prev,
+            }
+        }
+    }
+
+    // Elements from at onwards move into a new node right after this one
+    unsafe fn split_node(&mut self, node: NonNull<Node<T, N>>, at: usize) -> NonNull<Node<T, N>> {
+        unsafe {
+            let len = (*node.as_ptr()).len;
+            debug_assert!(at > 0 && at < len);
+            let new = new_node();
+            ptr::copy_nonoverlapping(slot(node, at), slot(new, 0), len - at);
+            (*new.as_ptr()).len = len - at;
+            (*node.as_ptr()).len = at;
+            self.link_node_after(Some(node), new);
+            new
+        }
+    }
+
+    // All of second's elements move to the back of first, and second goes away
+    // Returns the offset in first where second's elements start now
+    unsafe fn merge(&mut self, first: NonNull<Node<T, N>>, second: NonNull<Node<T, N>>) -> usize {
+        unsafe {
+            let at = (*first.as_ptr()).len;
+            let count = (*second.as_ptr()).len;
+            debug_assert!(at + count <= N);
+            ptr::copy_nonoverlapping(slot(second, 0), slot(first, at), count);
+            (*first.as_ptr()).len += count;
+            (*second.as_ptr()).len = 0;
+            self.unlink_node(second);
+            free_node(second);
+            at
+        }
+    }
+
+    // Put elem at pos in node, pos can be one past its last element too
+    // A full node gets split in half first
+    // Returns where elem ended up
+    unsafe fn insert_at(&mut self, node: NonNull<Node<T, N>>, pos: usize, elem: T) -> Pos<T, N> {
+        unsafe {
+            let (node, pos) = if (*node.as_ptr()).len == N {
+                let new = self.split_node(node, N / 2);
+                if pos <= N / 2 {
+                    (node, pos)
+                } else {
+                    (new, pos - N / 2)
+                }
+            } else {
+                (node, pos)
+            };
+            insert_in(node, pos, elem);
+            self.len += 1;
+            (node, pos)
+        }
+    }
+
+    // Take out the element at off, then tidy up the node
+    // Returns the element, and where the element that came after it lives now
+    unsafe fn remove_at(
+        &mut self,
+        node: NonNull<Node<T, N>>,
+        off: usize,
+    ) -> (T, Option<Pos<T, N>>) {
+        unsafe {
+            let elem = remove_in(node, off);
+            self.len -= 1;
+
+            let len = (*node.as_ptr()).len;
+            let next = (*node.as_ptr()).back;
+            let mut follower = if off < len {
+                Some((node, off))
+            } else {
+                next.map(|next| (next, 0))
+            };
+
+            if len == 0 {
+                self.unlink_node(node);
+                free_node(node);
+            } else if len < N / 2 {
+                // Less than half full, try to team up with a neighbour
+                let prev = (*node.as_ptr()).front;
+                if let Some(next) = next
+                    && len + (*next.as_ptr()).len <= N
+                {
+                    let at = self.merge(node, next);
+                    if let Some((n, k)) = follower
+                        && n == next
+                    {
+                        follower = Some((node, at + k));
+                    }
+                } else if let Some(prev) = prev
+                    && (*prev.as_ptr()).len + len <= N
+                {
+                    let at = self.merge(prev, node);
+                    if let Some((n, k)) = follower
+                        && n == node
+                    {
+                        follower = Some((prev, at + k));
+                    }
+                }
+            }
+            (elem, follower)
+        }
+    }
+
+    // Everything after node becomes its own list
+    // count is how many elements we keep, up to and including node
+    unsafe fn split_after_node(&mut self, node: NonNull<Node<T, N>>, count: usize) -> Self {
+        unsafe {
+            let mut output = Self::new();
+            if let Some(next) = (*node.as_ptr()).back.take() {
+                (*next.as_ptr()).front = None;
+                output.front = Some(next);
+                output.back = self.back;
+                output.len = self.len - count;
+            }
+            self.back = Some(node);
+            self.len = count;
+            output
+        }
+    }
+
+    // Everything before node becomes its own list
+    // count is how many elements are in front of node
+    unsafe fn split_before_node(&mut self, node: NonNull<Node<T, N>>, count: usize) -> Self {
+        unsafe {
+            let mut output = Self::new();
+            if let Some(prev) = (*node.as_ptr()).front.take() {
+                (*prev.as_ptr()).back = None;
+                output.front = self.front;
+                output.back = Some(prev);
+                output.len = count;
+            }
+            self.front = Some(node);
+            self.len -= count;
+            output
+        }
+    }
+
+    // All of other's nodes go right after prev, or to the front if there's no prev
+    unsafe fn splice_after_node(&mut self, prev: Link<T, N>, other: &mut Self) {
+        let (Some(in_front), Some(in_back)) = (other.front.take(), other.back.take()) else {
+            return;
+        };
+        unsafe {
+            let next = match prev {
+                Some(prev) => (*prev.as_ptr()).back,
+                None => self.front,
+            };
+            (*in_front.as_ptr()).front = prev;
+            (*in_back.as_ptr()).back = next;
+            match prev {
+                Some(prev) => (*prev.as_ptr()).back = Some(in_front),
+                None => self.front = Some(in_front),
+            }
+            match next {
+                Some(next) => (*next.as_ptr()).front = Some(in_back),
+                None => self.back = Some(in_back),
+            }
+        }
+        self.len += std::mem::take(&mut other.len);
+    }
+}
+
+impl<T, const N: usize> Drop for UnrolledList<T, N> {
+    fn drop(&mut self) {
+        while let Some(node) = self.front {
+            unsafe {
+                self.front = (*node.as_ptr()).back;
+                let len = (*node.as_ptr()).len;
+                ptr::drop_in_place(ptr::slice_from_raw_parts_mut(slot(node, 0), len));
+                free_node(node);
+            }
+        }
+    }
+}
+
+pub struct Iter<'a, T, const N: usize> {
+    front: Option<Pos<T, N>>,
+    back: Option<Pos<T, N>>,
+    len: usize,
+    _boo: PhantomData<&'a T>,
+}
+
+impl<'a, T, const N: usize> IntoIterator for &'a UnrolledList<T, N> {
+    type IntoIter = Iter<'a, T, N>;
+    type Item = &'a T;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+impl<'a, T, const N: usize> Iterator for Iter<'a, T, N> {
+    type Item = &'a T;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        // len says when front and back met, same as in the deque
+        if self.len > 0 {
+            self.front.map(|pos| unsafe {
+                self.len -= 1;
+                self.front = next_pos(pos);
+                &*slot(pos.0, pos.1)
+            })
+        } else {
+            None
+        }
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.len, Some(self.len))
+    }
+}
+
+impl<T, const N: usize> DoubleEndedIterator for Iter<'_, T, N> {
+    fn next_back(&mut self) -> Option<Self::Item> {
+        if self.len > 0 {
+            self.back.map(|pos| unsafe {
+                self.len -= 1;
+                self.back = prev_pos(pos);
+                &*slot(pos.0, pos.1)
+            })
+        } else {
+            None
+        }
+    }
+}
+
+impl<T, const N: usize> ExactSizeIterator for Iter<'_, T, N> {}
+
+impl<T, const N: usize> FusedIterator for Iter<'_, T, N> {}
+
+impl<T, const N: usize> Clone for Iter<'_, T, N> {
+    fn clone(&self) -> Self {
+        Iter { ..*self }
+    }
+}
+
+pub struct IterMut<'a, T, const N: usize> {
+    front: Option<Pos<T, N>>,
+    back: Option<Pos<T, N>>,
+    len: usize,
+    _boo: PhantomData<&'a mut T>,
+}
+
+impl<'a, T, const N: usize> IntoIterator for &'a mut UnrolledList<T, N> {
+    type IntoIter = IterMut<'a, T, N>;
+    type Item = &'a mut T;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter_mut()
+    }
+}
+
+impl<'a, T, const N: usize> Iterator for IterMut<'a, T, N> {
+    type Item = &'a mut T;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.len > 0 {
+            self.front.map(|pos| unsafe {
+                self.len -= 1;
+                self.front = next_pos(pos);
+                &mut *slot(pos.0, pos.1)
+            })
+        } else {
+            None
+        }
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.len, Some(self.len))
+    }
+}
+
+impl<T, const N: usize> DoubleEndedIterator for IterMut<'_, T, N> {
+    fn next_back(&mut self) -> Option<Self::Item> {
+        if self.len > 0 {
+            self.back.map(|pos| unsafe {
+                self.len -= 1;
+                self.back = prev_pos(pos);
+                &mut *slot(pos.0, pos.1)
+            })
+        } else {
+            None
+        }
+    }
+}
+
+impl<T, const N: usize> ExactSizeIterator for IterMut<'_, T, N> {}
+
+impl<T, const N: usize> FusedIterator for IterMut<'_, T, N> {}
+
+pub struct IntoIter<T, const N: usize> {
+    list: UnrolledList<T, N>,
+}
+
+impl<T, const N: usize> IntoIterator for UnrolledList<T, N> {
+    type IntoIter = IntoIter<T, N>;
+    type Item = T;
+
+    fn into_iter(self) -> Self::IntoIter {
+        IntoIter { list: self }
+    }
+}
+
+impl<T, const N: usize> Iterator for IntoIter<T, N> {
+    type Item = T;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.list.pop_front()
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.list.len, Some(self.list.len))
+    }
+}
+
+impl<T, const N: usize> DoubleEndedIterator for IntoIter<T, N> {
+    fn next_back(&mut self) -> Option<Self::Item> {
+        self.list.pop_back()
+    }
+}
+
+impl<T, const N: usize> ExactSizeIterator for IntoIter<T, N> {}
+
+impl<T, const N: usize> FusedIterator for IntoIter<T, N> {}
+
+// Same ghost element semantics as the deque's CursorMut
+// The ghost sits between back and front, and has no index
+pub struct CursorMut<'a, T, const N: usize> {
+    cur: Option<Pos<T, N>>,
+    list: &'a mut UnrolledList<T, N>,
+    index: Option<usize>,
+}
+
+impl<T, const N: usize> UnrolledList<T, N> {
+    pub fn cursor_mut(&mut self) -> CursorMut<'_, T, N> {
+        CursorMut {
+            cur: None,
+            list: self,
+            index: None,
+        }
+    }
+}
+
+impl<T, const N: usize> CursorMut<'_, T, N> {
+    pub fn index(&self) -> Option<usize> {
+        self.index
+    }
+
+    pub fn move_next(&mut self) {
+        unsafe {
+            match self.cur {
+                Some(pos) => {
+                    self.cur = next_pos(pos);
+                    self.index = self.cur.and(self.index.map(|index| index + 1));
+                }
+                None => {
+                    // Ghost goes to the front
+                    self.cur = self.list.front.map(|node| (node, 0));
+                    self.index = self.cur.and(Some(0));
+                }
+            }
+        }
+    }
+
+    pub fn move_prev(&mut self) {
+        unsafe {
+            match self.cur {
+                Some(pos) => {
+                    self.cur = prev_pos(pos);
+                    self.index = self.cur.and(self.index.map(|index| index - 1));
+                }
+                None => {
+                    // Ghost goes to the back
+                    self.cur = self.list.last_pos();
+                    self.index = self.cur.and(Some(self.list.len.wrapping_sub(1)));
+                }
+            }
+        }
+    }
+
+    pub fn current(&mut self) -> Option<&mut T> {
+        unsafe { self.cur.map(|(node, off)| &mut *slot(node, off)) }
+    }
+
+    pub fn peek_next(&mut self) -> Option<&mut T> {
+        unsafe {
+            let next = match self.cur {
+                Some(pos) => next_pos(pos),
+                None => self.list.front.map(|node| (node, 0)),
+            };
+            next.map(|(node, off)| &mut *slot(node, off))
+        }
+    }
+
+    pub fn peek_prev(&mut self) -> Option<&mut T> {
+        unsafe {
+            let prev = match self.cur {
+                Some(pos) => prev_pos(pos),
+                None => self.list.last_pos(),
+            };
+            prev.map(|(node, off)| &mut *slot(node, off))
+        }
+    }
+
+    pub fn insert_before(&mut self, elem: T) {
+        match self.cur {
+            Some((node, off)) => unsafe {
+                // We might have been moved into a new node by a split, follow the new element
+                let new = self.list.insert_at(node, off, elem);
+                self.cur = next_pos(new);
+                *self.index.as_mut().unwrap() += 1;
+            },
+            // Before the ghost is the back of the list
+            None => self.list.push_back(elem),
+        }
+    }
+
+    pub fn insert_after(&mut self, elem: T) {
+        match self.cur {
+            Some((node, off)) => unsafe {
+                let new = self.list.insert_at(node, off + 1, elem);
+                self.cur = prev_pos(new);
+            },
+            // After the ghost is the front of the list
+            None => self.list.push_front(elem),
+        }
+    }
+
+    // Removes the current element, the cursor moves on to the next one
+    // (or the ghost, if that was the last one)
+    pub fn remove_current(&mut self) -> Option<T> {
+        let (node, off) = self.cur?;
+        let (elem, next) = unsafe { self.list.remove_at(node, off) };
+        self.cur = next;
+        if next.is_none() {
+            self.index = None;
+        }
+        Some(elem)
+    }
+
+    // Everything before the cursor becomes its own list
+    pub fn split_before(&mut self) -> UnrolledList<T, N> {
+        let Some((node, off)) = self.cur else {
+            // Ghost, everything is before us
+            return std::mem::take(self.list);
+        };
+        unsafe {
+            let node = if off > 0 {
+                self.list.split_node(node, off)
+            } else {
+                node
+            };
+            let count = self.index.unwrap();
+            self.cur = Some((node, 0));
+            self.index = Some(0);
+            self.list.split_before_node(node, count)
+        }
+    }
+
+    // Everything after the cursor becomes its own list
+    pub fn split_after(&mut self) -> UnrolledList<T, N> {
+        let Some((node, off)) = self.cur else {
+            return std::mem::take(self.list);
+        };
+        unsafe {
+            if off + 1 < (*node.as_ptr()).len {
+                self.list.split_node(node, off + 1);
+            }
+            self.list.split_after_node(node, self.index.unwrap() + 1)
+        }
+    }
+
+    pub fn splice_before(&mut self, mut input: UnrolledList<T, N>) {
+        if input.is_empty() {
+            return;
+        }
+        unsafe {
+            match self.cur {
+                Some((node, off)) => {
+                    // Nodes go in whole, so we need a node boundary right before us
+                    let node = if off > 0 {
+                        self.list.split_node(node, off)
+                    } else {
+                        node
+                    };
+                    self.cur = Some((node, 0));
+                    *self.index.as_mut().unwrap() += input.len;
+                    self.list
+                        .splice_after_node((*node.as_ptr()).front, &mut input);
+                }
+                None => self.list.splice_after_node(self.list.back, &mut input),
+            }
+        }
+    }
+
+    pub fn splice_after(&mut self, mut input: UnrolledList<T, N>) {
+        if input.is_empty() {
+            return;
+        }
+        unsafe {
+            match self.cur {
+                Some((node, off)) => {
+                    if off + 1 < (*node.as_ptr()).len {
+                        self.list.split_node(node, off + 1);
+                    }
+                    self.list.splice_after_node(Some(node), &mut input);
+                }
+                None => self.list.splice_after_node(None, &mut input),
+            }
+        }
+    }
+}
+
+impl<T, const N: usize> Default for UnrolledList<T, N> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T: Clone, const N: usize> Clone for UnrolledList<T, N> {
+    fn clone(&self) -> Self {
+        self.iter().cloned().collect()
+    }
+}
+
+impl<T, const N: usize> Extend<T> for UnrolledList<T, N> {
+    fn extend<I: IntoIterator<Item = T>>(&mut self, iter: I) {
+        for elem in iter {
+            self.push_back(elem);
+        }
+    }
+}
+
+impl<T, const N: usize> FromIterator<T> for UnrolledList<T, N> {
+    fn from_iter<I: IntoIterator<Item = T>>(iter: I) -> Self {
+        let mut list = Self::new();
+        list.extend(iter);
+        list
+    }
+}
+
+impl<T: Debug, const N: usize> Debug for UnrolledList<T, N> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_list().entries(self).finish()
+    }
+}
+
+impl<T: PartialEq, const N: usize> PartialEq for UnrolledList<T, N> {
+    fn eq(&self, other: &Self) -> bool {
+        self.len == other.len && self.iter().eq(other)
+    }
+}
+
+impl<T: Eq, const N: usize> Eq for UnrolledList<T, N> {}
+
+impl<T: PartialOrd, const N: usize> PartialOrd for UnrolledList<T, N> {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        self.iter().partial_cmp(other)
+    }
+}
+
+impl<T: Ord, const N: usize> Ord for UnrolledList<T, N> {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.iter().cmp(other)
+    }
+}
+
+impl<T: Hash, const N: usize> Hash for UnrolledList<T, N> {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.len.hash(state);
+        for elem in self {
+            elem.hash(state);
+        }
+    }
+}
+
+// Raw pointers opted us out of Send/Sync, but we own our nodes just like the deque does
+unsafe impl<T: Send, const N: usize> Send for UnrolledList<T, N> {}
+unsafe impl<T: Sync, const N: usize> Sync for UnrolledList<T, N> {}
+
+unsafe impl<T: Sync, const N: usize> Send for Iter<'_, T, N> {}
+unsafe impl<T: Sync, const N: usize> Sync for Iter<'_, T, N> {}
+
+unsafe impl<T: Send, const N: usize> Send for IterMut<'_, T, N> {}
+unsafe impl<T: Sync, const N: usize> Sync for IterMut<'_, T, N> {}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use std::rc::Rc;
+
+    // Walks the nodes and checks every invariant, returns how full each node is
+    fn check_nodes<T, const N: usize>(list: &UnrolledList<T, N>) -> Vec<usize> {
+        let mut lens = Vec::new();
+        let mut prev = None;
+        let mut cur = list.front;
+        unsafe {
+            while let Some(node) = cur {
+                assert_eq!((*node.as_ptr()).front, prev);
+                let len = (*node.as_ptr()).len;
+                assert!(len > 0 && len <= N, "node with {len} elements");
+                lens.push(len);
+                prev = cur;
+                cur = (*node.as_ptr()).back;
+            }
+        }
+        assert_eq!(list.back, prev);
+        assert_eq!(lens.iter().sum::<usize>(), list.len());
+        lens
+    }
+
+    fn to_vec<T: Clone, const N: usize>(list: &UnrolledList<T, N>) -> Vec<T> {
+        list.iter().cloned().collect()
+    }
+
+    fn pseudo_random(seed: &mut u64) -> u64 {
+        *seed = seed
+            .wrapping_mul(6364136223846793005)
+            .wrapping_add(1442695040888963407);
+        *seed >> 33
+    }
+
+    #[test]
+    fn test_basic() {
+        let mut list = UnrolledList::<i32, 4>::new();
+        assert_eq!(list.pop_front(), None);
+        assert_eq!(list.pop_back(), None);
+
+        for i in 0..10 {
+            list.push_back(i);
+        }
+        for i in 1..4 {
+            list.push_front(-i);
+        }
+        assert_eq!(list.len(), 13);
+        assert_eq!(list.front(), Some(&-3));
+        assert_eq!(list.back(), Some(&9));
+        // Pushing keeps the nodes packed
+        assert_eq!(check_nodes(&list), &[3, 4, 4, 2]);
+
+        *list.front_mut().unwrap() = -30;
+        *list.back_mut().unwrap() = 90;
+        assert_eq!(list.pop_front(), Some(-30));
+        assert_eq!(list.pop_back(), Some(90));
+        assert_eq!(to_vec(&list), &[-2, -1, 0, 1, 2, 3, 4, 5, 6, 7, 8]);
+
+        while list.pop_back().is_some() {
+            check_nodes(&list);
+        }
+        assert!(list.is_empty());
+        assert_eq!(list.front, None);
+        list.push_front(1);
+        assert_eq!(list.back(), Some(&1));
+    }
+
+    #[test]
+    fn test_iter() {
+        let mut list: UnrolledList<i32, 3> = (0..10).collect();
+        let mut iter = list.iter();
+        assert_eq!(iter.len(), 10);
+        assert_eq!(iter.next(), Some(&0));
+        assert_eq!(iter.next_back(), Some(&9));
+        assert_eq!(iter.len(), 8);
+        assert_eq!(
+            iter.rev().collect::<Vec<_>>(),
+            [&8, &7, &6, &5, &4, &3, &2, &1]
+        );
+
+        for elem in list.iter_mut() {
+            *elem *= 10;
+        }
+        let mut iter = list.iter_mut();
+        assert_eq!(iter.next_back(), Some(&mut 90));
+        assert_eq!(iter.next(), Some(&mut 0));
+
+        let mut iter = list.into_iter();
+        assert_eq!(iter.next_back(), Some(90));
+        assert_eq!(iter.next(), Some(0));
+        assert_eq!(iter.collect::<Vec<_>>(), [10, 20, 30, 40, 50, 60, 70, 80]);
+    }
+
+    #[test]
+    fn test_split_and_merge_nodes() {
+        let mut list: UnrolledList<i32, 4> = (0..8).collect();
+        assert_eq!(check_nodes(&list), &[4, 4]);
+
+        // Inserting into a full node splits it in half
+        let mut cursor = list.cursor_mut();
+        cursor.move_next();
+        cursor.move_next();
+        cursor.insert_before(10);
+        assert_eq!(cursor.current(), Some(&mut 1));
+        assert_eq!(cursor.index(), Some(2));
+        assert_eq!(check_nodes(&list), &[3, 2, 4]);
+        assert_eq!(to_vec(&list), &[0, 10, 1, 2, 3, 4, 5, 6, 7]);
+
+        // Going under half full merges with the next node when it fits
+        let mut cursor = list.cursor_mut();
+        cursor.move_next();
+        assert_eq!(cursor.remove_current(), Some(0));
+        assert_eq!(cursor.remove_current(), Some(10));
+        assert_eq!(cursor.current(), Some(&mut 1));
+        assert_eq!(check_nodes(&list), &[3, 4]);
+
+        // Or with the previous one, when there's no next to merge with
+        let mut list: UnrolledList<i32, 4> = (0..7).collect();
+        assert_eq!(list.pop_front(), Some(0));
+        let mut cursor = list.cursor_mut();
+        cursor.move_prev();
+        assert_eq!(cursor.remove_current(), Some(6));
+        assert_eq!(cursor.index(), None);
+        cursor.move_prev();
+        cursor.move_prev();
+        assert_eq!(cursor.remove_current(), Some(4));
+        assert_eq!(cursor.current(), Some(&mut 5));
+        assert_eq!(cursor.index(), Some(3));
+        assert_eq!(check_nodes(&list), &[4]);
+        assert_eq!(to_vec(&list), &[1, 2, 3, 5]);
+
+        // And stays as it is when neither neighbour has room
+        let mut list: UnrolledList<i32, 4> = (0..6).collect();
+        list.push_front(-1);
+        assert_eq!(check_nodes(&list), &[1, 4, 2]);
+        let mut cursor = list.cursor_mut();
+        cursor.move_prev();
+        cursor.move_prev();
+        assert_eq!(cursor.remove_current(), Some(4));
+        assert_eq!(cursor.current(), Some(&mut 5));
+        assert_eq!(cursor.index(), Some(5));
+        assert_eq!(check_nodes(&list), &[1, 4, 1]);
+    }
+
+    #[test]
+    fn test_cursor() {
+        let mut list: UnrolledList<i32, 3> = (0..5).collect();
+        let mut cursor = list.cursor_mut();
+        assert_eq!(cursor.current(), None);
+        assert_eq!(cursor.peek_next(), Some(&mut 0));
+        assert_eq!(cursor.peek_prev(), Some(&mut 4));
+        cursor.move_prev();
+        assert_eq!(cursor.index(), Some(4));
+        cursor.move_next();
+        assert_eq!(cursor.index(), None);
+        cursor.move_next();
+        cursor.move_next();
+        cursor.move_next();
+        cursor.move_next();
+        assert_eq!(cursor.current(), Some(&mut 3));
+        assert_eq!(cursor.peek_prev(), Some(&mut 2));
+        assert_eq!(cursor.peek_next(), Some(&mut 4));
+
+        cursor.insert_after(30);
+        cursor.insert_before(20);
+        assert_eq!(cursor.current(), Some(&mut 3));
+        assert_eq!(cursor.index(), Some(4));
+        assert_eq!(cursor.peek_next(), Some(&mut 30));
+
+        // Ghost inserts go to the ends
+        cursor.move_next();
+        cursor.move_next();
+        cursor.move_next();
+        assert_eq!(cursor.current(), None);
+        cursor.insert_before(100);
+        cursor.insert_after(-100);
+        assert_eq!(to_vec(&list), &[-100, 0, 1, 2, 20, 3, 30, 4, 100]);
+        check_nodes(&list);
+    }
+
+    #[test]
+    fn test_cursor_split_splice() {
+        let mut list: UnrolledList<i32, 4> = (0..10).collect();
+        let mut cursor = list.cursor_mut();
+        for _ in 0..6 {
+            cursor.move_next();
+        }
+        assert_eq!(cursor.current(), Some(&mut 5));
+
+        let front = cursor.split_before();
+        assert_eq!(cursor.index(), Some(0));
+        assert_eq!(cursor.current(), Some(&mut 5));
+        let back = cursor.split_after();
+        assert_eq!(cursor.current(), Some(&mut 5));
+        assert_eq!(to_vec(&front), &[0, 1, 2, 3, 4]);
+        assert_eq!(to_vec(&back), &[6, 7, 8, 9]);
+        check_nodes(&front);
+        check_nodes(&back);
+
+        cursor.splice_after(back);
+        cursor.splice_before(front);
+        assert_eq!(cursor.index(), Some(5));
+        assert_eq!(cursor.current(), Some(&mut 5));
+        check_nodes(&list);
+        assert_eq!(to_vec(&list), (0..10).collect::<Vec<_>>());
+
+        // Splicing into the middle of a node
+        let mut cursor = list.cursor_mut();
+        cursor.move_next();
+        cursor.move_next();
+        cursor.splice_before((10..13).collect());
+        cursor.splice_after((20..22).collect());
+        assert_eq!(cursor.current(), Some(&mut 1));
+        assert_eq!(cursor.index(), Some(4));
+        cursor.move_next();
+        assert_eq!(cursor.current(), Some(&mut 20));
+        check_nodes(&list);
+        assert_eq!(
+            to_vec(&list),
+            &[0, 10, 11, 12, 1, 20, 21, 2, 3, 4, 5, 6, 7, 8, 9]
+        );
+
+        // Ghost splits take everything
+        let mut cursor = list.cursor_mut();
+        let all = cursor.split_after();
+        assert!(list.is_empty());
+        assert_eq!(all.len(), 15);
+    }
+
+    #[test]
+    fn test_split_off_append() {
+        for at in 0..=11 {
+            let mut list: UnrolledList<i32, 4> = (0..11).collect();
+            let tail = list.split_off(at);
+            check_nodes(&list);
+            check_nodes(&tail);
+            assert_eq!(to_vec(&list), (0..at as i32).collect::<Vec<_>>());
+            assert_eq!(to_vec(&tail), (at as i32..11).collect::<Vec<_>>());
+
+            let mut tail = tail;
+            list.append(&mut tail);
+            assert!(tail.is_empty());
+            check_nodes(&list);
+            assert_eq!(to_vec(&list), (0..11).collect::<Vec<_>>());
+        }
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_split_off_out_of_bounds() {
+        let mut list: UnrolledList<i32, 4> = (0..3).collect();
+        list.split_off(4);
+    }
+
+    #[test]
+    fn test_random_ops() {
+        // Do the same thing to a Vec and to the list, and compare
+        let mut seed = 7;
+        let mut list = UnrolledList::<u64, 5>::new();
+        let mut expected = Vec::new();
+        let mut cursor_at = 0;
+        for _ in 0..2000 {
+            let op = pseudo_random(&mut seed) % 8;
+            let value = pseudo_random(&mut seed);
+            let mut cursor = list.cursor_mut();
+            for _ in 0..=cursor_at.min(expected.len()) {
+                cursor.move_next();
+            }
+            let index = cursor.index();
+            match (op, index) {
+                (0, _) => {
+                    list.push_back(value);
+                    expected.push(value);
+                }
+                (1, _) => {
+                    list.push_front(value);
+                    expected.insert(0, value);
+                }
+                (2, _) => assert_eq!(
+                    list.pop_front(),
+                    (!expected.is_empty()).then(|| expected.remove(0))
+                ),
+                (3, _) => assert_eq!(list.pop_back(), expected.pop()),
+                (4 | 5, Some(index)) => {
+                    cursor.insert_before(value);
+                    expected.insert(index, value);
+                    assert_eq!(cursor.index(), Some(index + 1));
+                }
+                (6, Some(index)) => {
+                    cursor.insert_after(value);
+                    expected.insert(index + 1, value);
+                }
+                (7, Some(index)) => {
+                    assert_eq!(cursor.remove_current(), Some(expected.remove(index)));
+                    assert_eq!(cursor.current().copied(), expected.get(index).copied());
+                }
+                _ => {}
+            }
+            cursor_at = value as usize % 64;
+            check_nodes(&list);
+            assert_eq!(list.len(), expected.len());
+        }
+        assert_eq!(to_vec(&list), expected);
+        assert!(list.iter().rev().eq(expected.iter().rev()));
+    }
+
+    #[test]
+    fn test_drop() {
+        let counter = Rc::new(());
+        let mut list = UnrolledList::<_, 4>::new();
+        for _ in 0..10 {
+            list.push_back(counter.clone());
+        }
+        let mut cursor = list.cursor_mut();
+        cursor.move_next();
+        cursor.move_next();
+        drop(cursor.remove_current());
+        let tail = list.split_off(5);
+        assert_eq!(Rc::strong_count(&counter), 10);
+        drop(tail);
+        assert_eq!(Rc::strong_count(&counter), 6);
+        drop(list);
+        assert_eq!(Rc::strong_count(&counter), 1);
+    }
+
+    #[test]
+    fn test_traits() {
+        let list: UnrolledList<i32, 2> = (0..5).collect();
+        let copy = list.clone();
+        assert_eq!(list, copy);
+        check_nodes(&copy);
+        assert_eq!(format!("{list:?}"), "[0, 1, 2, 3, 4]");
+        let bigger: UnrolledList<i32, 2> = (1..3).collect();
+        assert!(list < bigger);
+        let mut extended = UnrolledList::<i32, 2>::default();
+        extended.extend([0, 1, 2, 3, 4]);
+        assert_eq!(extended, list);
+    }
+
+    #[allow(dead_code)]
+    fn assert_properties() {
+        fn is_send<T: Send>() {}
+        fn is_sync<T: Sync>() {}
+
+        is_send::<UnrolledList<i32, 4>>();
+        is_sync::<UnrolledList<i32, 4>>();
+        is_send::<Iter<i32, 4>>();
+        is_sync::<Iter<i32, 4>>();
+        is_send::<IterMut<i32, 4>>();
+        is_sync::<IterMut<i32, 4>>();
+
+        fn list_covariant<'a, T>(x: UnrolledList<&'static T, 4>) -> UnrolledList<&'a T, 4> {
+            x
+        }
+        fn iter_covariant<'i, 'a, T>(x: Iter<'i, &'static T, 4>) -> Iter<'i, &'a T, 4> {
+            x
+        }
+    }
+}